@@ -0,0 +1,152 @@
+/* In-process topic-based publish/subscribe */
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub topic: String,
+    pub payload: String,
+}
+
+/// Cloneable handle to the server's event bus. Every clone publishes to and
+/// subscribes on the same topics, so a handler can notify SSE streams or
+/// background jobs without wiring up its own channels.
+///
+/// ```no_run
+/// # use schnell::http::HttpResponse;
+/// # use schnell::routing::RouteBuilder;
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// let bus = server.bus();
+/// server.post("/todos", move |req| {
+///     bus.publish("todos", &req.body);
+///     HttpResponse::created()
+/// });
+/// ```
+#[derive(Clone, Default)]
+pub struct Bus {
+    topics: Arc<Mutex<HashMap<String, Vec<Sender<Event>>>>>,
+}
+
+impl Bus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delivers `payload` to every current subscriber of `topic` and returns how
+    /// many received it. Subscriptions that have been dropped are pruned.
+    pub fn publish(&self, topic: &str, payload: &str) -> usize {
+        let mut topics = self.topics.lock().unwrap();
+        let Some(subscribers) = topics.get_mut(topic) else {
+            return 0;
+        };
+
+        let event = Event {
+            topic: topic.to_string(),
+            payload: payload.to_string(),
+        };
+        subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+
+        let delivered = subscribers.len();
+        if delivered == 0 {
+            topics.remove(topic);
+        }
+        delivered
+    }
+
+    #[cfg(feature = "json")]
+    pub fn publish_json<T: serde::Serialize>(
+        &self,
+        topic: &str,
+        payload: &T,
+    ) -> serde_json::Result<usize> {
+        Ok(self.publish(topic, &serde_json::to_string(payload)?))
+    }
+
+    /// Receives every event published on `topic` from now on, until the
+    /// subscription is dropped.
+    pub fn subscribe(&self, topic: &str) -> Subscription {
+        let (sender, receiver) = mpsc::channel();
+        self.topics
+            .lock()
+            .unwrap()
+            .entry(topic.to_string())
+            .or_default()
+            .push(sender);
+        Subscription { receiver }
+    }
+
+    pub fn subscriber_count(&self, topic: &str) -> usize {
+        self.topics.lock().unwrap().get(topic).map_or(0, Vec::len)
+    }
+}
+
+pub struct Subscription {
+    receiver: Receiver<Event>,
+}
+
+impl Subscription {
+    /// Blocks until the next event arrives.
+    pub fn recv(&self) -> Option<Event> {
+        self.receiver.recv().ok()
+    }
+
+    /// Waits at most `timeout` for the next event, e.g. so an SSE loop can send
+    /// keep-alive comments in between.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Event> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    pub fn try_recv(&self) -> Option<Event> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_reaches_all_subscribers_of_topic() {
+        let bus = Bus::new();
+        let first = bus.subscribe("todos");
+        let second = bus.subscribe("todos");
+        let other = bus.subscribe("users");
+
+        assert_eq!(bus.publish("todos", "created"), 2);
+        assert_eq!(first.try_recv().unwrap().payload, "created");
+        assert_eq!(second.try_recv().unwrap().payload, "created");
+        assert!(other.try_recv().is_none());
+    }
+
+    #[test]
+    fn test_dropped_subscriptions_are_pruned() {
+        let bus = Bus::new();
+        let kept = bus.subscribe("todos");
+        drop(bus.subscribe("todos"));
+
+        assert_eq!(bus.publish("todos", "created"), 1);
+        assert_eq!(bus.subscriber_count("todos"), 1);
+        drop(kept);
+        assert_eq!(bus.publish("todos", "deleted"), 0);
+        assert_eq!(bus.subscriber_count("todos"), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_publish_across_threads() {
+        let bus = Bus::new();
+        let subscription = bus.subscribe("jobs");
+
+        let publisher = bus.clone();
+        std::thread::spawn(move || publisher.publish_json("jobs", &vec![1, 2]).unwrap())
+            .join()
+            .unwrap();
+
+        let event = subscription.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(event.topic, "jobs");
+        assert_eq!(event.payload, "[1,2]");
+    }
+}
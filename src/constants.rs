@@ -1 +1,14 @@
 pub const HTTP_VERSION: &str = "HTTP/1.1";
+pub const DEFAULT_OUTPUT_BUFFER_SIZE: usize = 8 * 1024;
+pub const DEFAULT_MAX_REQUEST_LINE: usize = 8 * 1024;
+pub const DEFAULT_MAX_URI_LENGTH: usize = 8 * 1024;
+pub const DEFAULT_ACCEPT_QUEUE_PER_WORKER: usize = 16;
+pub const DEFAULT_MAX_TIMED_OUT_HANDLERS: usize = 64;
+pub const DEFAULT_TLS_HANDSHAKE_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_SUBSYSTEM_STOP_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+pub const DEFAULT_MAX_HEADER_SIZE: usize = 16 * 1024;
+pub const DEFAULT_MAX_HEADERS: usize = 100;
+pub const DEFAULT_COMPRESSION_MIN_SIZE: usize = 1024;
+pub const DEFAULT_SSE_PING_INTERVAL_SECS: u64 = 15;
+pub const MAX_FORM_DEPTH: usize = 5;
@@ -0,0 +1,95 @@
+/* Typed request body parsing */
+use std::fmt;
+
+use crate::http::HttpResponse;
+
+#[derive(Debug)]
+pub enum BodyError {
+    /// The request's `Content-Type` isn't the expected media type.
+    UnsupportedMediaType(&'static str),
+    /// The body doesn't deserialize into the requested type.
+    #[cfg(feature = "json")]
+    InvalidJson(serde_json::Error),
+    /// The urlencoded form doesn't deserialize into the requested type.
+    #[cfg(feature = "forms")]
+    InvalidForm(serde_qs::Error),
+    /// The multipart body is malformed; carries what was wrong with it.
+    InvalidMultipart(&'static str),
+}
+
+impl BodyError {
+    /// 415 for a wrong `Content-Type`, 400 for a malformed body.
+    pub fn into_response(self) -> HttpResponse {
+        match self {
+            BodyError::UnsupportedMediaType(_) => HttpResponse::new(415).text(&self.to_string()),
+            _ => HttpResponse::bad_request().text(&self.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BodyError::UnsupportedMediaType(expected) => {
+                write!(f, "Expected Content-Type: {}", expected)
+            }
+            #[cfg(feature = "json")]
+            BodyError::InvalidJson(e) => write!(f, "Invalid JSON body: {}", e),
+            #[cfg(feature = "forms")]
+            BodyError::InvalidForm(e) => write!(f, "Invalid form body: {}", e),
+            BodyError::InvalidMultipart(reason) => write!(f, "Invalid multipart body: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for BodyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BodyError::UnsupportedMediaType(_) | BodyError::InvalidMultipart(_) => None,
+            #[cfg(feature = "json")]
+            BodyError::InvalidJson(e) => Some(e),
+            #[cfg(feature = "forms")]
+            BodyError::InvalidForm(e) => Some(e),
+        }
+    }
+}
+
+impl From<BodyError> for HttpResponse {
+    fn from(error: BodyError) -> Self {
+        error.into_response()
+    }
+}
+
+/// Whether `content_type` is `application/json` or a `+json` media type,
+/// ignoring parameters such as `charset`.
+pub fn is_json_content_type(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    essence == "application/json"
+        || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+/// `body` without a leading UTF-8 byte order mark, which some Windows clients
+/// and editors prepend and which JSON and form parsers reject.
+#[cfg(any(feature = "json", feature = "forms"))]
+pub(crate) fn strip_bom(body: &[u8]) -> &[u8] {
+    body.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_json_content_type() {
+        assert!(is_json_content_type("application/json"));
+        assert!(is_json_content_type("Application/JSON; charset=utf-8"));
+        assert!(is_json_content_type("application/problem+json"));
+        assert!(!is_json_content_type("text/json+html"));
+        assert!(!is_json_content_type("text/plain"));
+    }
+}
@@ -0,0 +1,207 @@
+/* Conditional requests (RFC 7232) */
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+
+use sha2::{Digest, Sha256};
+
+use crate::http::response::strong_etag;
+use crate::http::{HttpMethod, HttpResponse, Request};
+
+/// Turns a successful GET/HEAD response into `304 Not Modified` when the
+/// request's `If-None-Match` matches the response's `ETag`.
+pub fn evaluate(request: &Request, response: HttpResponse) -> HttpResponse {
+    if !matches!(request.method, HttpMethod::GET | HttpMethod::HEAD)
+        || !(200..300).contains(&response.status_code)
+    {
+        return response;
+    }
+
+    let (Some(if_none_match), Some(etag)) = (
+        request.headers.get("if-none-match"),
+        response.get_header("ETag"),
+    ) else {
+        return response;
+    };
+
+    if !etag_matches(if_none_match, etag) {
+        return response;
+    }
+
+    let mut not_modified = response.status(304);
+    not_modified.body.clear();
+    not_modified
+}
+
+/// Weak comparison of an `If-None-Match` list against an entity tag.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let etag = etag.trim().trim_start_matches("W/");
+    if_none_match
+        .split(',')
+        .map(|candidate| candidate.trim())
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+/// ETags of streamed responses, computed while they were sent. See
+/// [`HttpResponse::stream_with_etag`].
+#[derive(Clone, Default)]
+pub struct EtagCache {
+    etags: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl EtagCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.etags.lock().unwrap().get(key).cloned()
+    }
+
+    /// Forgets `key`, e.g. once the underlying resource is deleted.
+    pub fn remove(&self, key: &str) {
+        self.etags.lock().unwrap().remove(key);
+    }
+
+    /// Wraps `reader` so that, once it has been read to the end, the ETag of
+    /// everything it produced is stored under `key`. Streams cut short (by a
+    /// read error or the client going away) store nothing.
+    pub fn digest<R: Read + Send + 'static>(
+        &self,
+        key: &str,
+        reader: R,
+    ) -> impl Read + Send + use<R> {
+        DigestReader {
+            reader,
+            hasher: Some(Sha256::new()),
+            key: key.to_string(),
+            cache: self.clone(),
+        }
+    }
+}
+
+struct DigestReader<R> {
+    reader: R,
+    hasher: Option<Sha256>,
+    key: String,
+    cache: EtagCache,
+}
+
+impl<R: Read> Read for DigestReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        if read > 0 {
+            if let Some(hasher) = &mut self.hasher {
+                hasher.update(&buf[..read]);
+            }
+        } else if let Some(hasher) = self.hasher.take() {
+            let etag = strong_etag(&hasher.finalize());
+            self.cache
+                .etags
+                .lock()
+                .unwrap()
+                .insert(std::mem::take(&mut self.key), etag);
+        }
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn request(raw: &str) -> Request {
+        let mut buffer = BufReader::new(raw.as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        assert!(etag_matches("\"abc\"", "\"abc\""));
+        assert!(etag_matches("\"xyz\", \"abc\"", "\"abc\""));
+        assert!(etag_matches("W/\"abc\"", "\"abc\""));
+        assert!(etag_matches("\"abc\"", "W/\"abc\""));
+        assert!(etag_matches("*", "\"abc\""));
+        assert!(!etag_matches("\"xyz\"", "\"abc\""));
+    }
+
+    #[test]
+    fn test_evaluate_returns_304_on_match() {
+        let response = HttpResponse::ok().text("[1,2,3]").with_etag();
+        let etag = response.get_header("ETag").unwrap().to_string();
+
+        let req = request(&format!(
+            "GET /items HTTP/1.1\r\nIf-None-Match: {}\r\n\r\n",
+            etag
+        ));
+        let response = evaluate(&req, response);
+
+        assert_eq!(response.status_code, 304);
+        assert!(response.body.is_empty());
+        assert_eq!(response.get_header("ETag"), Some(etag.as_str()));
+    }
+
+    #[test]
+    fn test_evaluate_passes_through() {
+        let fresh = || HttpResponse::ok().text("[1,2,3]").with_etag();
+
+        let req = request("GET /items HTTP/1.1\r\nIf-None-Match: \"stale\"\r\n\r\n");
+        assert_eq!(evaluate(&req, fresh()).status_code, 200);
+
+        let req = request("GET /items HTTP/1.1\r\n\r\n");
+        assert_eq!(evaluate(&req, fresh()).status_code, 200);
+
+        let req = request("POST /items HTTP/1.1\r\nIf-None-Match: *\r\n\r\n");
+        assert_eq!(evaluate(&req, fresh()).status_code, 200);
+
+        let req = request("GET /items HTTP/1.1\r\nIf-None-Match: *\r\n\r\n");
+        let error = HttpResponse::not_found().with_etag();
+        assert_eq!(evaluate(&req, error).status_code, 404);
+    }
+
+    #[test]
+    fn test_streamed_etag_is_cached() {
+        let cache = EtagCache::new();
+        let export = || {
+            HttpResponse::ok().stream_with_etag(
+                io::Cursor::new(b"id,name\n1,a\n"),
+                &cache,
+                "export",
+            )
+        };
+
+        let first = export();
+        assert_eq!(first.get_header("ETag"), None);
+        crate::http::response::write_response(&mut Vec::new(), first).unwrap();
+
+        let etag = cache.get("export").unwrap();
+        assert_eq!(
+            etag,
+            HttpResponse::ok()
+                .text("id,name\n1,a\n")
+                .with_etag()
+                .get_header("ETag")
+                .unwrap()
+        );
+
+        let req = request(&format!(
+            "GET /export HTTP/1.1\r\nIf-None-Match: {}\r\n\r\n",
+            etag
+        ));
+        let response = evaluate(&req, export());
+        assert_eq!(response.status_code, 304);
+        let mut output = Vec::new();
+        crate::http::response::write_response(&mut output, response).unwrap();
+        assert!(String::from_utf8(output).unwrap().ends_with("\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_partial_stream_stores_nothing() {
+        let cache = EtagCache::new();
+        let mut reader = cache.digest("export", io::Cursor::new(vec![0; 64]));
+        reader.read_exact(&mut [0; 16]).unwrap();
+        drop(reader);
+        assert_eq!(cache.get("export"), None);
+    }
+}
@@ -0,0 +1,275 @@
+/* Set-Cookie values */
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use crate::keyring::Keyring;
+use crate::utils::{base64url_decode, base64url_encode, http_date};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    /// Browsers only accept this together with `secure(true)`.
+    None,
+}
+
+impl SameSite {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// A cookie to send with `HttpResponse::cookie`. Without `max_age` or
+/// `expires` it is a session cookie, dropped when the browser closes.
+///
+/// Characters a cookie may not carry (controls, whitespace, `;`, `,`, `"` and
+/// `\` in the value, plus separators in the name) are percent-encoded, so a
+/// value taken from user input can't add attributes or break the header.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use schnell::http::{Cookie, HttpResponse, SameSite};
+/// # let id = "5f2b";
+/// let response = HttpResponse::ok().cookie(
+///     Cookie::new("session", &id)
+///         .path("/")
+///         .same_site(SameSite::Lax)
+///         .http_only(true)
+///         .secure(true)
+///         .max_age(Duration::from_secs(60 * 60 * 24 * 30)),
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    max_age: Option<Duration>,
+    expires: Option<SystemTime>,
+    same_site: Option<SameSite>,
+    http_only: bool,
+    secure: bool,
+}
+
+impl Cookie {
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: encode(name, is_token),
+            value: encode(value, is_cookie_octet),
+            path: None,
+            domain: None,
+            max_age: None,
+            expires: None,
+            same_site: None,
+            http_only: false,
+            secure: false,
+        }
+    }
+
+    /// A cookie that tells the browser to delete `name` right away. `path` and
+    /// `domain` must match the ones the cookie was set with.
+    pub fn removal(name: &str) -> Self {
+        Self::new(name, "").max_age(Duration::ZERO)
+    }
+
+    pub fn path(self, path: &str) -> Self {
+        let mut cookie = self;
+        cookie.path = Some(encode(path, is_attribute_octet));
+        cookie
+    }
+
+    pub fn domain(self, domain: &str) -> Self {
+        let mut cookie = self;
+        cookie.domain = Some(encode(domain, is_attribute_octet));
+        cookie
+    }
+
+    pub fn max_age(self, max_age: Duration) -> Self {
+        let mut cookie = self;
+        cookie.max_age = Some(max_age);
+        cookie
+    }
+
+    pub fn expires(self, expires: SystemTime) -> Self {
+        let mut cookie = self;
+        cookie.expires = Some(expires);
+        cookie
+    }
+
+    pub fn same_site(self, same_site: SameSite) -> Self {
+        let mut cookie = self;
+        cookie.same_site = Some(same_site);
+        cookie
+    }
+
+    pub fn http_only(self, http_only: bool) -> Self {
+        let mut cookie = self;
+        cookie.http_only = http_only;
+        cookie
+    }
+
+    pub fn secure(self, secure: bool) -> Self {
+        let mut cookie = self;
+        cookie.secure = secure;
+        cookie
+    }
+
+    /// Appends an HMAC of the name and value, so the client can read the value
+    /// but any change to it is rejected by `Request::signed_cookie`.
+    pub fn signed(self, keyring: &Keyring) -> Self {
+        let mut cookie = self;
+        let signature = keyring.sign(&signed_payload(&cookie.name, &cookie.value));
+        cookie.value = format!("{}.{}", cookie.value, base64url_encode(&signature));
+        cookie
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// The value of a cookie set with `Cookie::signed`, if its signature is valid.
+pub(crate) fn verify_signed<'a>(name: &str, value: &'a str, keyring: &Keyring) -> Option<&'a str> {
+    let (value, signature) = value.rsplit_once('.')?;
+    let signature = base64url_decode(signature)?;
+    keyring
+        .verify(&signed_payload(name, value), &signature)
+        .then_some(value)
+}
+
+/// Binds the signature to the name, so a signed value can't be replayed under
+/// another cookie.
+fn signed_payload(name: &str, value: &str) -> Vec<u8> {
+    format!("{}={}", name, value).into_bytes()
+}
+
+/// `cookie-octet` from RFC 6265 §4.1.1, less `%` so the encoding is reversible.
+fn is_cookie_octet(byte: u8) -> bool {
+    matches!(byte, b'!'..=b'~') && !matches!(byte, b'"' | b',' | b';' | b'\\' | b'%')
+}
+
+/// `token` from RFC 9110 §5.6.2, less `%`.
+fn is_token(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$&'*+-.^_`|~".contains(&byte)
+}
+
+/// Anything but controls and `;` (RFC 6265 §4.1.1 `av-octet`).
+fn is_attribute_octet(byte: u8) -> bool {
+    matches!(byte, b' '..=b'~') && byte != b';'
+}
+
+fn encode(input: &str, allowed: fn(u8) -> bool) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if allowed(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// The `Set-Cookie` header value.
+impl fmt::Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", http_date(expires))?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site.as_str())?;
+        }
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+        if self.secure {
+            f.write_str("; Secure")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_all_attributes() {
+        let cookie = Cookie::new("session", "abc123")
+            .path("/")
+            .domain("example.com")
+            .max_age(Duration::from_secs(3600))
+            .expires(UNIX_EPOCH + Duration::from_secs(1_893_553_445))
+            .same_site(SameSite::Lax)
+            .http_only(true)
+            .secure(true);
+
+        assert_eq!(
+            cookie.to_string(),
+            "session=abc123; Path=/; Domain=example.com; Max-Age=3600; \
+             Expires=Wed, 02 Jan 2030 03:04:05 GMT; SameSite=Lax; HttpOnly; Secure"
+        );
+    }
+
+    #[test]
+    fn test_signed_cookies() {
+        let keyring = Keyring::new("s3cr3t");
+        let cookie = Cookie::new("uid", "42").signed(&keyring);
+        let (value, _) = cookie.value().split_once('.').unwrap();
+        assert_eq!(value, "42");
+
+        assert_eq!(verify_signed("uid", cookie.value(), &keyring), Some("42"));
+        assert_eq!(verify_signed("admin", cookie.value(), &keyring), None);
+        assert_eq!(verify_signed("uid", "42", &keyring), None);
+
+        let tampered = cookie.value().replacen("42", "43", 1);
+        assert_eq!(verify_signed("uid", &tampered, &keyring), None);
+        assert_eq!(
+            verify_signed("uid", cookie.value(), &Keyring::new("other")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_session_and_removal_cookies() {
+        assert_eq!(Cookie::new("theme", "dark").to_string(), "theme=dark");
+        assert_eq!(
+            Cookie::removal("session").path("/").to_string(),
+            "session=; Path=/; Max-Age=0"
+        );
+    }
+
+    #[test]
+    fn test_unsafe_characters_are_encoded() {
+        let cookie = Cookie::new("we ird;", "x; Domain=evil.com\r\nSet-Cookie: a=b")
+            .path("/a;Secure")
+            .domain("example.com\r\n");
+
+        assert_eq!(
+            cookie.to_string(),
+            "we%20ird%3B=x%3B%20Domain=evil.com%0D%0ASet-Cookie:%20a=b; \
+             Path=/a%3BSecure; Domain=example.com%0D%0A"
+        );
+        assert_eq!(Cookie::new("n", "100%,\"ok\"").value(), "100%25%2C%22ok%22");
+    }
+}
@@ -0,0 +1,175 @@
+/* Uniform JSON envelopes for API responses */
+use log::error;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::http::HttpResponse;
+
+/// Names of the top-level envelope fields.
+#[derive(Debug, Clone)]
+pub struct EnvelopeFields {
+    pub data: String,
+    pub meta: String,
+    pub error: String,
+}
+
+impl Default for EnvelopeFields {
+    fn default() -> Self {
+        Self {
+            data: String::from("data"),
+            meta: String::from("meta"),
+            error: String::from("error"),
+        }
+    }
+}
+
+/// Builds `{ "data": ..., "meta": ... }` success envelopes and
+/// `{ "error": { "code": ..., "message": ... } }` error envelopes.
+///
+/// ```no_run
+/// # use schnell::http::ApiResponse;
+/// # use schnell::routing::RouteBuilder;
+/// # use schnell::server::Server;
+/// # use serde_json::json;
+/// # let users = || vec!["alice", "bob"];
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// server.get("/users", move |_| {
+///     ApiResponse::ok(users()).meta(json!({ "page": 1 }))
+/// });
+/// ```
+#[derive(Debug)]
+pub struct ApiResponse {
+    status_code: u16,
+    data: Option<Value>,
+    meta: Option<Value>,
+    error: Option<Value>,
+    fields: EnvelopeFields,
+    serialize_error: Option<serde_json::Error>,
+}
+
+impl ApiResponse {
+    pub fn ok<T: Serialize>(data: T) -> Self {
+        Self::success(200, data)
+    }
+
+    pub fn created<T: Serialize>(data: T) -> Self {
+        Self::success(201, data)
+    }
+
+    pub fn success<T: Serialize>(status_code: u16, data: T) -> Self {
+        let mut response = Self::empty(status_code);
+        match serde_json::to_value(data) {
+            Ok(data) => response.data = Some(data),
+            Err(e) => response.serialize_error = Some(e),
+        }
+        response
+    }
+
+    /// An error envelope with a machine-readable `code` and a human-readable `message`.
+    pub fn error(status_code: u16, code: &str, message: &str) -> Self {
+        let mut response = Self::empty(status_code);
+        response.error = Some(serde_json::json!({ "code": code, "message": message }));
+        response
+    }
+
+    pub fn meta<T: Serialize>(self, meta: T) -> Self {
+        let mut response = self;
+        match serde_json::to_value(meta) {
+            Ok(meta) => response.meta = Some(meta),
+            Err(e) => response.serialize_error = Some(e),
+        }
+        response
+    }
+
+    pub fn fields(self, fields: EnvelopeFields) -> Self {
+        let mut response = self;
+        response.fields = fields;
+        response
+    }
+
+    pub fn into_response(self) -> HttpResponse {
+        if let Some(e) = self.serialize_error {
+            error!("Failed to serialize API response: {}", e);
+            return HttpResponse::internal_server_error();
+        }
+
+        let mut envelope = Map::new();
+        if let Some(data) = self.data {
+            envelope.insert(self.fields.data, data);
+        }
+        if let Some(meta) = self.meta {
+            envelope.insert(self.fields.meta, meta);
+        }
+        if let Some(error) = self.error {
+            envelope.insert(self.fields.error, error);
+        }
+
+        HttpResponse::new(self.status_code).json(Value::Object(envelope))
+    }
+
+    fn empty(status_code: u16) -> Self {
+        Self {
+            status_code,
+            data: None,
+            meta: None,
+            error: None,
+            fields: EnvelopeFields::default(),
+            serialize_error: None,
+        }
+    }
+}
+
+impl From<ApiResponse> for HttpResponse {
+    fn from(response: ApiResponse) -> Self {
+        response.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn body(response: HttpResponse) -> Value {
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    #[test]
+    fn test_success_envelope() {
+        let response = ApiResponse::ok(vec!["a", "b"])
+            .meta(json!({ "total": 2 }))
+            .into_response();
+
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.content_type, "application/json");
+        assert_eq!(
+            body(response),
+            json!({ "data": ["a", "b"], "meta": { "total": 2 } })
+        );
+    }
+
+    #[test]
+    fn test_error_envelope() {
+        let response: HttpResponse =
+            ApiResponse::error(404, "user_not_found", "No user with id 7").into();
+
+        assert_eq!(response.status_code, 404);
+        assert_eq!(
+            body(response),
+            json!({ "error": { "code": "user_not_found", "message": "No user with id 7" } })
+        );
+    }
+
+    #[test]
+    fn test_custom_field_names() {
+        let response = ApiResponse::created(json!({ "id": 1 }))
+            .fields(EnvelopeFields {
+                data: String::from("result"),
+                ..EnvelopeFields::default()
+            })
+            .into_response();
+
+        assert_eq!(response.status_code, 201);
+        assert_eq!(body(response), json!({ "result": { "id": 1 } }));
+    }
+}
@@ -0,0 +1,197 @@
+/* Handler errors that map onto HTTP statuses */
+use std::fmt;
+use std::io;
+
+use log::error;
+
+use crate::http::body::BodyError;
+use crate::http::{HttpResponse, IntoResponse};
+use crate::utils::get_status_text;
+
+/// An error a handler can return in place of a response.
+///
+/// Variants carrying a message answer with a JSON error envelope
+/// (`{ "error": { "code": "conflict", "message": ... } }`, or the bare message
+/// as text without the `json` feature); the others answer
+/// with a bare status, so registered error pages still apply. `Internal` is
+/// logged and answered with a plain 500, keeping its source out of the response.
+///
+/// ```no_run
+/// # use std::io;
+/// # use schnell::http::{HttpError, HttpResponse};
+/// # use schnell::routing::RouteBuilder;
+/// # use schnell::server::Server;
+/// # struct Db;
+/// # impl Db {
+/// #     fn find(&self, _: u32) -> io::Result<Option<String>> { Ok(None) }
+/// # }
+/// # let db = Db;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// server.get("/users/:id", move |req| -> Result<HttpResponse, HttpError> {
+///     let id: u32 = req.params["id"].parse().map_err(|_| HttpError::BadRequest("id must be a number".into()))?;
+///     let user = db.find(id)?.ok_or(HttpError::NotFound)?;
+///     Ok(HttpResponse::ok().text(&user))
+/// });
+/// ```
+#[derive(Debug)]
+pub enum HttpError {
+    BadRequest(String),
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict(String),
+    UnsupportedMediaType(String),
+    UnprocessableEntity(String),
+    TooManyRequests,
+    Internal(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl HttpError {
+    pub fn status_code(&self) -> u16 {
+        match self {
+            HttpError::BadRequest(_) => 400,
+            HttpError::Unauthorized => 401,
+            HttpError::Forbidden => 403,
+            HttpError::NotFound => 404,
+            HttpError::Conflict(_) => 409,
+            HttpError::UnsupportedMediaType(_) => 415,
+            HttpError::UnprocessableEntity(_) => 422,
+            HttpError::TooManyRequests => 429,
+            HttpError::Internal(_) => 500,
+        }
+    }
+
+    /// Machine-readable code for the error envelope, e.g. `unprocessable_entity`.
+    pub fn code(&self) -> String {
+        get_status_text(self.status_code())
+            .to_ascii_lowercase()
+            .replace(' ', "_")
+    }
+
+    pub fn into_response(self) -> HttpResponse {
+        let (status_code, code) = (self.status_code(), self.code());
+        match self {
+            HttpError::BadRequest(message)
+            | HttpError::Conflict(message)
+            | HttpError::UnsupportedMediaType(message)
+            | HttpError::UnprocessableEntity(message) => {
+                message_response(status_code, &code, &message)
+            }
+            HttpError::Internal(source) => {
+                error!("Error handling request: {}", source);
+                HttpResponse::internal_server_error()
+            }
+            _ => HttpResponse::new(status_code),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+fn message_response(status_code: u16, code: &str, message: &str) -> HttpResponse {
+    crate::http::ApiResponse::error(status_code, code, message).into()
+}
+
+#[cfg(not(feature = "json"))]
+fn message_response(status_code: u16, _code: &str, message: &str) -> HttpResponse {
+    HttpResponse::new(status_code).text(message)
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::BadRequest(message)
+            | HttpError::Conflict(message)
+            | HttpError::UnsupportedMediaType(message)
+            | HttpError::UnprocessableEntity(message) => write!(f, "{}", message),
+            HttpError::Internal(source) => write!(f, "Internal error: {}", source),
+            _ => write!(f, "{}", get_status_text(self.status_code())),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HttpError::Internal(source) => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for HttpError {
+    fn from(error: io::Error) -> Self {
+        HttpError::Internal(Box::new(error))
+    }
+}
+
+impl From<BodyError> for HttpError {
+    fn from(error: BodyError) -> Self {
+        match error {
+            BodyError::UnsupportedMediaType(_) => {
+                HttpError::UnsupportedMediaType(error.to_string())
+            }
+            _ => HttpError::BadRequest(error.to_string()),
+        }
+    }
+}
+
+impl From<HttpError> for HttpResponse {
+    fn from(error: HttpError) -> Self {
+        error.into_response()
+    }
+}
+
+impl IntoResponse for HttpError {
+    fn into_response(self) -> io::Result<HttpResponse> {
+        Ok(HttpError::into_response(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_mapping() {
+        let response = HttpError::Conflict("Email already registered".into()).into_response();
+        assert_eq!(response.status_code, 409);
+        #[cfg(feature = "json")]
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&response.body).unwrap(),
+            serde_json::json!({ "error": { "code": "conflict", "message": "Email already registered" } })
+        );
+        #[cfg(not(feature = "json"))]
+        assert_eq!(response.body, "Email already registered");
+
+        let response = HttpError::NotFound.into_response();
+        assert_eq!((response.status_code, response.body.as_str()), (404, ""));
+
+        let error = HttpError::from(io::Error::other("connection refused"));
+        assert_eq!(error.code(), "internal_server_error");
+        let response = error.into_response();
+        assert_eq!(response.status_code, 500);
+        assert!(!response.body.contains("connection refused"));
+    }
+
+    #[test]
+    fn test_handler_result() {
+        let handler = |id: &str| -> Result<HttpResponse, HttpError> {
+            let id: u32 = id
+                .parse()
+                .map_err(|_| HttpError::BadRequest("id must be a number".into()))?;
+            match id {
+                7 => Ok(HttpResponse::ok()),
+                _ => Err(HttpError::NotFound),
+            }
+        };
+
+        let status = |id| {
+            IntoResponse::into_response(handler(id))
+                .unwrap()
+                .status_code
+        };
+        assert_eq!(status("7"), 200);
+        assert_eq!(status("8"), 404);
+        assert_eq!(status("x"), 400);
+    }
+}
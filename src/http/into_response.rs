@@ -0,0 +1,117 @@
+use std::io;
+
+#[cfg(feature = "json")]
+use crate::http::ApiResponse;
+use crate::http::HttpResponse;
+
+/// Anything a handler can return. Text becomes a `text/plain` 200, a JSON
+/// value an `application/json` 200, and a `(status, body)` pair sets the
+/// status as well. An `io::Error` is passed through to the server, which
+/// answers it like any other failed handler.
+///
+/// ```no_run
+/// # use std::io;
+/// # use schnell::http::Request;
+/// # use schnell::routing::RouteBuilder;
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// # let enqueue = |_: &Request| -> io::Result<String> { Ok("job-1".to_string()) };
+/// server.get("/", |_| "hello");
+/// # #[cfg(feature = "json")]
+/// server.get("/health", |_| serde_json::json!({ "status": "up" }));
+/// server.post("/jobs", move |req| -> io::Result<_> { Ok((202, enqueue(req)?)) });
+/// ```
+pub trait IntoResponse {
+    fn into_response(self) -> io::Result<HttpResponse>;
+}
+
+impl IntoResponse for HttpResponse {
+    fn into_response(self) -> io::Result<HttpResponse> {
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "json")]
+impl IntoResponse for ApiResponse {
+    fn into_response(self) -> io::Result<HttpResponse> {
+        Ok(self.into())
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> io::Result<HttpResponse> {
+        Ok(HttpResponse::ok().text(&self))
+    }
+}
+
+impl IntoResponse for &str {
+    fn into_response(self) -> io::Result<HttpResponse> {
+        Ok(HttpResponse::ok().text(self))
+    }
+}
+
+impl IntoResponse for (u16, String) {
+    fn into_response(self) -> io::Result<HttpResponse> {
+        Ok(HttpResponse::new(self.0).text(&self.1))
+    }
+}
+
+impl IntoResponse for (u16, &str) {
+    fn into_response(self) -> io::Result<HttpResponse> {
+        Ok(HttpResponse::new(self.0).text(self.1))
+    }
+}
+
+#[cfg(feature = "json")]
+impl IntoResponse for serde_json::Value {
+    fn into_response(self) -> io::Result<HttpResponse> {
+        Ok(HttpResponse::ok().json(self))
+    }
+}
+
+impl IntoResponse for io::Error {
+    fn into_response(self) -> io::Result<HttpResponse> {
+        Err(self)
+    }
+}
+
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    fn into_response(self) -> io::Result<HttpResponse> {
+        match self {
+            Ok(value) => value.into_response(),
+            Err(error) => error.into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_response() {
+        let response = "hello".into_response().unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.body, "hello");
+
+        let response = (201, String::from("made")).into_response().unwrap();
+        assert_eq!(
+            (response.status_code, response.body.as_str()),
+            (201, "made")
+        );
+
+        let failed: io::Result<&str> = Err(io::Error::other("db down"));
+        assert!(failed.into_response().is_err());
+
+        let rejected: Result<String, (u16, &str)> = Err((409, "taken"));
+        assert_eq!(rejected.into_response().unwrap().status_code, 409);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_json_value() {
+        let response = serde_json::json!({ "ok": true }).into_response().unwrap();
+        assert_eq!(response.content_type, "application/json");
+        assert_eq!(response.body, r#"{"ok":true}"#);
+    }
+}
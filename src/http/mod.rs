@@ -1,9 +1,30 @@
+pub mod body;
+pub mod conditional;
+pub mod cookie;
+#[cfg(feature = "json")]
+pub mod envelope;
+pub mod error;
+pub mod into_response;
 pub mod method;
+pub mod multipart;
+pub mod redirect;
 pub mod request;
 pub mod response;
+pub mod spool;
+pub mod sse;
+pub mod uri;
 pub mod version;
 
+pub use cookie::{Cookie, SameSite};
+#[cfg(feature = "json")]
+pub use envelope::ApiResponse;
+pub use error::HttpError;
+pub use into_response::IntoResponse;
 pub use method::HttpMethod;
+pub use multipart::Multipart;
+pub use redirect::safe_redirect;
 pub use request::Request;
 pub use response::HttpResponse;
+pub use spool::SpooledBody;
+pub use sse::SseStream;
 pub use version::Version;
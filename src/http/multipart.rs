@@ -0,0 +1,285 @@
+/* multipart/form-data parsing for form posts and file uploads */
+use crate::http::body::BodyError;
+
+/// One part of a `multipart/form-data` body: a plain form field, or a file when
+/// `filename` is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl Part {
+    pub fn is_file(&self) -> bool {
+        self.filename.is_some()
+    }
+
+    /// The part's content as text, if it is valid UTF-8.
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.data).ok()
+    }
+}
+
+/// A parsed `multipart/form-data` body, parts kept in the order they were sent.
+#[derive(Debug, Clone, Default)]
+pub struct Multipart {
+    parts: Vec<Part>,
+}
+
+impl Multipart {
+    /// Parses `body` using the boundary from `content_type`, which must be
+    /// `multipart/form-data`.
+    pub fn parse(content_type: &str, body: &[u8]) -> Result<Self, BodyError> {
+        let mut params = content_type.split(';');
+        let essence = params.next().unwrap_or("").trim();
+        if !essence.eq_ignore_ascii_case("multipart/form-data") {
+            return Err(BodyError::UnsupportedMediaType("multipart/form-data"));
+        }
+
+        let boundary = params
+            .filter_map(|param| param.split_once('='))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case("boundary"))
+            .map(|(_, value)| value.trim().trim_matches('"'))
+            .filter(|boundary| !boundary.is_empty() && boundary.len() <= 70)
+            .ok_or(BodyError::InvalidMultipart("missing boundary"))?;
+
+        parse_parts(body, boundary.as_bytes()).map(|parts| Self { parts })
+    }
+
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    /// The first part called `name`.
+    pub fn part(&self, name: &str) -> Option<&Part> {
+        self.parts.iter().find(|part| part.name == name)
+    }
+
+    /// The text of the first non-file field called `name`.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.parts
+            .iter()
+            .find(|part| part.name == name && !part.is_file())?
+            .text()
+    }
+
+    /// The first file part called `name`.
+    pub fn file(&self, name: &str) -> Option<&Part> {
+        self.parts
+            .iter()
+            .find(|part| part.name == name && part.is_file())
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &Part> {
+        self.parts.iter().filter(|part| part.is_file())
+    }
+}
+
+fn parse_parts(body: &[u8], boundary: &[u8]) -> Result<Vec<Part>, BodyError> {
+    let mut delimiter = b"--".to_vec();
+    delimiter.extend_from_slice(boundary);
+
+    // Anything before the first delimiter is preamble and ignored
+    let start = find(body, &delimiter).ok_or(BodyError::InvalidMultipart("missing boundary"))?;
+    let mut rest = &body[start + delimiter.len()..];
+
+    // Every later delimiter must start on its own line
+    delimiter.splice(0..0, *b"\r\n");
+
+    let mut parts = Vec::new();
+    loop {
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+
+        // Transport padding may follow the boundary before the line break
+        let line_end = find(rest, b"\r\n").ok_or(BodyError::InvalidMultipart("truncated body"))?;
+        if rest[..line_end].iter().any(|b| *b != b' ' && *b != b'\t') {
+            return Err(BodyError::InvalidMultipart("malformed boundary line"));
+        }
+        rest = &rest[line_end + 2..];
+
+        let end =
+            find(rest, &delimiter).ok_or(BodyError::InvalidMultipart("missing close delimiter"))?;
+        parts.push(parse_part(&rest[..end])?);
+        rest = &rest[end + delimiter.len()..];
+    }
+}
+
+fn parse_part(raw: &[u8]) -> Result<Part, BodyError> {
+    let (head, data) = if raw.starts_with(b"\r\n") {
+        (&raw[..0], &raw[2..])
+    } else {
+        let split =
+            find(raw, b"\r\n\r\n").ok_or(BodyError::InvalidMultipart("missing part headers"))?;
+        (&raw[..split], &raw[split + 4..])
+    };
+    let head = std::str::from_utf8(head)
+        .map_err(|_| BodyError::InvalidMultipart("part headers are not UTF-8"))?;
+
+    let mut disposition = None;
+    let mut content_type = None;
+    for line in head.split("\r\n") {
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(BodyError::InvalidMultipart("malformed part header"));
+        };
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-disposition" => disposition = Some(value.trim()),
+            "content-type" => content_type = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+
+    let disposition = disposition.ok_or(BodyError::InvalidMultipart(
+        "part without Content-Disposition",
+    ))?;
+    let params = disposition_params(disposition);
+    let param = |key: &str| {
+        params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.clone())
+    };
+
+    Ok(Part {
+        name: param("name").ok_or(BodyError::InvalidMultipart("part without a name"))?,
+        filename: param("filename"),
+        content_type,
+        data: data.to_vec(),
+    })
+}
+
+/// `key=value` parameters of a `Content-Disposition` value, unquoting quoted
+/// values so that `;` inside a filename doesn't split it.
+fn disposition_params(value: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut chars = value.chars().peekable();
+
+    // Skip the disposition type itself
+    for c in chars.by_ref() {
+        if c == ';' {
+            break;
+        }
+    }
+
+    loop {
+        let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        let key = key.trim().to_string();
+        if key.is_empty() {
+            return params;
+        }
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => value.extend(chars.next()),
+                    c => value.push(c),
+                }
+            }
+            for c in chars.by_ref() {
+                if c == ';' {
+                    break;
+                }
+            }
+        } else {
+            value = chars.by_ref().take_while(|c| *c != ';').collect();
+            value = value.trim().to_string();
+        }
+
+        params.push((key, value));
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT_TYPE: &str = "multipart/form-data; boundary=----XyZ";
+
+    fn body(parts: &[&[u8]]) -> Vec<u8> {
+        let mut body = b"preamble\r\n".to_vec();
+        for part in parts {
+            body.extend_from_slice(b"------XyZ\r\n");
+            body.extend_from_slice(part);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(b"------XyZ--\r\n");
+        body
+    }
+
+    #[test]
+    fn test_fields_and_files() {
+        let body = body(&[
+            b"Content-Disposition: form-data; name=\"title\"\r\n\r\nHoliday",
+            b"Content-Disposition: form-data; name=\"photo\"; filename=\"a;b.png\"\r\n\
+              Content-Type: image/png\r\n\r\n\x89PNG\r\n\x00\xff",
+        ]);
+        let form = Multipart::parse(CONTENT_TYPE, &body).unwrap();
+
+        assert_eq!(form.parts().len(), 2);
+        assert_eq!(form.field("title"), Some("Holiday"));
+        assert_eq!(form.field("photo"), None);
+
+        let photo = form.file("photo").unwrap();
+        assert_eq!(photo.filename.as_deref(), Some("a;b.png"));
+        assert_eq!(photo.content_type.as_deref(), Some("image/png"));
+        assert_eq!(photo.data, b"\x89PNG\r\n\x00\xff");
+        assert_eq!(form.files().count(), 1);
+    }
+
+    #[test]
+    fn test_quoted_boundary_and_empty_field() {
+        let body = b"--abc\r\nContent-Disposition: form-data; name=empty\r\n\r\n\r\n--abc--";
+        let form = Multipart::parse("Multipart/Form-Data; boundary=\"abc\"", body).unwrap();
+
+        assert_eq!(form.field("empty"), Some(""));
+        assert_eq!(form.part("empty").unwrap().content_type, None);
+    }
+
+    #[test]
+    fn test_disposition_params_unescape() {
+        assert_eq!(
+            disposition_params(r#"form-data; name="file"; filename="say \"hi\".txt""#),
+            [
+                ("name".to_string(), "file".to_string()),
+                ("filename".to_string(), "say \"hi\".txt".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_bodies() {
+        assert!(matches!(
+            Multipart::parse("application/json", b""),
+            Err(BodyError::UnsupportedMediaType(_))
+        ));
+        assert!(matches!(
+            Multipart::parse("multipart/form-data", b""),
+            Err(BodyError::InvalidMultipart("missing boundary"))
+        ));
+
+        let unterminated = b"------XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1";
+        assert!(matches!(
+            Multipart::parse(CONTENT_TYPE, unterminated),
+            Err(BodyError::InvalidMultipart("missing close delimiter"))
+        ));
+
+        let nameless = body(&[b"Content-Disposition: form-data\r\n\r\nx"]);
+        assert!(matches!(
+            Multipart::parse(CONTENT_TYPE, &nameless),
+            Err(BodyError::InvalidMultipart("part without a name"))
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+/* Open-redirect protection for user-supplied redirect targets */
+use crate::http::{HttpResponse, Request};
+
+/// Redirects to `next` (typically a login form's `?next=` parameter) if
+/// `is_safe_redirect` accepts it, and to `/` otherwise.
+///
+/// ```no_run
+/// # use schnell::http::safe_redirect;
+/// # use schnell::routing::RouteBuilder;
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// server.post("/login", |req| {
+///     // ... check credentials ...
+///     safe_redirect(req, req.query_param_or("next", "/"), &["accounts.example.com"])
+/// });
+/// ```
+pub fn safe_redirect(request: &Request, next: &str, allowed_hosts: &[&str]) -> HttpResponse {
+    let target = match is_safe_redirect(request, next, allowed_hosts) {
+        true => next,
+        false => "/",
+    };
+    HttpResponse::new(302).redirect(target)
+}
+
+/// Whether `target` stays on this site: a path on the same origin, or an
+/// http(s) URL whose host is the request's own `Host` or one of
+/// `allowed_hosts` (`example.com`, or `example.com:8443` to pin the port).
+///
+/// Scheme-relative (`//evil.test`), backslashed (`/\evil.test`), userinfo
+/// (`https://example.com@evil.test`) and non-http(s) targets are rejected,
+/// as browsers would resolve them to another origin or run them as script.
+pub fn is_safe_redirect(request: &Request, target: &str, allowed_hosts: &[&str]) -> bool {
+    if target.is_empty() || target.bytes().any(|b| b.is_ascii_control() || b == b'\\') {
+        return false;
+    }
+
+    let Some((scheme, rest)) = split_scheme(target) else {
+        // A relative reference; only a scheme-relative one leaves the origin
+        return !target.starts_with("//");
+    };
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return false;
+    }
+
+    let Some(rest) = rest.strip_prefix("//") else {
+        return false;
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    if authority.is_empty() || authority.contains('@') {
+        return false;
+    }
+
+    let same_origin = request
+        .headers
+        .get("host")
+        .is_some_and(|host| host.trim().eq_ignore_ascii_case(authority));
+    let host = authority
+        .rsplit_once(':')
+        .map_or(authority, |(host, _)| host);
+    same_origin
+        || allowed_hosts.iter().any(|allowed| {
+            allowed.eq_ignore_ascii_case(authority)
+                || (!allowed.contains(':') && allowed.eq_ignore_ascii_case(host))
+        })
+}
+
+/// Splits off the scheme of an absolute URL: letters, digits, `+-.` before
+/// the first `:`, which must come before any `/`, `?` or `#`.
+fn split_scheme(target: &str) -> Option<(&str, &str)> {
+    let (scheme, rest) = target.split_once(':')?;
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+    valid.then_some((scheme, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn request() -> Request {
+        let raw = "POST /login HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let mut buffer = BufReader::new(raw.as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    #[test]
+    fn test_same_origin_targets() {
+        let req = request();
+        for target in [
+            "/dashboard",
+            "/search?q=a:b#top",
+            "settings",
+            "?tab=2",
+            "https://example.com/account",
+            "http://EXAMPLE.com",
+        ] {
+            assert!(is_safe_redirect(&req, target, &[]), "{}", target);
+        }
+    }
+
+    #[test]
+    fn test_allowed_hosts() {
+        let req = request();
+        let allowed = ["accounts.example.com", "admin.example.com:8443"];
+
+        assert!(is_safe_redirect(
+            &req,
+            "https://accounts.example.com/x",
+            &allowed
+        ));
+        assert!(is_safe_redirect(
+            &req,
+            "https://accounts.example.com:444",
+            &allowed
+        ));
+        assert!(is_safe_redirect(
+            &req,
+            "https://admin.example.com:8443/",
+            &allowed
+        ));
+        assert!(!is_safe_redirect(
+            &req,
+            "https://admin.example.com/",
+            &allowed
+        ));
+    }
+
+    #[test]
+    fn test_open_redirects_rejected() {
+        let req = request();
+        for target in [
+            "",
+            "//evil.test",
+            "/\\evil.test",
+            "\\\\evil.test",
+            "https://evil.test",
+            "https://example.com@evil.test/",
+            "https:evil.test",
+            "javascript:alert(1)",
+            "/ok\r\nSet-Cookie: a=1",
+        ] {
+            assert!(!is_safe_redirect(&req, target, &[]), "{:?}", target);
+        }
+
+        let response = safe_redirect(&req, "//evil.test", &[]);
+        assert_eq!(response.status_code, 302);
+        assert_eq!(response.headers["Location"], "/");
+    }
+}
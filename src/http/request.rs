@@ -1,11 +1,27 @@
 use std::{
+    any::Any,
     collections::HashMap,
+    fmt,
     io::{BufRead, BufReader, ErrorKind, Read},
+    net::{IpAddr, SocketAddr},
     str::FromStr,
+    sync::Arc,
 };
 
-use crate::http::{HttpMethod, Version};
+use crate::constants::{
+    DEFAULT_MAX_BODY_SIZE, DEFAULT_MAX_HEADER_SIZE, DEFAULT_MAX_HEADERS, DEFAULT_MAX_REQUEST_LINE,
+    DEFAULT_MAX_URI_LENGTH,
+};
+use crate::http::body::BodyError;
+#[cfg(any(feature = "json", feature = "forms"))]
+use crate::http::body::strip_bom;
+use crate::http::cookie::verify_signed;
+use crate::http::uri::normalize_path;
+use crate::http::{HttpMethod, Multipart, Version};
+use crate::keyring::Keyring;
+use crate::utils::{percent_decode_query, split_host_port};
 
+#[derive(Debug)]
 pub enum RequestError {
     ReadError,
     InvalidRequest,
@@ -13,156 +29,568 @@ pub enum RequestError {
     ConnectionClosed,
     ConnectionTimedOut,
     ParseError,
+    UriTooLong,
+    HeadersTooLarge,
 }
 
-#[derive(Debug)]
+/// Upper bounds enforced while reading a request, before anything is buffered
+/// past them.
+#[derive(Debug, Clone)]
+pub struct RequestLimits {
+    /// Longest accepted request line, including the trailing CRLF.
+    pub max_request_line: usize,
+    /// Longest accepted request target (path and query string).
+    pub max_uri_length: usize,
+    /// Budget for the whole header section, including line endings.
+    pub max_header_size: usize,
+    /// Most header fields accepted in one request.
+    pub max_headers: usize,
+    /// Largest accepted body. Checked against `Content-Length` before the body is read.
+    pub max_body_size: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_request_line: DEFAULT_MAX_REQUEST_LINE,
+            max_uri_length: DEFAULT_MAX_URI_LENGTH,
+            max_header_size: DEFAULT_MAX_HEADER_SIZE,
+            max_headers: DEFAULT_MAX_HEADERS,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+}
+
+/// Application state shared by the server with every request.
+#[derive(Clone, Default)]
+pub(crate) struct SharedState(Option<Arc<dyn Any + Send + Sync>>);
+
+impl SharedState {
+    pub(crate) fn new<T: Send + Sync + 'static>(state: T) -> Self {
+        Self(Some(Arc::new(state)))
+    }
+
+    pub(crate) fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+impl fmt::Debug for SharedState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedState { .. }")
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Request {
     pub method: HttpMethod,
+    /// Percent-decoded (except for `%2F`, which stays escaped) and NFC-normalized.
     pub path: String,
     pub version: Version,
+    /// Header values keyed by lowercased name.
     pub headers: HashMap<String, String>,
+    /// Body decoded as UTF-8, with invalid sequences replaced.
     pub body: String,
     pub params: HashMap<String, String>,
+    /// Percent-decoded query parameters, with `+` read as a space.
     pub query: HashMap<String, String>,
+    raw_body: Vec<u8>,
+    raw_headers: Vec<(String, String)>,
+    remote_addr: Option<SocketAddr>,
+    secure: bool,
+    authority: Option<String>,
+    target: String,
+    state: SharedState,
+    keyring: Option<Arc<Keyring>>,
+    routed_methods: Option<Vec<HttpMethod>>,
 }
 
 impl Request {
-    pub fn read<R: Read>(mut buffer: BufReader<R>) -> Result<Self, RequestError> {
-        let mut lines = Vec::new();
-        let mut line = String::new();
+    /// Reads exactly one request off `buffer`. Any bytes past the end of its body stay
+    /// buffered, so pipelined requests can be read by calling this again.
+    pub fn read<R: Read>(buffer: &mut BufReader<R>) -> Result<Self, RequestError> {
+        Self::read_with_limits(buffer, &RequestLimits::default())
+    }
 
-        loop {
-            match buffer.read_line(&mut line) {
-                Ok(0) => {
-                    // End of stream reached
-                    if lines.is_empty() {
-                        return Err(RequestError::ConnectionClosed);
-                    }
-                    break;
-                }
-                Ok(_) => {
-                    if line.trim().is_empty() {
-                        break; // End of headers
-                    }
-                    lines.push(line.trim().to_string());
-                    line.clear();
-                }
-                Err(e) => match e.kind() {
-                    std::io::ErrorKind::UnexpectedEof => {
-                        return Err(RequestError::ConnectionClosed);
-                    }
-                    std::io::ErrorKind::TimedOut => {
-                        return Err(RequestError::ConnectionTimedOut);
-                    }
-                    std::io::ErrorKind::ConnectionReset
-                    | std::io::ErrorKind::ConnectionAborted
-                    | std::io::ErrorKind::BrokenPipe => {
-                        return Err(RequestError::ConnectionClosed);
-                    }
-                    _ => {
-                        return Err(RequestError::ReadError);
-                    }
-                },
-            }
-        }
-
-        if lines.is_empty() {
-            return Err(RequestError::ConnectionClosed);
-        }
+    pub fn read_with_limits<R: Read>(
+        buffer: &mut BufReader<R>,
+        limits: &RequestLimits,
+    ) -> Result<Self, RequestError> {
+        let request_line = Self::read_request_line(buffer, limits.max_request_line)?;
 
-        if buffer.buffer().len() > 1024 * 1024 * 10 {
-            return Err(RequestError::RequestTooLarge);
-        }
+        let raw_headers = Self::read_headers(buffer, limits)?;
 
         // Parse request line
-        let (method, path, version) = Self::parse_request_line(&lines[0])?;
+        let (method, path, version) = Self::parse_request_line(&request_line)?;
+        if path.len() > limits.max_uri_length {
+            return Err(RequestError::UriTooLong);
+        }
 
         // Parse headers
-        let headers = Self::parse_headers(&lines[1..]);
+        let headers = raw_headers
+            .iter()
+            .map(|(key, value)| (key.to_lowercase(), value.clone()))
+            .collect();
 
         // Parse body (read remaining content)
-        let body = Self::parse_body(&mut buffer, &headers)?;
+        let raw_body = Self::parse_body(buffer, &headers, &raw_headers, limits.max_body_size)?;
+        let body = String::from_utf8_lossy(&raw_body).into_owned();
 
-        let (path, query) = path.split_once('?').unwrap_or((&path, ""));
+        let target = path;
+        let (authority, origin_form) = split_absolute_form(&target);
+        let (path, query) = origin_form.split_once('?').unwrap_or((origin_form, ""));
+        let path = if path.is_empty() { "/" } else { path };
 
         Ok(Request {
             method,
-            path: path.to_string(),
+            path: normalize_path(path),
             version,
             headers,
             body,
             params: HashMap::new(),
             query: Self::parse_query(query),
+            raw_body,
+            raw_headers,
+            remote_addr: None,
+            secure: false,
+            authority: authority.map(str::to_string),
+            target: target.clone(),
+            state: SharedState::default(),
+            keyring: None,
+            routed_methods: None,
         })
     }
 
+    /// The body exactly as it was received, e.g. for verifying webhook signatures.
+    pub fn raw_body(&self) -> &[u8] {
+        &self.raw_body
+    }
+
+    #[cfg(feature = "json")]
+    /// Deserializes a JSON body. Fails with [`BodyError::UnsupportedMediaType`]
+    /// unless `Content-Type` is JSON, which handlers can turn into a 415/400 with
+    /// [`BodyError::into_response`].
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, BodyError> {
+        let content_type = self.headers.get("content-type").map_or("", |v| v.as_str());
+        if !crate::http::body::is_json_content_type(content_type) {
+            return Err(BodyError::UnsupportedMediaType("application/json"));
+        }
+
+        serde_json::from_slice(strip_bom(&self.raw_body)).map_err(BodyError::InvalidJson)
+    }
+
+    /// Deserializes an `application/x-www-form-urlencoded` body. Bracketed keys
+    /// nest, as sent by many JS form libraries: `user[name]=x&user[emails][]=a`
+    /// fills `user.name` and appends to `user.emails`.
+    #[cfg(feature = "forms")]
+    pub fn form<T: serde::de::DeserializeOwned>(&self) -> Result<T, BodyError> {
+        let content_type = self.headers.get("content-type").map_or("", |v| v.as_str());
+        let essence = content_type.split(';').next().unwrap_or("").trim();
+        if !essence.eq_ignore_ascii_case("application/x-www-form-urlencoded") {
+            return Err(BodyError::UnsupportedMediaType(
+                "application/x-www-form-urlencoded",
+            ));
+        }
+
+        serde_qs::Config::new()
+            .max_depth(crate::constants::MAX_FORM_DEPTH)
+            .use_form_encoding(true)
+            .deserialize_bytes(strip_bom(&self.raw_body))
+            .map_err(BodyError::InvalidForm)
+    }
+
+    /// Parses a `multipart/form-data` body into its fields and uploaded files.
+    pub fn multipart(&self) -> Result<Multipart, BodyError> {
+        let content_type = self.headers.get("content-type").map_or("", |v| v.as_str());
+        Multipart::parse(content_type, &self.raw_body)
+    }
+
+    /// Headers exactly as received: original name casing, order and duplicates,
+    /// e.g. for forwarding a request byte-faithfully to an upstream.
+    pub fn raw_headers(&self) -> &[(String, String)] {
+        &self.raw_headers
+    }
+
+    /// State registered with `Server::with_state`, if it is of type `T`.
+    pub fn state<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.state.0.as_ref()?.downcast_ref()
+    }
+
+    pub(crate) fn set_state(&mut self, state: SharedState) {
+        self.state = state;
+    }
+
+    /// Keys configured with `Server::with_secret_key`, for signing cookies.
+    pub fn keyring(&self) -> Option<&Keyring> {
+        self.keyring.as_deref()
+    }
+
+    pub(crate) fn set_keyring(&mut self, keyring: Option<Arc<Keyring>>) {
+        self.keyring = keyring;
+    }
+
+    /// Methods routed for this path, as in the `Allow` header. Only set on
+    /// OPTIONS requests dispatched by a `Server`.
+    pub(crate) fn routed_methods(&self) -> Option<&[HttpMethod]> {
+        self.routed_methods.as_deref()
+    }
+
+    pub(crate) fn set_routed_methods(&mut self, methods: Vec<HttpMethod>) {
+        self.routed_methods = Some(methods);
+    }
+
+    /// Value of the cookie called `name` from the `Cookie` header.
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get("cookie")?
+            .split(';')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| key.trim() == name)
+            .map(|(_, value)| value.trim().trim_matches('"'))
+    }
+
+    /// Value of a cookie set with `Cookie::signed`. `None` if it is missing, has
+    /// been tampered with, or no secret key is configured.
+    pub fn signed_cookie(&self, name: &str) -> Option<&str> {
+        verify_signed(name, self.cookie(name)?, self.keyring()?)
+    }
+
+    /// Host named by the `Host` header, without its port. IPv6 literals are
+    /// returned without their brackets, e.g. `::1` for `[::1]:8080`.
+    pub fn host(&self) -> Option<&str> {
+        split_host_port(self.headers.get("host")?).map(|(host, _)| host)
+    }
+
+    /// Port named by the `Host` header, if any.
+    pub fn host_port(&self) -> Option<u16> {
+        split_host_port(self.headers.get("host")?).and_then(|(_, port)| port)
+    }
+
+    /// Address of the peer this request was read from, when served over TCP.
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+
+    /// IP of the connected client, with IPv4-mapped IPv6 addresses
+    /// (`::ffff:10.0.0.1`) normalized to plain IPv4.
+    pub fn client_ip(&self) -> Option<IpAddr> {
+        self.remote_addr.map(|addr| addr.ip().to_canonical())
+    }
+
+    pub(crate) fn set_remote_addr(&mut self, addr: Option<SocketAddr>) {
+        self.remote_addr = addr;
+    }
+
+    /// Whether the request arrived over TLS.
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    pub(crate) fn set_secure(&mut self, secure: bool) {
+        self.secure = secure;
+    }
+
+    /// Authority of an absolute-form target (`GET http://example.com/a HTTP/1.1`),
+    /// which clients only send to proxies. `path` and `query` then hold the
+    /// target's path and query string.
+    pub fn absolute_authority(&self) -> Option<&str> {
+        self.authority.as_deref()
+    }
+
+    /// The request target exactly as sent, path and query string, before
+    /// percent-decoding, e.g. `/search?q=a%20b`.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// The target in origin form, path and query string, with the scheme and
+    /// authority of an absolute-form target removed. Always starts with `/`.
+    pub(crate) fn origin_target(&self) -> &str {
+        match split_absolute_form(&self.target).1 {
+            origin_form if origin_form.starts_with('/') => origin_form,
+            _ => "/",
+        }
+    }
+
+    pub fn query_param(&self, key: &str) -> Option<&str> {
+        self.query.get(key).map(|v| v.as_str())
+    }
+
+    pub fn query_param_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str {
+        self.query_param(key).unwrap_or(default)
+    }
+
+    /// Whether the client is willing to send further requests on this connection.
+    /// HTTP/1.0 clients always get their connection closed; 1.1 clients keep it
+    /// open unless they send `Connection: close`.
+    pub fn keep_alive(&self) -> bool {
+        self.version != Version::HTTP1_0
+            && !self
+                .headers
+                .get("connection")
+                .is_some_and(|v| v.eq_ignore_ascii_case("close"))
+    }
+
+    /// Reads the request line without buffering more than `max_len` bytes of it.
+    fn read_request_line<R: Read>(
+        buffer: &mut BufReader<R>,
+        max_len: usize,
+    ) -> Result<String, RequestError> {
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+            let read = buffer
+                .by_ref()
+                .take(max_len as u64)
+                .read_line(&mut line)
+                .map_err(Self::map_io_error)?;
+
+            if read == 0 {
+                return Err(RequestError::ConnectionClosed);
+            }
+            if read >= max_len && !line.ends_with('\n') {
+                return Err(RequestError::UriTooLong);
+            }
+            // Tolerate stray CRLFs between pipelined requests
+            if !line.trim().is_empty() {
+                return Ok(line.trim().to_string());
+            }
+        }
+    }
+
+    fn map_io_error(e: std::io::Error) -> RequestError {
+        match e.kind() {
+            ErrorKind::UnexpectedEof
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::BrokenPipe => RequestError::ConnectionClosed,
+            // Socket read timeouts surface as WouldBlock on Unix
+            ErrorKind::TimedOut | ErrorKind::WouldBlock => RequestError::ConnectionTimedOut,
+            ErrorKind::InvalidData => RequestError::ParseError,
+            _ => RequestError::ReadError,
+        }
+    }
+
     fn parse_request_line(line: &str) -> Result<(HttpMethod, String, Version), RequestError> {
         let parts: Vec<&str> = line.split_whitespace().collect();
         if parts.len() != 3 {
             return Err(RequestError::ParseError);
         }
 
-        let method = HttpMethod::from_str(parts[0]).ok_or(RequestError::ParseError)?;
+        let method = HttpMethod::from_str(parts[0]).map_err(|_| RequestError::ParseError)?;
 
         let version = Version::from_str(parts[2]).map_err(|_| RequestError::InvalidRequest)?;
 
-        Ok((method, parts[1].to_string(), version))
+        // Only origin form, absolute form and `OPTIONS *` are meaningful here;
+        // anything else (`@evil.com/x`) would leak into targets we echo back
+        let target = parts[1];
+        let valid_target = target.starts_with('/')
+            || split_absolute_form(target).0.is_some()
+            || (target == "*" && method == HttpMethod::OPTIONS);
+        if !valid_target {
+            return Err(RequestError::ParseError);
+        }
+
+        Ok((method, target.to_string(), version))
     }
 
-    fn parse_headers(lines: &[String]) -> HashMap<String, String> {
-        let mut headers = HashMap::new();
-        for line in lines {
+    /// Reads header fields one line at a time against a running byte budget, so
+    /// oversized header sections are rejected without being buffered.
+    fn read_headers<R: Read>(
+        buffer: &mut BufReader<R>,
+        limits: &RequestLimits,
+    ) -> Result<Vec<(String, String)>, RequestError> {
+        let mut headers = Vec::new();
+        let mut budget = limits.max_header_size;
+        let mut line = String::new();
+
+        loop {
+            if budget == 0 {
+                return Err(RequestError::HeadersTooLarge);
+            }
+
+            line.clear();
+            let read = buffer
+                .by_ref()
+                .take(budget as u64)
+                .read_line(&mut line)
+                .map_err(Self::map_io_error)?;
+
+            if read == 0 {
+                break; // End of stream reached
+            }
+            if read == budget && !line.ends_with('\n') {
+                return Err(RequestError::HeadersTooLarge);
+            }
+            budget -= read;
+
+            if line.trim().is_empty() {
+                break; // End of headers
+            }
             if let Some((key, value)) = line.split_once(':') {
-                headers.insert(key.trim().to_lowercase(), value.trim().to_string());
+                if headers.len() == limits.max_headers {
+                    return Err(RequestError::HeadersTooLarge);
+                }
+                headers.push((key.trim().to_string(), value.trim().to_string()));
             }
         }
-        headers
+
+        Ok(headers)
     }
 
     fn parse_body<R: Read>(
         buffer: &mut BufReader<R>,
         headers: &HashMap<String, String>,
-    ) -> Result<String, RequestError> {
-        let content_length = headers
-            .get("content-length")
-            .and_then(|v| v.parse::<usize>().ok())
-            .unwrap_or(0);
+        raw_headers: &[(String, String)],
+        max_body_size: usize,
+    ) -> Result<Vec<u8>, RequestError> {
+        if let Some(encoding) = headers.get("transfer-encoding") {
+            // Framed both ways, the request may be read differently by a proxy in
+            // front of us, so it is rejected rather than decoded (RFC 9112 §6.1)
+            if headers.contains_key("content-length") {
+                return Err(RequestError::ParseError);
+            }
+            let chunked = encoding
+                .rsplit(',')
+                .next()
+                .is_some_and(|last| last.trim().eq_ignore_ascii_case("chunked"));
+            if !chunked {
+                return Err(RequestError::ParseError);
+            }
+            return Self::parse_chunked_body(buffer, max_body_size);
+        }
+
+        let content_length = Self::content_length(raw_headers)?;
 
         if content_length == 0 {
-            return Ok(String::new());
+            return Ok(Vec::new());
+        }
+
+        // Reject before allocating or reading anything of the body
+        if content_length > max_body_size {
+            return Err(RequestError::RequestTooLarge);
         }
 
         let mut body = vec![0; content_length];
-        match buffer.read_exact(&mut body) {
-            Ok(()) => {}
-            Err(e) => match e.kind() {
-                ErrorKind::UnexpectedEof => {
-                    return Err(RequestError::ConnectionClosed);
-                }
-                ErrorKind::TimedOut => {
-                    return Err(RequestError::ConnectionTimedOut);
-                }
-                ErrorKind::ConnectionReset
-                | ErrorKind::ConnectionAborted
-                | ErrorKind::BrokenPipe => {
-                    return Err(RequestError::ConnectionClosed);
-                }
-                _ => {
-                    return Err(RequestError::ReadError);
-                }
-            },
+        buffer.read_exact(&mut body).map_err(Self::map_io_error)?;
+
+        Ok(body)
+    }
+
+    /// The body length from `Content-Length`, or 0 without one. A value that
+    /// isn't a plain number, or several headers that disagree, would leave the
+    /// end of the body ambiguous, so the rest of the connection can't be trusted.
+    fn content_length(raw_headers: &[(String, String)]) -> Result<usize, RequestError> {
+        let mut content_length = None;
+        for (_, value) in raw_headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        {
+            if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(RequestError::ParseError);
+            }
+            let length = value
+                .parse::<usize>()
+                .map_err(|_| RequestError::ParseError)?;
+            if content_length.is_some_and(|previous| previous != length) {
+                return Err(RequestError::ParseError);
+            }
+            content_length = Some(length);
+        }
+        Ok(content_length.unwrap_or(0))
+    }
+
+    fn parse_chunked_body<R: Read>(
+        buffer: &mut BufReader<R>,
+        max_body_size: usize,
+    ) -> Result<Vec<u8>, RequestError> {
+        let mut body = Vec::new();
+
+        loop {
+            let size_line = Self::read_chunk_line(buffer)?;
+            // Chunk extensions (";name=value") are allowed and ignored
+            let size = size_line.split(';').next().unwrap_or("").trim();
+            // `from_str_radix` would also take a sign, which a proxy in front of
+            // us may not (RFC 9112 §7.1 allows hex digits only)
+            if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(RequestError::ParseError);
+            }
+            let size = usize::from_str_radix(size, 16).map_err(|_| RequestError::ParseError)?;
+
+            if size == 0 {
+                break;
+            }
+            if size > max_body_size.saturating_sub(body.len()) {
+                return Err(RequestError::RequestTooLarge);
+            }
+
+            let start = body.len();
+            body.resize(start + size, 0);
+            buffer
+                .read_exact(&mut body[start..])
+                .map_err(Self::map_io_error)?;
+
+            if !Self::read_chunk_line(buffer)?.is_empty() {
+                return Err(RequestError::ParseError);
+            }
         }
 
-        String::from_utf8(body).map_err(|_| RequestError::ParseError)
+        // Trailer fields are read off the wire but not exposed
+        while !Self::read_chunk_line(buffer)?.is_empty() {}
+
+        Ok(body)
+    }
+
+    /// Reads one CRLF-terminated line of chunked framing, without the line ending.
+    fn read_chunk_line<R: Read>(buffer: &mut BufReader<R>) -> Result<String, RequestError> {
+        const MAX_CHUNK_LINE: u64 = 4096;
+
+        let mut line = String::new();
+        let read = buffer
+            .by_ref()
+            .take(MAX_CHUNK_LINE)
+            .read_line(&mut line)
+            .map_err(Self::map_io_error)?;
+
+        if read == 0 {
+            return Err(RequestError::ConnectionClosed);
+        }
+        if !line.ends_with('\n') {
+            return Err(RequestError::ParseError);
+        }
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
     }
 
     fn parse_query(url: &str) -> HashMap<String, String> {
         let mut query_map = HashMap::new();
         for pair in url.split('&') {
             let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
-            query_map.insert(key.to_string(), value.to_string());
+            query_map.insert(percent_decode_query(key), percent_decode_query(value));
         }
 
         query_map
     }
 }
+
+/// Splits an absolute-form target (`http://example.com/a?b`) into its authority
+/// and origin form (`/a?b`). Other targets come back unchanged with no authority.
+fn split_absolute_form(target: &str) -> (Option<&str>, &str) {
+    let scheme_len = ["http://", "https://"].into_iter().find_map(|scheme| {
+        target
+            .get(..scheme.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(scheme))
+            .map(str::len)
+    });
+    let Some(scheme_len) = scheme_len else {
+        return (None, target);
+    };
+
+    let rest = &target[scheme_len..];
+    let end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let origin_form = match &rest[end..] {
+        "" => "/",
+        origin_form => origin_form,
+    };
+    (Some(&rest[..end]), origin_form)
+}
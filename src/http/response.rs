@@ -1,18 +1,45 @@
-use crate::constants::HTTP_VERSION;
-use crate::utils::{get_status_text, sanitize_header_key};
-use chrono::{DateTime, Duration, Utc};
-use serde::Serialize;
-use serde_json;
+use crate::constants::DEFAULT_OUTPUT_BUFFER_SIZE;
+use crate::http::Version;
+use crate::http::conditional::EtagCache;
+use crate::http::cookie::Cookie;
+use crate::http::spool::SpooledBody;
+use crate::utils::{encode_uri, get_status_text, sanitize_header_key};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::io::{Result, Write};
-use std::net::TcpStream;
+use std::fmt;
+use std::io::{BufWriter, ErrorKind, Read, Result, Write};
+
+/// How eagerly a response is pushed onto the wire.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlushMode {
+    /// Coalesce writes in the output buffer and flush once the response is complete.
+    Buffered,
+    /// Latency-sensitive: flush as soon as possible and bypass Nagle's algorithm.
+    Immediate,
+}
+
+/// A body produced while the response is being written, sent with chunked framing.
+pub struct BodyStream(Box<dyn Read + Send>);
+
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BodyStream { .. }")
+    }
+}
 
+#[derive(Debug)]
 pub struct HttpResponse {
     pub status_code: u16,
     pub content_type: String,
     pub body: String,
     pub headers: HashMap<String, String>,
     pub cookies: Vec<String>,
+    pub flush_mode: FlushMode,
+    stream: Option<BodyStream>,
+    /// `body` after content coding (gzip, deflate), sent in its place.
+    encoded_body: Option<Vec<u8>>,
+    head_only: bool,
+    version: Version,
 }
 
 impl HttpResponse {
@@ -23,115 +50,143 @@ impl HttpResponse {
             headers: HashMap::new(),
             body: String::new(),
             cookies: Vec::new(),
+            flush_mode: FlushMode::Buffered,
+            stream: None,
+            encoded_body: None,
+            head_only: false,
+            version: Version::HTTP1_1,
         }
     }
 
-    pub fn to_string(&self) -> String {
-        let mut response = String::new();
-
-        response.push_str(&format!(
-            "{} {} {}\r\n",
-            HTTP_VERSION,
-            self.status_code,
-            get_status_text(self.status_code)
-        ));
-
-        // Content-Type
-        response.push_str(&format!("Content-Type: {}\r\n", self.content_type));
-
-        // Content-Length
-        response.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+    /// Adds a `Set-Cookie` header for `cookie`.
+    pub fn cookie(self, cookie: Cookie) -> Self {
+        let mut new_response = self;
+        new_response.cookies.push(cookie.to_string());
+        new_response
+    }
 
-        // Custom Headers
-        let mut header_keys: Vec<_> = self.headers.keys().collect();
-        header_keys.sort();
-        for key in header_keys {
-            let value = &self.headers[key];
-            response.push_str(&format!("{}: {}\r\n", key, value));
-        }
+    pub fn header(self, key: &str, value: &str) -> Self {
+        let mut new_response = self;
+        let key = sanitize_header_key(key);
+        new_response.headers.insert(key, value.to_string());
+        new_response
+    }
 
-        // Cookies
-        for cookie in &self.cookies {
-            response.push_str(&format!("Set-Cookie: {}\r\n", cookie));
+    pub fn headers<const N: usize>(self, headers: [(&str, &str); N]) -> Self {
+        let mut new_response = self;
+        for (key, value) in headers {
+            let key = sanitize_header_key(key);
+            new_response.headers.insert(key, value.to_string());
         }
+        new_response
+    }
 
-        // Empty line
-        response.push_str("\r\n");
-
-        // Body
-        response.push_str(&self.body);
+    pub fn body(self, body: &str) -> Self {
+        let mut new_response = self;
+        new_response.body = body.to_string();
+        new_response
+    }
 
-        response
+    /// Streams the body from `reader` with `Transfer-Encoding: chunked` instead of
+    /// buffering it, e.g. for large files or generated exports. Replaces any `body`.
+    pub fn stream<R: Read + Send + 'static>(self, reader: R) -> Self {
+        let mut new_response = self;
+        new_response.body.clear();
+        new_response.stream = Some(BodyStream(Box::new(reader)));
+        new_response
     }
 
-    pub fn set_cookie(
+    /// Streams the body like [`HttpResponse::stream`], hashing it on the way out.
+    /// Once fully sent, its ETag is stored in `cache` under `key`; later
+    /// responses for the same key carry that ETag up front, so conditional
+    /// requests can be answered with 304 without streaming anything.
+    ///
+    /// The ETag sent is the one from the previous complete stream: if the
+    /// content changed in between, the next response corrects it.
+    pub fn stream_with_etag<R: Read + Send + 'static>(
         self,
+        reader: R,
+        cache: &EtagCache,
         key: &str,
-        value: &str,
-        samesite: &str,
-        http_only: bool,
-        secure: bool,
-        max_age: Option<u32>,
-        expires: Option<DateTime<Utc>>,
     ) -> Self {
         let mut new_response = self;
-        let mut cookie_str = format!("{}={}", key, value);
-        match samesite {
-            "Strict" | "Lax" | "None" => cookie_str.push_str(&format!("; SameSite={}", samesite)),
-            _ => {}
-        }
-        if let Some(max_age) = max_age {
-            cookie_str.push_str(&format!("; Max-Age={}", max_age));
-        } else {
-            cookie_str.push_str(&format!("; Max-Age={}", 60 * 60 * 24 * 30)); // 30 days
+        if let Some(etag) = cache.get(key) {
+            new_response = new_response.header("ETag", &etag);
         }
+        new_response.stream(cache.digest(key, reader))
+    }
 
-        if let Some(expires) = expires {
-            cookie_str.push_str(&format!(
-                "; Expires={}",
-                expires.format("%a, %d %b %Y %H:%M:%S GMT")
-            ));
-        } else {
-            cookie_str.push_str(&format!(
-                "; Expires={}",
-                (Utc::now() + Duration::days(30)).format("%a, %d %b %Y %H:%M:%S GMT")
-            ));
-        }
+    /// Streams a body built with [`SpooledBody`], from memory or its temp file.
+    pub fn spooled(self, body: SpooledBody) -> Result<Self> {
+        Ok(self.stream(body.into_reader()?))
+    }
 
-        if http_only {
-            cookie_str.push_str("; HttpOnly");
+    /// Moves a body longer than `threshold` bytes to a temp file and streams it
+    /// from there, so it isn't held in memory while the client downloads it.
+    /// Should that fail, the body is sent from memory as usual.
+    pub(crate) fn spill_over(self, threshold: usize) -> Self {
+        if self.body.len() <= threshold
+            || self.stream.is_some()
+            || self.encoded_body.is_some()
+            || self.head_only
+        {
+            return self;
         }
 
-        if secure {
-            cookie_str.push_str("; Secure");
+        let mut spooled = SpooledBody::new(0);
+        let spilled = spooled
+            .write_all(self.body.as_bytes())
+            .and_then(|()| spooled.into_reader());
+        match spilled {
+            Ok(reader) => self.stream(reader),
+            Err(e) => {
+                log::error!("Error spilling response body to disk: {}", e);
+                self
+            }
         }
+    }
 
-        new_response.cookies.push(cookie_str);
-        new_response
+    pub fn is_streaming(&self) -> bool {
+        self.stream.is_some()
     }
 
-    pub fn header(self, key: &str, value: &str) -> Self {
-        let mut new_response = self;
-        let key = sanitize_header_key(key);
-        new_response.headers.insert(key, value.to_string());
+    /// Replaces the wire form of `body` with `bytes`, coded as `coding`.
+    #[cfg(feature = "compression")]
+    pub(crate) fn with_encoded_body(self, coding: &str, bytes: Vec<u8>) -> Self {
+        let mut new_response = self.header("Content-Encoding", coding);
+        new_response.encoded_body = Some(bytes);
         new_response
     }
 
-    pub fn headers<const N: usize>(self, headers: [(&str, &str); N]) -> Self {
+    /// Serializes the response for a client that sent a `version` request.
+    /// HTTP/1.0 clients get an `HTTP/1.0` status line, no chunked framing (a
+    /// streamed body is delimited by closing the connection instead) and
+    /// `Connection: close`. Everything else is answered as HTTP/1.1.
+    pub fn for_version(self, version: Version) -> Self {
         let mut new_response = self;
-        for (key, value) in headers {
-            let key = sanitize_header_key(key);
-            new_response.headers.insert(key, value.to_string());
+        new_response.version = match version {
+            Version::HTTP1_0 => Version::HTTP1_0,
+            Version::HTTP1_1 | Version::HTTP2_0 => Version::HTTP1_1,
+        };
+        if new_response.version == Version::HTTP1_0 {
+            new_response = new_response.header("Connection", "close");
         }
         new_response
     }
 
-    pub fn body(self, body: &str) -> Self {
+    /// Sends only the status line and headers, for a `HEAD` request. The headers
+    /// still describe the body, including its `Content-Length`.
+    pub fn head_only(self) -> Self {
         let mut new_response = self;
-        new_response.body = body.to_string();
+        new_response.head_only = true;
         new_response
     }
 
+    /// Whether a streamed body goes out with chunked framing rather than raw.
+    fn is_chunked(&self) -> bool {
+        self.stream.is_some() && self.version != Version::HTTP1_0
+    }
+
     pub fn content_type(self, content_type: &str) -> Self {
         let mut new_response = self;
         new_response.content_type = content_type.to_string();
@@ -144,11 +199,45 @@ impl HttpResponse {
         new_response
     }
 
-    pub fn json<T: Serialize>(self, body: T) -> Self {
+    #[cfg(feature = "json")]
+    /// Serializes `body` as JSON. If serialization fails the error is logged and
+    /// a 500 is returned instead; use [`HttpResponse::try_json`] to handle it yourself.
+    pub fn json<T: serde::Serialize>(self, body: T) -> Self {
+        match self.try_json(body) {
+            Ok(response) => response,
+            Err(e) => {
+                log::error!("Failed to serialize JSON response body: {}", e);
+                HttpResponse::internal_server_error()
+            }
+        }
+    }
+
+    #[cfg(feature = "json")]
+    pub fn try_json<T: serde::Serialize>(self, body: T) -> serde_json::Result<Self> {
+        let body = serde_json::to_string(&body)?;
         let mut new_response = self;
         new_response.content_type = String::from("application/json");
-        new_response.body = serde_json::to_string(&body).unwrap();
-        new_response
+        new_response.body = body;
+        Ok(new_response)
+    }
+
+    #[cfg(feature = "json")]
+    /// JSON body plus a strong `ETag` derived from it, so clients can revalidate
+    /// with `If-None-Match` and get a 304 when nothing changed.
+    pub fn json_with_etag<T: serde::Serialize>(self, body: T) -> Self {
+        self.json(body).with_etag()
+    }
+
+    /// Sets a strong `ETag` computed from the current body.
+    pub fn with_etag(self) -> Self {
+        let etag = strong_etag(&Sha256::digest(self.body.as_bytes()));
+        self.header("ETag", &etag)
+    }
+
+    pub fn get_header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .get(&sanitize_header_key(key))
+            .map(|v| v.as_str())
     }
 
     pub fn text(self, body: &str) -> Self {
@@ -165,15 +254,33 @@ impl HttpResponse {
         new_response
     }
 
+    pub fn flush_immediately(self) -> Self {
+        let mut new_response = self;
+        new_response.flush_mode = FlushMode::Immediate;
+        new_response
+    }
+
+    pub fn buffered(self) -> Self {
+        let mut new_response = self;
+        new_response.flush_mode = FlushMode::Buffered;
+        new_response
+    }
+
+    /// 302 to `url`. Characters that aren't allowed in a URL, such as CR/LF or
+    /// spaces from user input, are percent-encoded so they can't split the header.
     pub fn redirect(self, url: &str) -> Self {
         let mut new_response = self;
         new_response.status_code = 302;
         new_response
             .headers
-            .insert(String::from("Location"), url.to_string());
+            .insert(String::from("Location"), encode_uri(url));
         new_response
     }
 
+    pub fn not_modified() -> Self {
+        Self::new(304)
+    }
+
     pub fn ok() -> Self {
         Self::new(200)
     }
@@ -213,11 +320,145 @@ impl HttpResponse {
     pub fn request_entity_too_large() -> Self {
         Self::new(413)
     }
+
+    pub fn uri_too_long() -> Self {
+        Self::new(414)
+    }
+
+    pub fn request_header_fields_too_large() -> Self {
+        Self::new(431)
+    }
+}
+
+/// Quoted strong entity tag from the first 128 bits of a SHA-256 digest.
+pub(crate) fn strong_etag(digest: &[u8]) -> String {
+    let hex: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("\"{}\"", hex)
+}
+
+impl HttpResponse {
+    /// 1xx, 204 and 304 responses never carry a body.
+    fn has_body(&self) -> bool {
+        !matches!(self.status_code, 100..=199 | 204 | 304)
+    }
+
+    /// Whether body bytes follow the head on the wire.
+    fn sends_body(&self) -> bool {
+        self.has_body() && !self.head_only
+    }
+
+    fn write_head(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}\r\n",
+            self.version.as_str(),
+            self.status_code,
+            get_status_text(self.status_code)
+        )?;
+
+        if self.has_body() {
+            // Content-Type
+            write!(f, "Content-Type: {}\r\n", self.content_type)?;
+
+            // Content-Length, or chunked framing for streamed bodies. HTTP/1.0
+            // streams have neither and end when the connection closes.
+            if self.is_chunked() {
+                f.write_str("Transfer-Encoding: chunked\r\n")?;
+            } else if self.stream.is_none() {
+                let length = self.encoded_body.as_ref().map_or(self.body.len(), Vec::len);
+                write!(f, "Content-Length: {}\r\n", length)?;
+            }
+        }
+
+        // Custom Headers
+        let mut header_keys: Vec<_> = self.headers.keys().collect();
+        header_keys.sort();
+        for key in header_keys {
+            write!(f, "{}: {}\r\n", key, strip_line_breaks(&self.headers[key]))?;
+        }
+
+        // Cookies
+        for cookie in &self.cookies {
+            write!(f, "Set-Cookie: {}\r\n", strip_line_breaks(cookie))?;
+        }
+
+        // Empty line
+        f.write_str("\r\n")
+    }
 }
 
-pub fn write_response(stream: &mut TcpStream, response: HttpResponse) -> Result<()> {
-    stream.write_all(response.to_string().as_bytes())?;
+/// A CR or LF in a header value would end the header early and let the rest
+/// be read as headers of its own.
+fn strip_line_breaks(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains(['\r', '\n']) {
+        value.replace(['\r', '\n'], "").into()
+    } else {
+        value.into()
+    }
+}
+
+/// The serialized response. Streamed and content-coded bodies can only be
+/// produced by [`write_response`], so for those only the head is rendered.
+impl fmt::Display for HttpResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_head(f)?;
+
+        // Body
+        if self.sends_body() && self.stream.is_none() && self.encoded_body.is_none() {
+            f.write_str(&self.body)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn write_response<W: Write>(stream: &mut W, response: HttpResponse) -> Result<()> {
+    write_response_buffered(stream, response, DEFAULT_OUTPUT_BUFFER_SIZE)
+}
+
+/// Writes `response` through an output buffer of `buffer_size` bytes, so the
+/// status line, headers and body reach the stream in as few writes as possible.
+pub fn write_response_buffered<W: Write>(
+    stream: &mut W,
+    response: HttpResponse,
+    buffer_size: usize,
+) -> Result<()> {
+    let mut writer = BufWriter::with_capacity(buffer_size, stream);
+    write!(writer, "{}", response)?;
+
+    if response.sends_body()
+        && let Some(encoded) = &response.encoded_body
+    {
+        writer.write_all(encoded)?;
+    }
+
+    let chunked = response.is_chunked();
+    if response.sends_body()
+        && let Some(BodyStream(mut reader)) = response.stream
+    {
+        let mut chunk = vec![0; buffer_size.max(1)];
+        loop {
+            let read = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if chunked {
+                write!(writer, "{:x}\r\n", read)?;
+                writer.write_all(&chunk[..read])?;
+                writer.write_all(b"\r\n")?;
+            } else {
+                writer.write_all(&chunk[..read])?;
+            }
+            if response.flush_mode == FlushMode::Immediate {
+                writer.flush()?;
+            }
+        }
+        if chunked {
+            writer.write_all(b"0\r\n\r\n")?;
+        }
+    }
 
-    stream.flush()?;
+    writer.flush()?;
     Ok(())
 }
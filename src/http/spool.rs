@@ -0,0 +1,205 @@
+/* Response bodies that spill from memory to a temp file past a size threshold */
+use std::collections::hash_map::RandomState;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_SPILL_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A body written piece by piece, e.g. a large export. It is kept in memory
+/// until it grows past `threshold` bytes, then moved to a temp file, so a
+/// handler's memory stays bounded however large the body gets. Send it with
+/// `HttpResponse::spooled`; the temp file is removed once the response is sent.
+///
+/// ```no_run
+/// # use std::io::{self, Write};
+/// # use schnell::http::{HttpResponse, SpooledBody};
+/// # use schnell::routing::RouteBuilder;
+/// # use schnell::server::Server;
+/// # struct Row { id: u32, name: String }
+/// # let rows = || Vec::<Row>::new();
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// server.get("/export.csv", move |_| -> io::Result<HttpResponse> {
+///     let mut body = SpooledBody::new(1024 * 1024);
+///     for row in rows() {
+///         writeln!(body, "{},{}", row.id, row.name)?;
+///     }
+///     HttpResponse::ok().content_type("text/csv").spooled(body)
+/// });
+/// ```
+pub struct SpooledBody {
+    threshold: usize,
+    storage: Storage,
+}
+
+enum Storage {
+    Memory(Vec<u8>),
+    Disk(BufWriter<SpillFile>),
+}
+
+impl SpooledBody {
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            storage: Storage::Memory(Vec::new()),
+        }
+    }
+
+    /// Whether the body has moved to a temp file.
+    pub fn is_spilled(&self) -> bool {
+        matches!(self.storage, Storage::Disk(_))
+    }
+
+    /// Reads the body back from the start.
+    pub fn into_reader(self) -> io::Result<SpooledReader> {
+        match self.storage {
+            Storage::Memory(bytes) => Ok(SpooledReader::Memory(Cursor::new(bytes))),
+            Storage::Disk(writer) => {
+                let mut file = writer.into_inner().map_err(|e| e.into_error())?;
+                file.file.seek(SeekFrom::Start(0))?;
+                Ok(SpooledReader::Disk(BufReader::new(file)))
+            }
+        }
+    }
+}
+
+impl Write for SpooledBody {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Storage::Memory(bytes) = &self.storage
+            && bytes.len() + buf.len() > self.threshold
+        {
+            let mut writer = BufWriter::new(SpillFile::create()?);
+            writer.write_all(bytes)?;
+            self.storage = Storage::Disk(writer);
+        }
+
+        match &mut self.storage {
+            Storage::Memory(bytes) => bytes.write(buf),
+            Storage::Disk(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.storage {
+            Storage::Memory(_) => Ok(()),
+            Storage::Disk(writer) => writer.flush(),
+        }
+    }
+}
+
+/// A [`SpooledBody`] being read back.
+pub enum SpooledReader {
+    Memory(Cursor<Vec<u8>>),
+    Disk(BufReader<SpillFile>),
+}
+
+impl Read for SpooledReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            SpooledReader::Memory(cursor) => cursor.read(buf),
+            SpooledReader::Disk(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// A temp file that is deleted when dropped.
+pub struct SpillFile {
+    file: File,
+    path: PathBuf,
+}
+
+impl SpillFile {
+    /// Only the owner may open the file, and its name can't be guessed ahead
+    /// of time, so other local users can neither read the body nor plant a
+    /// file (or symlink) where it will be written.
+    fn create() -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "schnell-spill-{}-{}-{:016x}",
+            std::process::id(),
+            NEXT_SPILL_ID.fetch_add(1, Ordering::Relaxed),
+            RandomState::new().build_hasher().finish()
+        ));
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options.open(&path)?;
+        Ok(Self { file, path })
+    }
+}
+
+impl Read for SpillFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Write for SpillFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(body: SpooledBody) -> String {
+        let mut text = String::new();
+        body.into_reader()
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    }
+
+    #[test]
+    fn test_small_bodies_stay_in_memory() {
+        let mut body = SpooledBody::new(16);
+        body.write_all(b"id,name\n").unwrap();
+        assert!(!body.is_spilled());
+        assert_eq!(read_all(body), "id,name\n");
+    }
+
+    #[test]
+    fn test_large_bodies_spill_to_disk() {
+        let mut body = SpooledBody::new(16);
+        for id in 0..100 {
+            writeln!(body, "{},user{}", id, id).unwrap();
+        }
+        assert!(body.is_spilled());
+
+        let Storage::Disk(writer) = &body.storage else {
+            unreachable!()
+        };
+        let path = writer.get_ref().path.clone();
+        assert!(path.exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let mut reader = body.into_reader().unwrap();
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert!(text.starts_with("0,user0\n1,user1\n"));
+        assert!(text.ends_with("99,user99\n"));
+
+        drop(reader);
+        assert!(!path.exists());
+    }
+}
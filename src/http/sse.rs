@@ -0,0 +1,189 @@
+/* Server-Sent Events (text/event-stream) responses */
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use crate::constants::DEFAULT_SSE_PING_INTERVAL_SECS;
+use crate::http::HttpResponse;
+use crate::http::response::FlushMode;
+
+/// Sending half of an SSE response. Every send fails once the client has gone
+/// away; dropping the last `SseStream` ends the response.
+///
+/// ```no_run
+/// # use std::thread;
+/// # use std::time::Duration;
+/// # use schnell::http::HttpResponse;
+/// # use schnell::routing::RouteBuilder;
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// server.get("/ticks", |_| {
+///     let (response, events) = HttpResponse::sse();
+///     thread::spawn(move || {
+///         for tick in 0.. {
+///             if !events.send_event("tick", &tick.to_string()) {
+///                 break;
+///             }
+///             thread::sleep(Duration::from_secs(1));
+///         }
+///     });
+///     response
+/// });
+/// ```
+///
+/// The connection holds on to its worker for as long as the stream is open.
+#[derive(Clone)]
+pub struct SseStream {
+    sender: Sender<String>,
+}
+
+impl SseStream {
+    /// Sends an unnamed event. Returns `false` if the client disconnected.
+    pub fn send(&self, data: &str) -> bool {
+        self.push(format_event(None, None, data))
+    }
+
+    /// Sends an event the client listens for with `addEventListener(event, ...)`.
+    pub fn send_event(&self, event: &str, data: &str) -> bool {
+        self.push(format_event(Some(event), None, data))
+    }
+
+    /// Sends an event with an `id`, which the browser echoes back in
+    /// `Last-Event-ID` when it reconnects.
+    pub fn send_with_id(&self, id: &str, event: Option<&str>, data: &str) -> bool {
+        self.push(format_event(event, Some(id), data))
+    }
+
+    fn push(&self, frame: String) -> bool {
+        self.sender.send(frame).is_ok()
+    }
+}
+
+fn format_event(event: Option<&str>, id: Option<&str>, data: &str) -> String {
+    let mut frame = String::new();
+    if let Some(event) = event {
+        frame.push_str(&format!("event: {}\n", single_line(event)));
+    }
+    if let Some(id) = id {
+        frame.push_str(&format!("id: {}\n", single_line(id)));
+    }
+    // A lone `\r` ends a line for the client too, so it would otherwise let
+    // the data start a field of its own
+    for line in data.split("\r\n").flat_map(|line| line.split(['\r', '\n'])) {
+        frame.push_str(&format!("data: {}\n", line));
+    }
+    frame.push('\n');
+    frame
+}
+
+/// Field values can't span lines without starting a new field.
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+/// Body of an SSE response: events as they are sent, with a comment line
+/// whenever nothing was sent for `ping_interval` to keep proxies from timing
+/// the connection out.
+struct EventReader {
+    receiver: Receiver<String>,
+    ping_interval: Duration,
+    pending: Vec<u8>,
+    offset: usize,
+}
+
+impl Read for EventReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.offset == self.pending.len() {
+            self.pending = match self.receiver.recv_timeout(self.ping_interval) {
+                Ok(frame) => frame.into_bytes(),
+                Err(RecvTimeoutError::Timeout) => b": ping\n\n".to_vec(),
+                Err(RecvTimeoutError::Disconnected) => return Ok(0),
+            };
+            self.offset = 0;
+        }
+
+        let read = buf.len().min(self.pending.len() - self.offset);
+        buf[..read].copy_from_slice(&self.pending[self.offset..self.offset + read]);
+        self.offset += read;
+        Ok(read)
+    }
+}
+
+impl HttpResponse {
+    /// A `text/event-stream` response and the [`SseStream`] that feeds it, pinging
+    /// idle connections every 15 seconds.
+    pub fn sse() -> (Self, SseStream) {
+        Self::sse_with_ping(Duration::from_secs(DEFAULT_SSE_PING_INTERVAL_SECS))
+    }
+
+    pub fn sse_with_ping(ping_interval: Duration) -> (Self, SseStream) {
+        let (sender, receiver) = mpsc::channel();
+        let reader = EventReader {
+            receiver,
+            ping_interval,
+            pending: Vec::new(),
+            offset: 0,
+        };
+
+        let mut response = HttpResponse::ok()
+            .content_type("text/event-stream")
+            .header("Cache-Control", "no-cache")
+            .stream(reader);
+        response.flush_mode = FlushMode::Immediate;
+        (response, SseStream { sender })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::response::write_response;
+
+    #[test]
+    fn test_event_format() {
+        assert_eq!(format_event(None, None, "hello"), "data: hello\n\n");
+        assert_eq!(
+            format_event(Some("update"), Some("7"), "line 1\r\nline 2"),
+            "event: update\nid: 7\ndata: line 1\ndata: line 2\n\n"
+        );
+        assert_eq!(
+            format_event(Some("a\nb"), None, ""),
+            "event: a b\ndata: \n\n"
+        );
+        assert_eq!(
+            format_event(None, None, "a\rid: 99\r\nb\n\rc"),
+            "data: a\ndata: id: 99\ndata: b\ndata: \ndata: c\n\n"
+        );
+    }
+
+    #[test]
+    fn test_sse_response() {
+        let (response, events) = HttpResponse::sse_with_ping(Duration::from_millis(10));
+        assert_eq!(response.content_type, "text/event-stream");
+        assert_eq!(response.flush_mode, FlushMode::Immediate);
+
+        let producer = std::thread::spawn(move || {
+            assert!(events.send("first"));
+            std::thread::sleep(Duration::from_millis(50));
+            assert!(events.send_event("done", "bye"));
+        });
+
+        let mut output = Vec::new();
+        write_response(&mut output, response).unwrap();
+        producer.join().unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(output.contains("data: first\n\n"));
+        assert!(output.contains(": ping\n\n"));
+        assert!(output.contains("event: done\ndata: bye\n\n"));
+        assert!(output.ends_with("0\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_send_fails_after_disconnect() {
+        let (response, events) = HttpResponse::sse();
+        drop(response);
+        assert!(!events.send("anyone there?"));
+    }
+}
@@ -0,0 +1,181 @@
+/* Request path and host normalization: percent-decoding, Unicode NFC and IDNA */
+use std::fmt;
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::utils::{percent_decode_bytes, percent_decode_path};
+
+#[derive(Debug, PartialEq)]
+pub enum UriError {
+    /// A `%` not followed by two hex digits.
+    MalformedEscape,
+    /// The decoded path isn't valid UTF-8, e.g. an overlong encoding like `%C0%AF`.
+    InvalidUtf8,
+}
+
+impl fmt::Display for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UriError::MalformedEscape => "Malformed percent-encoding in path",
+            UriError::InvalidUtf8 => "Path is not valid UTF-8",
+        })
+    }
+}
+
+impl std::error::Error for UriError {}
+
+/// Percent-decodes `path` (keeping `%2F` escaped) and normalizes it to Unicode
+/// NFC, so `/café` matches whether the client sent a precomposed `é` or `e`
+/// followed by a combining accent. Malformed escapes are kept and invalid UTF-8
+/// is replaced; see [`normalize_path_strict`] to reject those instead.
+pub fn normalize_path(path: &str) -> String {
+    percent_decode_path(path).nfc().collect()
+}
+
+/// Like [`normalize_path`], but fails on malformed escapes and invalid or
+/// overlong UTF-8 sequences instead of repairing them.
+pub fn normalize_path_strict(path: &str) -> Result<String, UriError> {
+    let bytes = path.as_bytes();
+    for (i, _) in path.match_indices('%') {
+        let escape = bytes.get(i + 1..i + 3);
+        if !escape.is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) {
+            return Err(UriError::MalformedEscape);
+        }
+    }
+
+    let decoded = String::from_utf8(percent_decode_bytes(path, false, b"/"))
+        .map_err(|_| UriError::InvalidUtf8)?;
+    Ok(decoded.nfc().collect())
+}
+
+/// Normalizes a host name for comparison: lowercased, without a trailing root
+/// dot, and with Unicode labels NFKC-normalized and Punycode-encoded, so
+/// `BÜCHER.example.` and `xn--bcher-kva.example` both become the latter. This
+/// covers the common IDNA cases without the full UTS #46 mapping tables.
+pub fn normalize_host(host: &str) -> String {
+    let host: String = host.trim().to_lowercase().nfkc().collect();
+    let host = host.strip_suffix(['.', '\u{3002}']).unwrap_or(&host);
+    host.split(['.', '\u{3002}'])
+        .map(|label| match label.is_ascii() {
+            true => label.to_string(),
+            false => punycode_encode(label)
+                .map_or_else(|| label.to_string(), |encoded| format!("xn--{}", encoded)),
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+const PUNYCODE_BASE: u32 = 36;
+const PUNYCODE_TMIN: u32 = 1;
+const PUNYCODE_TMAX: u32 = 26;
+
+/// Punycode (RFC 3492) for one label, without the `xn--` prefix. `None` if the
+/// label is too long to encode.
+fn punycode_encode(label: &str) -> Option<String> {
+    let code_points: Vec<u32> = label.chars().map(u32::from).collect();
+    let mut output: String = label.chars().filter(char::is_ascii).collect();
+    let basic = output.len() as u32;
+    if basic > 0 {
+        output.push('-');
+    }
+
+    let (mut n, mut delta, mut bias, mut handled) = (128u32, 0u32, 72u32, basic);
+    while (handled as usize) < code_points.len() {
+        let next = code_points.iter().copied().filter(|&c| c >= n).min()?;
+        delta = delta.checked_add((next - n).checked_mul(handled + 1)?)?;
+        n = next;
+
+        for &c in &code_points {
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c != n {
+                continue;
+            }
+
+            let mut q = delta;
+            let mut k = PUNYCODE_BASE;
+            loop {
+                let t = k.saturating_sub(bias).clamp(PUNYCODE_TMIN, PUNYCODE_TMAX);
+                if q < t {
+                    break;
+                }
+                output.push(punycode_digit(t + (q - t) % (PUNYCODE_BASE - t)));
+                q = (q - t) / (PUNYCODE_BASE - t);
+                k += PUNYCODE_BASE;
+            }
+            output.push(punycode_digit(q));
+            bias = punycode_adapt(delta, handled + 1, handled == basic);
+            delta = 0;
+            handled += 1;
+        }
+        delta += 1;
+        n += 1;
+    }
+    Some(output)
+}
+
+fn punycode_adapt(delta: u32, points: u32, first: bool) -> u32 {
+    let mut delta = if first { delta / 700 } else { delta / 2 };
+    delta += delta / points;
+    let mut k = 0;
+    while delta > ((PUNYCODE_BASE - PUNYCODE_TMIN) * PUNYCODE_TMAX) / 2 {
+        delta /= PUNYCODE_BASE - PUNYCODE_TMIN;
+        k += PUNYCODE_BASE;
+    }
+    k + ((PUNYCODE_BASE - PUNYCODE_TMIN + 1) * delta) / (delta + 38)
+}
+
+fn punycode_digit(digit: u32) -> char {
+    match digit {
+        0..=25 => (b'a' + digit as u8) as char,
+        _ => (b'0' + (digit - 26) as u8) as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nfc_normalization() {
+        let precomposed = normalize_path("/caf%C3%A9");
+        let decomposed = normalize_path("/cafe%CC%81");
+        assert_eq!(precomposed, "/café");
+        assert_eq!(decomposed, precomposed);
+        assert_eq!(normalize_path("/cafe\u{301}"), precomposed);
+        assert_eq!(normalize_path("/a%2Fb"), "/a%2Fb");
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        assert_eq!(normalize_path_strict("/cafe%CC%81").unwrap(), "/café");
+        assert_eq!(
+            normalize_path_strict("/100%"),
+            Err(UriError::MalformedEscape)
+        );
+        assert_eq!(
+            normalize_path_strict("/%zz"),
+            Err(UriError::MalformedEscape)
+        );
+        assert_eq!(normalize_path_strict("/%C0%AF"), Err(UriError::InvalidUtf8));
+        assert_eq!(normalize_path("/%C0%AF"), "/\u{FFFD}\u{FFFD}");
+    }
+
+    #[test]
+    fn test_host_normalization() {
+        assert_eq!(normalize_host("BÜCHER.example"), "xn--bcher-kva.example");
+        assert_eq!(
+            normalize_host("xn--bcher-kva.example."),
+            "xn--bcher-kva.example"
+        );
+        assert_eq!(
+            normalize_host("bu\u{308}cher.example"),
+            "xn--bcher-kva.example"
+        );
+        assert_eq!(normalize_host("München.DE"), "xn--mnchen-3ya.de");
+        assert_eq!(normalize_host("日本語。jp"), "xn--wgv71a119e.jp");
+        assert_eq!(normalize_host("ＥＸＡＭＰＬＥ.com"), "example.com");
+        assert_eq!(normalize_host("10.0.0.5"), "10.0.0.5");
+    }
+}
@@ -1,19 +1,38 @@
+use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Version {
+    HTTP1_0,
     HTTP1_1,
     HTTP2_0,
 }
 
+impl Version {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Version::HTTP1_0 => "HTTP/1.0",
+            Version::HTTP1_1 => "HTTP/1.1",
+            Version::HTTP2_0 => "HTTP/2.0",
+        }
+    }
+}
+
 impl FromStr for Version {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
+            "HTTP/1.0" => Ok(Version::HTTP1_0),
             "HTTP/1.1" => Ok(Version::HTTP1_1),
             "HTTP/2.0" => Ok(Version::HTTP2_0),
             _ => Err(()),
         }
     }
 }
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
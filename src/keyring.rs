@@ -0,0 +1,83 @@
+/* Secret keys for signing tokens and cookies */
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// HMAC-SHA256 signing keys with rotation: new signatures always use the
+/// primary key, while signatures made with retired keys keep verifying until
+/// those keys are dropped from the ring.
+///
+/// ```no_run
+/// # use std::env;
+/// # use schnell::keyring::Keyring;
+/// # fn main() -> Result<(), env::VarError> {
+/// let keyring = Keyring::new(&env::var("SECRET_KEY")?)
+///     .with_retired_key(&env::var("OLD_SECRET_KEY")?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Keyring {
+    keys: Vec<Vec<u8>>,
+}
+
+impl Keyring {
+    pub fn new(primary: impl AsRef<[u8]>) -> Self {
+        Self {
+            keys: vec![primary.as_ref().to_vec()],
+        }
+    }
+
+    /// Accepts signatures made with `key` without signing anything new with it.
+    pub fn with_retired_key(self, key: impl AsRef<[u8]>) -> Self {
+        let mut keyring = self;
+        keyring.keys.push(key.as_ref().to_vec());
+        keyring
+    }
+
+    /// HMAC-SHA256 of `payload` under the primary key.
+    pub fn sign(&self, payload: &[u8]) -> Vec<u8> {
+        let mut mac = Self::mac(&self.keys[0]);
+        mac.update(payload);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    /// Checks `signature` against every key in the ring, in constant time.
+    pub fn verify(&self, payload: &[u8], signature: &[u8]) -> bool {
+        self.keys.iter().any(|key| {
+            let mut mac = Self::mac(key);
+            mac.update(payload);
+            mac.verify_slice(signature).is_ok()
+        })
+    }
+
+    fn mac(key: &[u8]) -> HmacSha256 {
+        HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length")
+    }
+}
+
+/// Keys are never printed.
+impl std::fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Keyring {{ keys: {} }}", self.keys.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        let old = Keyring::new("old secret");
+        let signature = old.sign(b"payload");
+
+        let rotated = Keyring::new("new secret").with_retired_key("old secret");
+        assert!(rotated.verify(b"payload", &signature));
+        assert!(!rotated.verify(b"tampered", &signature));
+        assert_ne!(rotated.sign(b"payload"), signature);
+        assert!(!old.verify(b"payload", &rotated.sign(b"payload")));
+        assert_eq!(format!("{:?}", rotated), "Keyring { keys: 2 }");
+    }
+}
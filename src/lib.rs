@@ -1,5 +1,19 @@
+pub mod bus;
 pub mod constants;
 pub mod http;
+pub mod keyring;
+pub mod middleware;
+pub mod redact;
+#[cfg(feature = "sanitize")]
+pub mod sanitize;
+pub mod testing;
+pub mod tokens;
 pub mod utils;
+pub mod webhooks;
 
 pub mod routing;
+pub mod server;
+
+#[cfg(feature = "autoload")]
+#[doc(hidden)]
+pub use inventory;
@@ -0,0 +1,330 @@
+use crate::http::{HttpResponse, Request};
+use crate::middleware::{Middleware, Next};
+use crate::utils::base64_decode;
+
+type BasicVerifier = Box<dyn Fn(&str, &str) -> bool + Send + Sync>;
+type BearerVerifier<C> = Box<dyn Fn(&str) -> Option<C> + Send + Sync>;
+type ApiKeyVerifier<C> = Box<dyn Fn(&str) -> Option<C> + Send + Sync>;
+
+/// HTTP Basic authentication. Requests without valid credentials get a 401
+/// with a `WWW-Authenticate` challenge. Register it with `use_middleware` to
+/// guard every route, or `wrap` individual handlers:
+///
+/// ```no_run
+/// # use schnell::http::{HttpResponse, Request};
+/// # use schnell::middleware::BasicAuth;
+/// # use schnell::routing::RouteBuilder;
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// # fn secret() -> &'static str { "hunter2" }
+/// # let stats = |_: &Request| Ok(HttpResponse::ok());
+/// let admin = BasicAuth::new("admin", |user, pass| user == "root" && pass == secret());
+/// server.get("/admin/stats", admin.wrap(stats));
+/// ```
+pub struct BasicAuth {
+    realm: String,
+    verify: BasicVerifier,
+}
+
+impl BasicAuth {
+    pub fn new<F>(realm: &str, verify: F) -> Self
+    where
+        F: Fn(&str, &str) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            realm: realm.to_string(),
+            verify: Box::new(verify),
+        }
+    }
+
+    /// User name and password from the `Authorization` header, if well-formed.
+    pub fn credentials(request: &Request) -> Option<(String, String)> {
+        let encoded = auth_param(request, "Basic")?;
+        let decoded = String::from_utf8(base64_decode(encoded)?).ok()?;
+        let (user, pass) = decoded.split_once(':')?;
+        Some((user.to_string(), pass.to_string()))
+    }
+
+    /// The 401 to send if `request` isn't authenticated.
+    pub fn check(&self, request: &Request) -> Option<HttpResponse> {
+        match Self::credentials(request) {
+            Some((user, pass)) if (self.verify)(&user, &pass) => None,
+            _ => Some(HttpResponse::unauthorized().header(
+                "WWW-Authenticate",
+                &format!("Basic realm=\"{}\", charset=\"UTF-8\"", self.realm),
+            )),
+        }
+    }
+
+    /// Wraps `handler` so it only runs for authenticated requests.
+    pub fn wrap<H>(
+        self,
+        handler: H,
+    ) -> impl Fn(&Request) -> std::io::Result<HttpResponse> + Send + Sync + 'static
+    where
+        H: Fn(&Request) -> std::io::Result<HttpResponse> + Send + Sync + 'static,
+    {
+        move |request| match self.check(request) {
+            Some(rejection) => Ok(rejection),
+            None => handler(request),
+        }
+    }
+}
+
+impl Middleware for BasicAuth {
+    fn handle(&self, request: &mut Request, next: Next) -> std::io::Result<HttpResponse> {
+        match self.check(request) {
+            Some(rejection) => Ok(rejection),
+            None => next.run(request),
+        }
+    }
+}
+
+/// Bearer token authentication. `verify` maps a token to its claims (user id,
+/// scopes, ...), or `None` to reject it with a 401. Wrapped handlers receive
+/// the claims; as middleware, only the check is applied.
+///
+/// ```no_run
+/// # use std::collections::HashMap;
+/// # use schnell::http::HttpResponse;
+/// # use schnell::middleware::BearerAuth;
+/// # use schnell::routing::RouteBuilder;
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// # let sessions: HashMap<String, String> = HashMap::new();
+/// let api = BearerAuth::new("api", move |token| sessions.get(token).cloned());
+/// server.get("/me", api.wrap(|_, user: &String| Ok(HttpResponse::ok().text(user))));
+/// ```
+pub struct BearerAuth<C> {
+    realm: String,
+    verify: BearerVerifier<C>,
+}
+
+impl<C: 'static> BearerAuth<C> {
+    pub fn new<F>(realm: &str, verify: F) -> Self
+    where
+        F: Fn(&str) -> Option<C> + Send + Sync + 'static,
+    {
+        Self {
+            realm: realm.to_string(),
+            verify: Box::new(verify),
+        }
+    }
+
+    /// The token from the `Authorization` header.
+    pub fn token(request: &Request) -> Option<&str> {
+        auth_param(request, "Bearer").filter(|token| !token.is_empty())
+    }
+
+    /// The token's claims, or `None` if it is missing or rejected by `verify`.
+    pub fn claims(&self, request: &Request) -> Option<C> {
+        (self.verify)(Self::token(request)?)
+    }
+
+    /// Wraps `handler` so it only runs for authenticated requests, with their claims.
+    pub fn wrap<H>(
+        self,
+        handler: H,
+    ) -> impl Fn(&Request) -> std::io::Result<HttpResponse> + Send + Sync + 'static
+    where
+        H: Fn(&Request, &C) -> std::io::Result<HttpResponse> + Send + Sync + 'static,
+    {
+        move |request| match self.claims(request) {
+            Some(claims) => handler(request, &claims),
+            None => Ok(self.challenge(request)),
+        }
+    }
+
+    /// The 401 for a request without valid claims, flagging a token that was
+    /// sent but rejected as `invalid_token`.
+    fn challenge(&self, request: &Request) -> HttpResponse {
+        let mut challenge = format!("Bearer realm=\"{}\"", self.realm);
+        if Self::token(request).is_some() {
+            challenge.push_str(", error=\"invalid_token\"");
+        }
+        HttpResponse::unauthorized().header("WWW-Authenticate", &challenge)
+    }
+}
+
+impl<C: 'static> Middleware for BearerAuth<C> {
+    fn handle(&self, request: &mut Request, next: Next) -> std::io::Result<HttpResponse> {
+        match self.claims(request) {
+            Some(_) => next.run(request),
+            None => Ok(self.challenge(request)),
+        }
+    }
+}
+
+/// API key authentication. The key is read from a header (`X-Api-Key` by
+/// default) or, if configured, a query parameter. `verify` maps a key to its
+/// scopes or tenant, or `None` to reject it with a 401. Wrapped handlers
+/// receive what `verify` returned; as middleware, only the check is applied.
+pub struct ApiKeyAuth<C> {
+    header: String,
+    query_param: Option<String>,
+    verify: ApiKeyVerifier<C>,
+}
+
+impl<C: 'static> ApiKeyAuth<C> {
+    pub fn new<F>(verify: F) -> Self
+    where
+        F: Fn(&str) -> Option<C> + Send + Sync + 'static,
+    {
+        Self {
+            header: "x-api-key".to_string(),
+            query_param: None,
+            verify: Box::new(verify),
+        }
+    }
+
+    /// Reads the key from `header` in place of `X-Api-Key`.
+    pub fn header(self, header: &str) -> Self {
+        let mut auth = self;
+        auth.header = header.to_lowercase();
+        auth
+    }
+
+    /// Also accepts the key as the query parameter `name`, for clients that
+    /// can't set headers. The header wins if both are sent.
+    pub fn query_param(self, name: &str) -> Self {
+        let mut auth = self;
+        auth.query_param = Some(name.to_string());
+        auth
+    }
+
+    /// The key sent with `request`.
+    pub fn key<'a>(&self, request: &'a Request) -> Option<&'a str> {
+        let from_header = request.headers.get(&self.header).map(|key| key.trim());
+        from_header
+            .or_else(|| request.query_param(self.query_param.as_deref()?))
+            .filter(|key| !key.is_empty())
+    }
+
+    /// What `verify` returned for the request's key, or `None` if it is
+    /// missing or unknown.
+    pub fn scopes(&self, request: &Request) -> Option<C> {
+        (self.verify)(self.key(request)?)
+    }
+
+    /// Wraps `handler` so it only runs for requests with a known key, with its scopes.
+    pub fn wrap<H>(
+        self,
+        handler: H,
+    ) -> impl Fn(&Request) -> std::io::Result<HttpResponse> + Send + Sync + 'static
+    where
+        H: Fn(&Request, &C) -> std::io::Result<HttpResponse> + Send + Sync + 'static,
+    {
+        move |request| match self.scopes(request) {
+            Some(scopes) => handler(request, &scopes),
+            None => Ok(HttpResponse::unauthorized()),
+        }
+    }
+}
+
+impl<C: 'static> Middleware for ApiKeyAuth<C> {
+    fn handle(&self, request: &mut Request, next: Next) -> std::io::Result<HttpResponse> {
+        match self.scopes(request) {
+            Some(_) => next.run(request),
+            None => Ok(HttpResponse::unauthorized()),
+        }
+    }
+}
+
+/// The credentials of an `Authorization: <scheme> <credentials>` header.
+fn auth_param<'a>(request: &'a Request, scheme: &str) -> Option<&'a str> {
+    let (given, credentials) = request
+        .headers
+        .get("authorization")?
+        .trim()
+        .split_once(' ')?;
+    given
+        .eq_ignore_ascii_case(scheme)
+        .then(|| credentials.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn request(authorization: &str) -> Request {
+        let raw = format!("GET / HTTP/1.1\r\nAuthorization: {}\r\n\r\n", authorization);
+        let mut buffer = BufReader::new(raw.as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    #[test]
+    fn test_basic_auth() {
+        let auth = BasicAuth::new("admin", |user, pass| {
+            user == "Aladdin" && pass == "open sesame"
+        });
+        let handler = auth.wrap(|_| Ok(HttpResponse::ok()));
+
+        // "Aladdin:open sesame"
+        let ok = request("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+        assert_eq!(handler(&ok).unwrap().status_code, 200);
+
+        // Scheme is case-insensitive, padding optional
+        assert_eq!(
+            handler(&request("basic QWxhZGRpbjpvcGVuIHNlc2FtZQ"))
+                .unwrap()
+                .status_code,
+            200
+        );
+        let wrong = request("Basic QWxhZGRpbjpzZXNhbWU="); // "Aladdin:sesame"
+        let response = handler(&wrong).unwrap();
+        assert_eq!(response.status_code, 401);
+        assert_eq!(
+            response.headers["Www-Authenticate"],
+            "Basic realm=\"admin\", charset=\"UTF-8\""
+        );
+
+        assert_eq!(
+            BasicAuth::credentials(&request("Basic dTpwOnc=")),
+            Some(("u".to_string(), "p:w".to_string()))
+        );
+        assert_eq!(BasicAuth::credentials(&request("Bearer dTpwOnc=")), None);
+        // URL-safe characters aren't part of the standard alphabet
+        assert_eq!(BasicAuth::credentials(&request("Basic dTpw-w==")), None);
+    }
+
+    #[test]
+    fn test_bearer_auth() {
+        let auth = BearerAuth::new("api", |token| (token == "t0ken").then_some(42u32));
+        let handler = auth.wrap(|_, user_id| Ok(HttpResponse::ok().text(&user_id.to_string())));
+
+        assert_eq!(handler(&request("Bearer t0ken")).unwrap().body, "42");
+
+        let response = handler(&request("Bearer nope")).unwrap();
+        assert_eq!(response.status_code, 401);
+        assert_eq!(
+            response.headers["Www-Authenticate"],
+            "Bearer realm=\"api\", error=\"invalid_token\""
+        );
+
+        let response = handler(&request("Basic dTpwOnc=")).unwrap();
+        assert_eq!(response.headers["Www-Authenticate"], "Bearer realm=\"api\"");
+    }
+
+    #[test]
+    fn test_api_key_auth() {
+        let auth =
+            ApiKeyAuth::new(|key| (key == "k3y").then_some(vec!["read"])).query_param("api_key");
+        let handler = auth.wrap(|_, scopes| Ok(HttpResponse::ok().text(&scopes.join(","))));
+        let call = |raw: &str| {
+            let mut buffer = BufReader::new(raw.as_bytes());
+            handler(&Request::read(&mut buffer).unwrap()).unwrap()
+        };
+
+        assert_eq!(
+            call("GET / HTTP/1.1\r\nX-Api-Key: k3y\r\n\r\n").body,
+            "read"
+        );
+        assert_eq!(call("GET /?api_key=k3y HTTP/1.1\r\n\r\n").body, "read");
+        assert_eq!(
+            call("GET /?api_key=k3y HTTP/1.1\r\nX-Api-Key: nope\r\n\r\n").status_code,
+            401
+        );
+        assert_eq!(call("GET / HTTP/1.1\r\n\r\n").status_code, 401);
+    }
+}
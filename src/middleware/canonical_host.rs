@@ -0,0 +1,218 @@
+use crate::http::uri::normalize_host;
+use crate::http::{HttpResponse, Request};
+use crate::middleware::{Middleware, Next};
+use crate::utils::split_host_port;
+
+/// Redirects (301) every request that doesn't arrive on the preferred host and
+/// scheme, keeping the path and query string. Excluded path prefixes, such as
+/// health checks or ACME challenges, are served wherever they arrive.
+///
+/// ```no_run
+/// # use schnell::middleware::CanonicalHost;
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// server.use_middleware(
+///     CanonicalHost::new("www.example.com")
+///         .https()
+///         .exclude("/healthz")
+///         .exclude("/.well-known/acme-challenge/"),
+/// );
+/// ```
+pub struct CanonicalHost {
+    host: String,
+    https: bool,
+    trust_forwarded_proto: bool,
+    excluded: Vec<String>,
+}
+
+impl CanonicalHost {
+    /// `host` may include a port, e.g. `example.com:8443`. Internationalized
+    /// names are matched and redirected to in their Punycode form.
+    pub fn new(host: &str) -> Self {
+        Self {
+            host: normalize_authority(host).unwrap_or_else(|| host.to_ascii_lowercase()),
+            https: false,
+            trust_forwarded_proto: false,
+            excluded: Vec::new(),
+        }
+    }
+
+    /// Also redirect plain HTTP requests to HTTPS.
+    pub fn https(self) -> Self {
+        let mut canonical = self;
+        canonical.https = true;
+        canonical
+    }
+
+    /// Take the scheme from `X-Forwarded-Proto`, for servers behind a TLS-terminating proxy.
+    pub fn trust_forwarded_proto(self) -> Self {
+        let mut canonical = self;
+        canonical.trust_forwarded_proto = true;
+        canonical
+    }
+
+    /// Never redirect requests whose path starts with `prefix`.
+    pub fn exclude(self, prefix: &str) -> Self {
+        let mut canonical = self;
+        canonical.excluded.push(prefix.to_string());
+        canonical
+    }
+
+    /// Where `request` should have been sent, or `None` if it's already canonical.
+    pub fn redirect_target(&self, request: &Request) -> Option<String> {
+        if self
+            .excluded
+            .iter()
+            .any(|prefix| request.path.starts_with(prefix.as_str()))
+        {
+            return None;
+        }
+
+        let host_matches = request
+            .headers
+            .get("host")
+            .and_then(|host| normalize_authority(host))
+            .is_some_and(|host| host == self.host);
+        if host_matches && (!self.https || self.is_https(request)) {
+            return None;
+        }
+
+        let scheme = if self.https || self.is_https(request) {
+            "https"
+        } else {
+            "http"
+        };
+        Some(format!(
+            "{}://{}{}",
+            scheme,
+            self.host,
+            request.origin_target()
+        ))
+    }
+
+    fn is_https(&self, request: &Request) -> bool {
+        request.is_secure()
+            || (self.trust_forwarded_proto
+                && request
+                    .headers
+                    .get("x-forwarded-proto")
+                    .is_some_and(|proto| proto.trim().eq_ignore_ascii_case("https")))
+    }
+}
+
+/// `host[:port]` with the host normalized, so `BÜCHER.example.` and
+/// `xn--bcher-kva.example` compare equal.
+fn normalize_authority(value: &str) -> Option<String> {
+    let (host, port) = split_host_port(value)?;
+    let host = match normalize_host(host) {
+        host if host.contains(':') => format!("[{}]", host),
+        host => host,
+    };
+    Some(match port {
+        Some(port) => format!("{}:{}", host, port),
+        None => host,
+    })
+}
+
+impl Middleware for CanonicalHost {
+    fn handle(&self, request: &mut Request, next: Next) -> std::io::Result<HttpResponse> {
+        match self.redirect_target(request) {
+            Some(location) => Ok(HttpResponse::new(301).header("Location", &location)),
+            None => next.run(request),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::request::RequestError;
+    use std::io::BufReader;
+
+    fn request(target: &str, headers: &str) -> Request {
+        let raw = format!("GET {} HTTP/1.1\r\n{}\r\n", target, headers);
+        let mut buffer = BufReader::new(raw.as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    #[test]
+    fn test_redirects_other_hosts() {
+        let canonical = CanonicalHost::new("www.example.com");
+
+        assert_eq!(
+            canonical.redirect_target(&request("/a%20b?q=1+2", "Host: example.com\r\n")),
+            Some("http://www.example.com/a%20b?q=1+2".to_string())
+        );
+        assert_eq!(
+            canonical.redirect_target(&request("/", "Host: WWW.Example.com\r\n")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_internationalized_hosts() {
+        let canonical = CanonicalHost::new("BÜCHER.example:8443");
+
+        for host in ["xn--bcher-kva.example:8443", "bücher.example.:8443"] {
+            let matching = request("/", &format!("Host: {}\r\n", host));
+            assert_eq!(canonical.redirect_target(&matching), None);
+        }
+        assert_eq!(
+            canonical.redirect_target(&request("/", "Host: bucher.example:8443\r\n")),
+            Some("http://xn--bcher-kva.example:8443/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_https_and_forwarded_proto() {
+        let canonical = CanonicalHost::new("example.com").https();
+        let plain = request(
+            "/login",
+            "Host: example.com\r\nX-Forwarded-Proto: https\r\n",
+        );
+        assert_eq!(
+            canonical.redirect_target(&plain),
+            Some("https://example.com/login".to_string())
+        );
+
+        let mut secure = request("/login", "Host: example.com\r\n");
+        secure.set_secure(true);
+        assert_eq!(canonical.redirect_target(&secure), None);
+
+        let proxied = canonical.trust_forwarded_proto();
+        assert_eq!(proxied.redirect_target(&plain), None);
+    }
+
+    #[test]
+    fn test_excluded_paths() {
+        let canonical = CanonicalHost::new("example.com")
+            .https()
+            .exclude("/healthz")
+            .exclude("/.well-known/acme-challenge/");
+
+        let acme = request("/.well-known/acme-challenge/token", "Host: 10.0.0.5\r\n");
+        assert_eq!(canonical.redirect_target(&acme), None);
+        assert_eq!(
+            canonical.redirect_target(&request("/", "")),
+            Some("https://example.com/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_location_stays_on_canonical_host() {
+        let canonical = CanonicalHost::new("example.com");
+
+        let raw = "GET @evil.com/x HTTP/1.1\r\nHost: other.com\r\n\r\n";
+        let mut buffer = BufReader::new(raw.as_bytes());
+        assert!(matches!(
+            Request::read(&mut buffer),
+            Err(RequestError::ParseError)
+        ));
+
+        let absolute = request("http://other.com/x?y=1", "Host: other.com\r\n");
+        assert_eq!(
+            canonical.redirect_target(&absolute),
+            Some("http://example.com/x?y=1".to_string())
+        );
+    }
+}
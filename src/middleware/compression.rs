@@ -0,0 +1,310 @@
+use std::io::Write;
+
+use flate2::Compression as Level;
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+use crate::http::{HttpResponse, Request};
+use crate::middleware::{Middleware, Next};
+
+/// Media types whose formats are compressed already, so gzip only costs CPU.
+const PRECOMPRESSED_TYPES: &[&str] = &[
+    "image/*",
+    "video/*",
+    "audio/*",
+    "font/woff",
+    "font/woff2",
+    "application/zip",
+    "application/gzip",
+    "application/x-gzip",
+    "application/x-bzip2",
+    "application/x-7z-compressed",
+    "application/x-rar-compressed",
+    "application/zstd",
+];
+
+/// Compresses response bodies of at least `min_size` bytes with gzip or
+/// deflate, whichever the client's `Accept-Encoding` allows (gzip preferred).
+/// Installed with `Server::enable_compression`.
+///
+/// Media types that are compressed already (`image/*` except SVG, `video/*`,
+/// `application/zip`, ...) are sent as they are, unless `content_types`
+/// narrows compression to an explicit list.
+///
+/// ```no_run
+/// # use schnell::middleware::Compression;
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// server.use_middleware(
+///     Compression::new(512)
+///         .level(9)
+///         .content_types(&["text/*", "application/json", "image/svg+xml"]),
+/// );
+/// ```
+pub struct Compression {
+    pub min_size: usize,
+    level: u32,
+    content_types: Option<Vec<String>>,
+}
+
+impl Compression {
+    pub fn new(min_size: usize) -> Self {
+        Self {
+            min_size,
+            level: Level::default().level(),
+            content_types: None,
+        }
+    }
+
+    /// Compression level from 0 (none) to 9 (smallest output, slowest).
+    /// Defaults to 6.
+    pub fn level(self, level: u32) -> Self {
+        let mut compression = self;
+        compression.level = level.min(9);
+        compression
+    }
+
+    /// Only compresses responses of these media types. `type/*` matches a
+    /// whole top-level type.
+    pub fn content_types(self, content_types: &[&str]) -> Self {
+        let mut compression = self;
+        compression.content_types = Some(
+            content_types
+                .iter()
+                .map(|t| t.to_ascii_lowercase())
+                .collect(),
+        );
+        compression
+    }
+
+    pub fn compress(&self, request: &Request, response: HttpResponse) -> HttpResponse {
+        if response.body.len() < self.min_size
+            || response.is_streaming()
+            || response.get_header("Content-Encoding").is_some()
+            || !self.compresses(&response.content_type)
+        {
+            return response;
+        }
+
+        let accept_encoding = request
+            .headers
+            .get("accept-encoding")
+            .map_or("", |v| v.as_str());
+        let Some(coding) = negotiate(accept_encoding) else {
+            return response;
+        };
+        let encoded = match encode(coding, response.body.as_bytes(), Level::new(self.level)) {
+            Ok(encoded) => encoded,
+            Err(_) => return response,
+        };
+
+        let vary = match response.get_header("Vary") {
+            Some(vary) => format!("{}, Accept-Encoding", vary),
+            None => "Accept-Encoding".to_string(),
+        };
+        let mut response = response.header("Vary", &vary);
+
+        // The coded bytes differ from the identity body, so a strong ETag no
+        // longer holds; a weak one still revalidates through If-None-Match.
+        if let Some(etag) = response.get_header("ETag")
+            && !etag.starts_with("W/")
+        {
+            let weak = format!("W/{}", etag);
+            response = response.header("ETag", &weak);
+        }
+
+        response.with_encoded_body(coding, encoded)
+    }
+}
+
+impl Compression {
+    fn compresses(&self, content_type: &str) -> bool {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        match &self.content_types {
+            Some(allowed) => allowed
+                .iter()
+                .any(|pattern| matches_type(pattern, &essence)),
+            None => {
+                essence == "image/svg+xml"
+                    || !PRECOMPRESSED_TYPES
+                        .iter()
+                        .any(|pattern| matches_type(pattern, &essence))
+            }
+        }
+    }
+}
+
+fn matches_type(pattern: &str, essence: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(top) => essence
+            .split_once('/')
+            .is_some_and(|(essence_top, _)| essence_top == top),
+        None => pattern == essence,
+    }
+}
+
+impl Middleware for Compression {
+    fn handle(&self, request: &mut Request, next: Next) -> std::io::Result<HttpResponse> {
+        let response = next.run(request)?;
+        Ok(self.compress(request, response))
+    }
+}
+
+/// Picks `gzip` or `deflate` from an `Accept-Encoding` value, honouring
+/// `q=0` exclusions and `*`.
+fn negotiate(accept_encoding: &str) -> Option<&'static str> {
+    let quality = |coding: &str| {
+        let mut wildcard = None;
+        for entry in accept_encoding.split(',') {
+            let mut params = entry.split(';');
+            let name = params.next().unwrap_or("").trim();
+            let q = params
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(1.0, |q| q.trim().parse::<f32>().unwrap_or(0.0));
+            if name.eq_ignore_ascii_case(coding) {
+                return q;
+            }
+            if name == "*" {
+                wildcard = Some(q);
+            }
+        }
+        wildcard.unwrap_or(0.0)
+    };
+
+    let (gzip, deflate) = (quality("gzip"), quality("deflate"));
+    if gzip > 0.0 && gzip >= deflate {
+        Some("gzip")
+    } else if deflate > 0.0 {
+        Some("deflate")
+    } else {
+        None
+    }
+}
+
+fn encode(coding: &str, body: &[u8], level: Level) -> std::io::Result<Vec<u8>> {
+    match coding {
+        "gzip" => {
+            let mut encoder = GzEncoder::new(Vec::new(), level);
+            encoder.write_all(body)?;
+            encoder.finish()
+        }
+        _ => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), level);
+            encoder.write_all(body)?;
+            encoder.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::response::write_response;
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    use std::io::{BufReader, Read};
+
+    fn request(accept_encoding: &str) -> Request {
+        let raw = format!(
+            "GET / HTTP/1.1\r\nAccept-Encoding: {}\r\n\r\n",
+            accept_encoding
+        );
+        let mut buffer = BufReader::new(raw.as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    fn body_of(response: HttpResponse) -> Vec<u8> {
+        let mut output = Vec::new();
+        write_response(&mut output, response).unwrap();
+        let split = output.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        output[split + 4..].to_vec()
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(negotiate("gzip, deflate, br"), Some("gzip"));
+        assert_eq!(negotiate("deflate"), Some("deflate"));
+        assert_eq!(negotiate("gzip;q=0.5, deflate"), Some("deflate"));
+        assert_eq!(negotiate("gzip;q=0, *"), Some("deflate"));
+        assert_eq!(negotiate("*;q=0.1"), Some("gzip"));
+        assert_eq!(negotiate("br, identity"), None);
+        assert_eq!(negotiate(""), None);
+    }
+
+    #[test]
+    fn test_gzip_round_trip() {
+        let text = "hello compression ".repeat(100);
+        let response = Compression::new(1024)
+            .compress(&request("gzip"), HttpResponse::ok().text(&text).with_etag());
+
+        assert_eq!(response.get_header("Content-Encoding"), Some("gzip"));
+        assert_eq!(response.get_header("Vary"), Some("Accept-Encoding"));
+        assert!(response.get_header("ETag").unwrap().starts_with("W/\""));
+
+        let head = response.to_string();
+        let body = body_of(response);
+        assert!(head.contains(&format!("Content-Length: {}\r\n", body.len())));
+        assert!(body.len() < text.len());
+
+        let mut decoded = String::new();
+        GzDecoder::new(body.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, text);
+    }
+
+    #[test]
+    fn test_deflate_and_skips() {
+        let text = "x".repeat(2048);
+        let compression = Compression::new(1024);
+
+        let response = compression.compress(&request("deflate"), HttpResponse::ok().text(&text));
+        let mut decoded = String::new();
+        ZlibDecoder::new(body_of(response).as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, text);
+
+        let small = compression.compress(&request("gzip"), HttpResponse::ok().text("tiny"));
+        assert_eq!(small.get_header("Content-Encoding"), None);
+
+        let refused = compression.compress(&request("identity"), HttpResponse::ok().text(&text));
+        assert_eq!(body_of(refused), text.as_bytes());
+    }
+
+    #[test]
+    fn test_content_types_and_level() {
+        let text = "x".repeat(2048);
+        let compressed = |compression: &Compression, content_type: &str| {
+            let response = HttpResponse::ok().body(&text).content_type(content_type);
+            compression
+                .compress(&request("gzip"), response)
+                .get_header("Content-Encoding")
+                .is_some()
+        };
+
+        let defaults = Compression::new(1024);
+        assert!(compressed(&defaults, "text/html; charset=utf-8"));
+        assert!(compressed(&defaults, "image/svg+xml"));
+        assert!(!compressed(&defaults, "image/png"));
+        assert!(!compressed(&defaults, "video/mp4"));
+        assert!(!compressed(&defaults, "application/zip"));
+
+        let allow_list = Compression::new(1024).content_types(&["text/*", "application/json"]);
+        assert!(compressed(&allow_list, "text/css"));
+        assert!(compressed(&allow_list, "Application/JSON"));
+        assert!(!compressed(&allow_list, "application/javascript"));
+
+        let fast = Compression::new(1024).level(1);
+        let best = Compression::new(1024).level(42);
+        assert_eq!(best.level, 9);
+        let text = "hello compression, again and again ".repeat(200);
+        let size = |compression: &Compression| {
+            body_of(compression.compress(&request("gzip"), HttpResponse::ok().text(&text))).len()
+        };
+        assert!(size(&best) <= size(&fast));
+    }
+}
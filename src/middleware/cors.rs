@@ -0,0 +1,401 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::http::{HttpMethod, HttpResponse, Request};
+use crate::middleware::{Middleware, Next};
+
+/// Cross-origin resource sharing policy, installed with `Server::with_cors`.
+///
+/// ```no_run
+/// # use schnell::middleware::CorsConfig;
+/// # use schnell::server::Server;
+/// let server = Server::new("0.0.0.0", 8080, None).with_cors(CorsConfig {
+///     allowed_origins: vec![
+///         "https://app.example.com".to_string(),
+///         "https://*.preview.example.com".to_string(),
+///     ],
+///     credentials: true,
+///     ..CorsConfig::default()
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// Exact origins (`https://app.example.com`), subdomain patterns
+    /// (`https://*.example.com`, which doesn't match `https://example.com`
+    /// itself), or `"*"` for any origin.
+    pub allowed_origins: Vec<String>,
+    /// Decides about origins `allowed_origins` doesn't list, e.g. by looking
+    /// them up in a tenant table.
+    pub origin_validator: Option<OriginValidator>,
+    /// Methods preflights may ask for. Under a `Server`, narrowed per path to
+    /// the methods actually routed there.
+    pub methods: Vec<HttpMethod>,
+    /// Request headers preflights may ask for. When empty, whatever the
+    /// preflight asks for is allowed.
+    pub headers: Vec<String>,
+    /// How long browsers may cache a preflight result.
+    pub max_age: Option<Duration>,
+    /// Allow cookies and `Authorization`. The matching origin is echoed back, as
+    /// browsers require. Can't be combined with `"*"`, which would let any site
+    /// make authenticated requests; `Server::with_cors` refuses to start with
+    /// that configuration, and the `"*"` entry matches nothing.
+    pub credentials: bool,
+    /// Also send `Timing-Allow-Origin` to allowed origins, so their scripts can
+    /// read detailed Resource Timing and `Server-Timing` metrics for responses.
+    pub timing_allow_origin: bool,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_string()],
+            origin_validator: None,
+            methods: vec![
+                HttpMethod::GET,
+                HttpMethod::HEAD,
+                HttpMethod::POST,
+                HttpMethod::PUT,
+                HttpMethod::PATCH,
+                HttpMethod::DELETE,
+            ],
+            headers: Vec::new(),
+            max_age: None,
+            credentials: false,
+            timing_allow_origin: false,
+        }
+    }
+}
+
+/// Callback for [`CorsConfig::origin_validator`], given the request's `Origin`.
+#[derive(Clone)]
+pub struct OriginValidator(Arc<dyn Fn(&str) -> bool + Send + Sync>);
+
+impl OriginValidator {
+    pub fn new<F>(validator: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        Self(Arc::new(validator))
+    }
+}
+
+impl fmt::Debug for OriginValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OriginValidator(..)")
+    }
+}
+
+impl CorsConfig {
+    /// Rejects `credentials` combined with the `"*"` origin.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.credentials && self.allows_any_origin() {
+            return Err(
+                "CORS credentials can't be allowed for the \"*\" origin; list the origins instead",
+            );
+        }
+        Ok(())
+    }
+
+    fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|a| a == "*")
+    }
+
+    /// Whether the CORS headers differ between origins, so caches must key
+    /// responses on `Origin`.
+    fn varies_by_origin(&self) -> bool {
+        self.credentials
+            || self.origin_validator.is_some()
+            || self.allowed_origins.iter().any(|a| a != "*")
+    }
+
+    fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|allowed| (allowed == "*" && !self.credentials) || origin_matches(allowed, origin))
+            || self
+                .origin_validator
+                .as_ref()
+                .is_some_and(|validator| (validator.0)(origin))
+    }
+
+    /// Value for `Access-Control-Allow-Origin`.
+    fn allow_origin<'a>(&self, origin: &'a str) -> &'a str {
+        if !self.credentials && self.allows_any_origin() {
+            "*"
+        } else {
+            origin
+        }
+    }
+
+    /// Only advertises configured methods that are also routed for the path,
+    /// so preflights never promise a method that would then get a 405.
+    fn preflight(&self, request: &Request, origin: &str) -> HttpResponse {
+        let methods = self
+            .methods
+            .iter()
+            .filter(|method| {
+                request
+                    .routed_methods()
+                    .is_none_or(|routed| routed.contains(method))
+            })
+            .map(HttpMethod::as_str)
+            .collect::<Vec<_>>();
+
+        let requested_method = request.headers["access-control-request-method"].trim();
+        if !methods.contains(&requested_method) {
+            return HttpResponse::forbidden();
+        }
+
+        let methods = methods.join(", ");
+        let headers = match request.headers.get("access-control-request-headers") {
+            Some(requested) if self.headers.is_empty() => requested.clone(),
+            _ => self.headers.join(", "),
+        };
+
+        let mut response = self
+            .decorate(HttpResponse::new(204), origin)
+            .header("Access-Control-Allow-Methods", &methods);
+        if !headers.is_empty() {
+            response = response.header("Access-Control-Allow-Headers", &headers);
+        }
+        if let Some(max_age) = self.max_age {
+            response = response.header("Access-Control-Max-Age", &max_age.as_secs().to_string());
+        }
+        response
+    }
+
+    fn decorate(&self, response: HttpResponse, origin: &str) -> HttpResponse {
+        let mut response =
+            response.header("Access-Control-Allow-Origin", self.allow_origin(origin));
+        if self.credentials {
+            response = response.header("Access-Control-Allow-Credentials", "true");
+        }
+        response
+    }
+}
+
+/// Whether `origin` is `allowed`, or a subdomain of it if `allowed` has the
+/// form `scheme://*.domain`. Scheme and port must match exactly.
+fn origin_matches(allowed: &str, origin: &str) -> bool {
+    let Some((prefix, suffix)) = allowed.split_once("://*.") else {
+        return allowed.eq_ignore_ascii_case(origin);
+    };
+    let scheme = format!("{}://", prefix);
+    let suffix = format!(".{}", suffix);
+    if origin.len() <= scheme.len() + suffix.len()
+        || !origin.is_char_boundary(scheme.len())
+        || !origin.is_char_boundary(origin.len() - suffix.len())
+    {
+        return false;
+    }
+
+    let (head, rest) = origin.split_at(scheme.len());
+    let (subdomain, tail) = rest.split_at(rest.len() - suffix.len());
+    head.eq_ignore_ascii_case(&scheme)
+        && tail.eq_ignore_ascii_case(&suffix)
+        && !subdomain.starts_with('.')
+        && subdomain
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.')
+}
+
+impl Middleware for CorsConfig {
+    fn handle(&self, request: &mut Request, next: Next) -> std::io::Result<HttpResponse> {
+        let response = self.respond(request, next)?;
+        if !self.varies_by_origin() {
+            return Ok(response);
+        }
+
+        // On every response, including those to requests without an Origin or
+        // from a disallowed one, or a cache could serve them to allowed origins
+        let vary = match response.headers.get("Vary") {
+            Some(vary) => format!("{}, Origin", vary),
+            None => "Origin".to_string(),
+        };
+        Ok(response.header("Vary", &vary))
+    }
+}
+
+impl CorsConfig {
+    fn respond(&self, request: &mut Request, next: Next) -> std::io::Result<HttpResponse> {
+        let Some(origin) = request.headers.get("origin").cloned() else {
+            return next.run(request);
+        };
+
+        let is_preflight = request.method == HttpMethod::OPTIONS
+            && request
+                .headers
+                .contains_key("access-control-request-method");
+        if !self.allows_origin(&origin) {
+            // No CORS headers: browsers then refuse to expose the response
+            return match is_preflight {
+                true => Ok(HttpResponse::forbidden()),
+                false => next.run(request),
+            };
+        }
+
+        if is_preflight {
+            return Ok(self.preflight(request, &origin));
+        }
+        let response = self.decorate(next.run(request)?, &origin);
+        match self.timing_allow_origin {
+            true => Ok(response.header("Timing-Allow-Origin", self.allow_origin(&origin))),
+            false => Ok(response),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn run(cors: &CorsConfig, raw: &str) -> HttpResponse {
+        let mut buffer = BufReader::new(raw.as_bytes());
+        let mut request = Request::read(&mut buffer).unwrap();
+        let middleware: Vec<Box<dyn Middleware>> = vec![Box::new(cors.clone())];
+        let endpoint = |_: &mut Request| Ok(HttpResponse::ok().header("Vary", "Accept"));
+        Next::new(&middleware, &endpoint).run(&mut request).unwrap()
+    }
+
+    #[test]
+    fn test_simple_requests() {
+        let cors = CorsConfig::default();
+
+        let response = run(&cors, "GET / HTTP/1.1\r\nOrigin: https://a.test\r\n\r\n");
+        assert_eq!(response.headers["Access-Control-Allow-Origin"], "*");
+        assert_eq!(response.headers["Vary"], "Accept");
+
+        let response = run(&cors, "GET / HTTP/1.1\r\n\r\n");
+        assert!(!response.headers.contains_key("Access-Control-Allow-Origin"));
+    }
+
+    #[test]
+    fn test_credentials_echo_origin() {
+        let cors = CorsConfig {
+            allowed_origins: vec!["https://app.test".to_string()],
+            credentials: true,
+            ..CorsConfig::default()
+        };
+
+        let response = run(&cors, "GET / HTTP/1.1\r\nOrigin: https://app.test\r\n\r\n");
+        assert_eq!(
+            response.headers["Access-Control-Allow-Origin"],
+            "https://app.test"
+        );
+        assert_eq!(response.headers["Access-Control-Allow-Credentials"], "true");
+        assert_eq!(response.headers["Vary"], "Accept, Origin");
+
+        let response = run(&cors, "GET / HTTP/1.1\r\nOrigin: https://evil.test\r\n\r\n");
+        assert_eq!(response.status_code, 200);
+        assert!(!response.headers.contains_key("Access-Control-Allow-Origin"));
+        assert_eq!(response.headers["Vary"], "Accept, Origin");
+
+        let response = run(&cors, "GET / HTTP/1.1\r\n\r\n");
+        assert_eq!(response.headers["Vary"], "Accept, Origin");
+    }
+
+    #[test]
+    fn test_credentials_with_any_origin_are_refused() {
+        let cors = CorsConfig {
+            credentials: true,
+            ..CorsConfig::default()
+        };
+        assert!(cors.validate().is_err());
+        assert!(CorsConfig::default().validate().is_ok());
+
+        let response = run(&cors, "GET / HTTP/1.1\r\nOrigin: https://evil.test\r\n\r\n");
+        assert!(!response.headers.contains_key("Access-Control-Allow-Origin"));
+        assert!(
+            !response
+                .headers
+                .contains_key("Access-Control-Allow-Credentials")
+        );
+    }
+
+    #[test]
+    fn test_timing_allow_origin() {
+        let cors = CorsConfig {
+            allowed_origins: vec!["https://app.test".to_string()],
+            timing_allow_origin: true,
+            ..CorsConfig::default()
+        };
+
+        let response = run(&cors, "GET / HTTP/1.1\r\nOrigin: https://app.test\r\n\r\n");
+        assert_eq!(response.headers["Timing-Allow-Origin"], "https://app.test");
+
+        let response = run(&cors, "GET / HTTP/1.1\r\nOrigin: https://evil.test\r\n\r\n");
+        assert!(!response.headers.contains_key("Timing-Allow-Origin"));
+
+        let response = run(
+            &CorsConfig::default(),
+            "GET / HTTP/1.1\r\nOrigin: https://a.test\r\n\r\n",
+        );
+        assert!(!response.headers.contains_key("Timing-Allow-Origin"));
+    }
+
+    #[test]
+    fn test_preflight() {
+        let cors = CorsConfig {
+            max_age: Some(Duration::from_secs(600)),
+            ..CorsConfig::default()
+        };
+
+        let response = run(
+            &cors,
+            "OPTIONS /items HTTP/1.1\r\nOrigin: https://a.test\r\n\
+             Access-Control-Request-Method: PUT\r\n\
+             Access-Control-Request-Headers: content-type, x-token\r\n\r\n",
+        );
+        assert_eq!(response.status_code, 204);
+        assert_eq!(
+            response.headers["Access-Control-Allow-Methods"],
+            "GET, HEAD, POST, PUT, PATCH, DELETE"
+        );
+        assert_eq!(
+            response.headers["Access-Control-Allow-Headers"],
+            "content-type, x-token"
+        );
+        assert_eq!(response.headers["Access-Control-Max-Age"], "600");
+
+        let response = run(
+            &cors,
+            "OPTIONS /items HTTP/1.1\r\nOrigin: https://a.test\r\n\
+             Access-Control-Request-Method: TRACE\r\n\r\n",
+        );
+        assert_eq!(response.status_code, 403);
+    }
+
+    #[test]
+    fn test_origin_patterns_and_validator() {
+        let cors = CorsConfig {
+            allowed_origins: vec!["https://*.example.com".to_string()],
+            origin_validator: Some(OriginValidator::new(|origin| {
+                origin == "https://tenant.test"
+            })),
+            ..CorsConfig::default()
+        };
+        let allow_origin = |origin: &str| {
+            let raw = format!("GET / HTTP/1.1\r\nOrigin: {}\r\n\r\n", origin);
+            run(&cors, &raw)
+                .headers
+                .get("Access-Control-Allow-Origin")
+                .cloned()
+        };
+
+        assert_eq!(
+            allow_origin("https://app.example.com").as_deref(),
+            Some("https://app.example.com")
+        );
+        assert!(allow_origin("https://a.b.Example.com").is_some());
+        assert!(allow_origin("https://tenant.test").is_some());
+
+        assert!(allow_origin("https://example.com").is_none());
+        assert!(allow_origin("http://app.example.com").is_none());
+        assert!(allow_origin("https://app.example.com:8443").is_none());
+        assert!(allow_origin("https://evil.com/.example.com").is_none());
+        assert!(allow_origin("https://app.example.com.evil.com").is_none());
+        assert!(allow_origin("https://other.test").is_none());
+    }
+}
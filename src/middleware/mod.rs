@@ -0,0 +1,135 @@
+mod auth;
+mod canonical_host;
+#[cfg(feature = "compression")]
+mod compression;
+mod cors;
+
+pub use auth::{ApiKeyAuth, BasicAuth, BearerAuth};
+pub use canonical_host::CanonicalHost;
+#[cfg(feature = "compression")]
+pub use compression::Compression;
+pub use cors::{CorsConfig, OriginValidator};
+
+use crate::http::{HttpResponse, Request};
+
+/// Code that runs around every request. A middleware may inspect or mutate the
+/// request, short-circuit by returning a response without calling `next`, or
+/// post-process the response `next` returns.
+///
+/// Closures of the form `|req, next| { ... }` implement this trait:
+///
+/// ```no_run
+/// # use schnell::http::{HttpResponse, Request};
+/// # use schnell::middleware::Next;
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// server.use_middleware(|req: &mut Request, next: Next| {
+///     if !req.headers.contains_key("authorization") {
+///         return Ok(HttpResponse::unauthorized());
+///     }
+///     let response = next.run(req)?;
+///     Ok(response.header("X-Served-By", "schnell"))
+/// });
+/// ```
+pub trait Middleware: Send + Sync {
+    fn handle(&self, request: &mut Request, next: Next) -> std::io::Result<HttpResponse>;
+}
+
+impl<F> Middleware for F
+where
+    F: Fn(&mut Request, Next) -> std::io::Result<HttpResponse> + Send + Sync,
+{
+    fn handle(&self, request: &mut Request, next: Next) -> std::io::Result<HttpResponse> {
+        self(request, next)
+    }
+}
+
+/// The rest of the chain: the remaining middleware followed by the route handler.
+pub struct Next<'a> {
+    middleware: &'a [Box<dyn Middleware>],
+    endpoint: &'a dyn Fn(&mut Request) -> std::io::Result<HttpResponse>,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        middleware: &'a [Box<dyn Middleware>],
+        endpoint: &'a dyn Fn(&mut Request) -> std::io::Result<HttpResponse>,
+    ) -> Self {
+        Self {
+            middleware,
+            endpoint,
+        }
+    }
+
+    pub fn run(self, request: &mut Request) -> std::io::Result<HttpResponse> {
+        match self.middleware.split_first() {
+            Some((current, rest)) => current.handle(request, Next::new(rest, self.endpoint)),
+            None => (self.endpoint)(request),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+    use std::sync::{Arc, Mutex};
+
+    fn request() -> Request {
+        let mut buffer = BufReader::new("GET / HTTP/1.1\r\n\r\n".as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    #[test]
+    fn test_chain_runs_in_registration_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut middleware: Vec<Box<dyn Middleware>> = Vec::new();
+        for name in ["outer", "inner"] {
+            let log = Arc::clone(&log);
+            middleware.push(Box::new(move |req: &mut Request, next: Next| {
+                log.lock().unwrap().push(format!("before {}", name));
+                let response = next.run(req);
+                log.lock().unwrap().push(format!("after {}", name));
+                response
+            }));
+        }
+
+        let endpoint = |_: &mut Request| Ok(HttpResponse::ok());
+        Next::new(&middleware, &endpoint)
+            .run(&mut request())
+            .unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["before outer", "before inner", "after inner", "after outer"]
+        );
+    }
+
+    #[test]
+    fn test_short_circuit_and_mutation() {
+        let middleware: Vec<Box<dyn Middleware>> = vec![
+            Box::new(|req: &mut Request, next: Next| {
+                req.headers
+                    .insert("x-user".to_string(), "alice".to_string());
+                next.run(req)
+            }),
+            Box::new(
+                |req: &mut Request, next: Next| match req.headers.get("x-user") {
+                    Some(_) => next.run(req),
+                    None => Ok(HttpResponse::unauthorized()),
+                },
+            ),
+        ];
+
+        let endpoint = |req: &mut Request| Ok(HttpResponse::ok().text(&req.headers["x-user"]));
+        let response = Next::new(&middleware, &endpoint)
+            .run(&mut request())
+            .unwrap();
+        assert_eq!(response.body, "alice");
+
+        let response = Next::new(&middleware[1..], &endpoint)
+            .run(&mut request())
+            .unwrap();
+        assert_eq!(response.status_code, 401);
+    }
+}
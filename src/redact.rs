@@ -0,0 +1,225 @@
+/* Redaction of credentials before requests are logged */
+use std::collections::HashSet;
+
+use crate::utils::percent_decode_query;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Header names and body fields whose values never reach the logs. Matching is
+/// case-insensitive; body fields are matched at any depth of a JSON body and by
+/// their innermost key in a form (`user[password]` matches `password`).
+///
+/// ```no_run
+/// # use schnell::redact::Redaction;
+/// # use schnell::server::Server;
+/// let server = Server::new("0.0.0.0", 8080, None)
+///     .with_request_logging(4096, Redaction::default().header("X-Session").field("ssn"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Redaction {
+    headers: HashSet<String>,
+    fields: HashSet<String>,
+}
+
+impl Default for Redaction {
+    /// Credentials headers plus the usual password and token fields.
+    fn default() -> Self {
+        let redaction = Self::none();
+        let redaction = [
+            "Authorization",
+            "Proxy-Authorization",
+            "Cookie",
+            "Set-Cookie",
+            "X-Api-Key",
+        ]
+        .into_iter()
+        .fold(redaction, Self::header);
+        [
+            "password",
+            "token",
+            "access_token",
+            "refresh_token",
+            "secret",
+            "api_key",
+        ]
+        .into_iter()
+        .fold(redaction, Self::field)
+    }
+}
+
+impl Redaction {
+    /// Redacts nothing; add rules with `header` and `field`.
+    pub fn none() -> Self {
+        Self {
+            headers: HashSet::new(),
+            fields: HashSet::new(),
+        }
+    }
+
+    pub fn header(self, name: &str) -> Self {
+        let mut redaction = self;
+        redaction.headers.insert(name.to_ascii_lowercase());
+        redaction
+    }
+
+    pub fn field(self, name: &str) -> Self {
+        let mut redaction = self;
+        redaction.fields.insert(name.to_ascii_lowercase());
+        redaction
+    }
+
+    pub fn header_value<'a>(&self, name: &str, value: &'a str) -> &'a str {
+        match self.headers.contains(&name.to_ascii_lowercase()) {
+            true => REDACTED,
+            false => value,
+        }
+    }
+
+    /// `body` as loggable text, at most `limit` bytes of it. JSON and
+    /// urlencoded bodies are redacted field by field; anything else that
+    /// isn't UTF-8 is summarised by its length only.
+    pub fn body(&self, content_type: &str, body: &[u8], limit: usize) -> String {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+
+        if essence.ends_with("json") {
+            #[cfg(feature = "json")]
+            if let Ok(mut json) = serde_json::from_slice(body) {
+                self.redact_json(&mut json);
+                return truncate(json.to_string(), limit);
+            }
+            // Without a parsed document its fields can't be told apart, so none
+            // are logged
+            return format!("<{} bytes of JSON>", body.len());
+        }
+
+        let Ok(text) = std::str::from_utf8(body) else {
+            return format!("<{} bytes of binary data>", body.len());
+        };
+        let text = match essence.as_str() {
+            "application/x-www-form-urlencoded" => self.redact_form(text),
+            _ => text.to_string(),
+        };
+        truncate(text, limit)
+    }
+
+    #[cfg(feature = "json")]
+    fn redact_json(&self, value: &mut serde_json::Value) {
+        use serde_json::Value;
+
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.fields.contains(&key.to_ascii_lowercase()) {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_json(value);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_json(item)),
+            _ => {}
+        }
+    }
+
+    fn redact_form(&self, form: &str) -> String {
+        form.split('&')
+            .map(|pair| {
+                let Some((key, _)) = pair.split_once('=') else {
+                    return pair.to_string();
+                };
+                let decoded = percent_decode_query(key);
+                let innermost = decoded
+                    .trim_end_matches(['[', ']'])
+                    .rsplit('[')
+                    .next()
+                    .unwrap_or("");
+                match self.fields.contains(&innermost.to_ascii_lowercase()) {
+                    true => format!("{}={}", key, REDACTED),
+                    false => pair.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+fn truncate(text: String, limit: usize) -> String {
+    if text.len() <= limit {
+        return text;
+    }
+    let mut end = limit;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... ({} bytes total)", &text[..end], text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headers() {
+        let redaction = Redaction::default().header("X-Session");
+
+        assert_eq!(
+            redaction.header_value("authorization", "Bearer t"),
+            REDACTED
+        );
+        assert_eq!(redaction.header_value("x-session", "abc"), REDACTED);
+        assert_eq!(redaction.header_value("Accept", "*/*"), "*/*");
+        assert_eq!(Redaction::none().header_value("Cookie", "a=1"), "a=1");
+    }
+
+    #[test]
+    fn test_json_and_form_bodies() {
+        let redaction = Redaction::default();
+
+        let json = br#"{"user":"ada","Password":"hunter2","sessions":[{"token":"t1"}]}"#;
+        #[cfg(feature = "json")]
+        assert_eq!(
+            redaction.body("application/json", json, 1024),
+            r#"{"Password":"[REDACTED]","sessions":[{"token":"[REDACTED]"}],"user":"ada"}"#
+        );
+        #[cfg(not(feature = "json"))]
+        assert_eq!(
+            redaction.body("application/json", json, 1024),
+            "<63 bytes of JSON>"
+        );
+
+        let malformed = br#"{"password":"hunter2","#;
+        assert_eq!(
+            redaction.body("application/json", malformed, 1024),
+            "<22 bytes of JSON>"
+        );
+
+        let form = b"user%5Bname%5D=ada&user[password]=hunter2&remember";
+        assert_eq!(
+            redaction.body(
+                "application/x-www-form-urlencoded; charset=utf-8",
+                form,
+                1024
+            ),
+            "user%5Bname%5D=ada&user[password]=[REDACTED]&remember"
+        );
+    }
+
+    #[test]
+    fn test_limits_and_binary() {
+        let redaction = Redaction::default();
+
+        assert_eq!(
+            redaction.body("text/plain", "héllo world".as_bytes(), 2),
+            "h... (12 bytes total)"
+        );
+        assert_eq!(
+            redaction.body("image/png", b"\x89PNG\xff", 1024),
+            "<5 bytes of binary data>"
+        );
+    }
+}
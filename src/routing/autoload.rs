@@ -0,0 +1,114 @@
+/* Convention-based route registration (feature "autoload") */
+use std::sync::Arc;
+
+use crate::http::{HttpMethod, HttpResponse, Request};
+use crate::routing::RouteBuilder;
+use crate::utils::join_path;
+
+/// A route submitted with [`register_route!`](crate::register_route) from
+/// anywhere in the program.
+pub struct RouteRegistration {
+    pub module_path: &'static str,
+    pub method: HttpMethod,
+    pub path: &'static str,
+    pub handler: fn(&Request) -> std::io::Result<HttpResponse>,
+}
+
+inventory::collect!(RouteRegistration);
+
+/// Registers a handler from the module it is defined in. Mount everything with
+/// [`mount_registered`]; the module path below the root becomes the URL prefix.
+///
+/// `register_route!(GET, "/", list)` in `myapp::handlers::users`, mounted
+/// with `mount_registered(&mut server, "myapp::handlers")`, serves `GET /users`.
+#[macro_export]
+macro_rules! register_route {
+    ($method:ident, $path:expr, $handler:path) => {
+        $crate::inventory::submit! {
+            $crate::routing::autoload::RouteRegistration {
+                module_path: module_path!(),
+                method: $crate::http::HttpMethod::$method,
+                path: $path,
+                handler: $handler,
+            }
+        }
+    };
+}
+
+/// Registers every submitted route whose module lives under `root_module`,
+/// prefixed with the rest of its module path: a route `/:id` in
+/// `myapp::handlers::users` is mounted at `/users/:id`. Returns how many routes
+/// were mounted.
+pub fn mount_registered<B: RouteBuilder>(builder: &mut B, root_module: &str) -> usize {
+    let mut mounted = 0;
+
+    for registration in inventory::iter::<RouteRegistration> {
+        let Some(prefix) = module_prefix(root_module, registration.module_path) else {
+            continue;
+        };
+        let path = join_path(&prefix, registration.path);
+        let path = match path.strip_suffix('/') {
+            Some(trimmed) if !trimmed.is_empty() => trimmed.to_string(),
+            _ => path,
+        };
+
+        builder.register(&path, registration.method, Arc::new(registration.handler));
+        mounted += 1;
+    }
+
+    mounted
+}
+
+/// URL prefix for `module_path` relative to `root_module`, or `None` if the module
+/// isn't below the root.
+fn module_prefix(root_module: &str, module_path: &str) -> Option<String> {
+    let rest = module_path.strip_prefix(root_module)?;
+    if !rest.is_empty() && !rest.starts_with("::") {
+        return None;
+    }
+
+    let segments: Vec<&str> = rest.split("::").filter(|s| !s.is_empty()).collect();
+    Some(format!("/{}", segments.join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::Router;
+
+    mod users {
+        use crate::http::{HttpResponse, Request};
+
+        fn list(_: &Request) -> std::io::Result<HttpResponse> {
+            Ok(HttpResponse::ok())
+        }
+
+        crate::register_route!(GET, "/", list);
+        crate::register_route!(POST, "/:id", list);
+    }
+
+    #[test]
+    fn test_module_prefix() {
+        assert_eq!(
+            module_prefix("app::handlers", "app::handlers"),
+            Some("/".to_string())
+        );
+        assert_eq!(
+            module_prefix("app::handlers", "app::handlers::users::admin"),
+            Some("/users/admin".to_string())
+        );
+        assert_eq!(module_prefix("app::handlers", "app::handlersx"), None);
+        assert_eq!(module_prefix("app::handlers", "app::other"), None);
+    }
+
+    #[test]
+    fn test_mount_registered() {
+        let mut router = Router::new("/");
+        let mounted = mount_registered(&mut router, module_path!());
+
+        assert_eq!(mounted, 2);
+        let paths: Vec<String> = router.routes().iter().map(|r| r.path.clone()).collect();
+        assert!(paths.contains(&"/users".to_string()));
+        assert!(paths.contains(&"/users/:id".to_string()));
+    }
+}
@@ -0,0 +1,422 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::http::{HttpResponse, Request};
+use crate::routing::route::RouteHandler;
+use crate::testing::Clock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum BreakerState {
+    /// Requests reach the handler.
+    Closed,
+    /// The handler kept failing; requests go straight to the fallback.
+    Open,
+    /// The cool-down elapsed and a single trial request is probing the handler.
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakerStats {
+    pub name: String,
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    pub total_failures: u64,
+    pub total_rejections: u64,
+}
+
+struct BreakerInner {
+    state: BreakerState,
+    /// Bumped on every state change, so outcomes of requests admitted under an
+    /// earlier state can be told apart and ignored.
+    generation: u64,
+    opened_at: Option<Instant>,
+    consecutive_failures: u32,
+    total_failures: u64,
+    total_rejections: u64,
+}
+
+/// Route-level circuit breaker. A handler is considered failed when it returns an
+/// error or a 5xx response; after `failure_threshold` consecutive failures the
+/// breaker opens and serves the fallback until `cooldown` has passed.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use schnell::http::{HttpResponse, Request};
+/// # use schnell::routing::{CircuitBreaker, RouteBuilder};
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// # let fetch_weather = |_: &Request| Ok(HttpResponse::ok());
+/// let breaker = server
+///     .breakers()
+///     .register(CircuitBreaker::new("weather", 5, Duration::from_secs(30)));
+/// server.get("/weather", breaker.wrap(fetch_weather));
+/// ```
+pub struct CircuitBreaker {
+    name: String,
+    failure_threshold: u32,
+    cooldown: Duration,
+    fallback: Option<RouteHandler>,
+    clock: Clock,
+    inner: Mutex<BreakerInner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: &str, failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            fallback: None,
+            clock: Clock::system(),
+            inner: Mutex::new(BreakerInner {
+                state: BreakerState::Closed,
+                generation: 0,
+                opened_at: None,
+                consecutive_failures: 0,
+                total_failures: 0,
+                total_rejections: 0,
+            }),
+        }
+    }
+
+    pub fn with_fallback<H>(self, fallback: H) -> Self
+    where
+        H: Fn(&Request) -> std::io::Result<HttpResponse> + Send + Sync + 'static,
+    {
+        let mut breaker = self;
+        breaker.fallback = Some(Arc::new(fallback));
+        breaker
+    }
+
+    /// Measures the cool-down with `clock` instead of the system clock.
+    pub fn with_clock(self, clock: Clock) -> Self {
+        let mut breaker = self;
+        breaker.clock = clock;
+        breaker
+    }
+
+    /// Wraps `handler` so every call goes through this breaker.
+    pub fn wrap<H>(
+        self: &Arc<Self>,
+        handler: H,
+    ) -> impl Fn(&Request) -> std::io::Result<HttpResponse> + Send + Sync + 'static
+    where
+        H: Fn(&Request) -> std::io::Result<HttpResponse> + Send + Sync + 'static,
+    {
+        let breaker = Arc::clone(self);
+        move |request| breaker.call(&handler, request)
+    }
+
+    pub fn call<H>(&self, handler: &H, request: &Request) -> std::io::Result<HttpResponse>
+    where
+        H: Fn(&Request) -> std::io::Result<HttpResponse>,
+    {
+        let generation = match self.acquire() {
+            Ok(generation) => generation,
+            Err(retry_after) => {
+                return match &self.fallback {
+                    Some(fallback) => fallback(request),
+                    None => Ok(HttpResponse::new(503)
+                        .header("Retry-After", &retry_after.as_secs().max(1).to_string())),
+                };
+            }
+        };
+
+        // A panicking handler unwinds past `record`, which would leave a
+        // half-open breaker waiting on its trial request forever
+        let attempt = Attempt {
+            breaker: self,
+            generation,
+        };
+        let result = handler(request);
+        std::mem::forget(attempt);
+
+        let failed = match &result {
+            Ok(response) => response.status_code >= 500,
+            Err(_) => true,
+        };
+        self.record(generation, failed);
+
+        result
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.inner.lock().unwrap().state
+    }
+
+    pub fn stats(&self) -> BreakerStats {
+        let inner = self.inner.lock().unwrap();
+        BreakerStats {
+            name: self.name.clone(),
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            total_failures: inner.total_failures,
+            total_rejections: inner.total_rejections,
+        }
+    }
+
+    /// Returns the generation the request is admitted under, or how long until
+    /// the breaker will let a trial request through.
+    fn acquire(&self) -> Result<u64, Duration> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            BreakerState::Closed => Ok(inner.generation),
+            BreakerState::Open => {
+                let elapsed = inner.opened_at.map_or(self.cooldown, |at| {
+                    self.clock.now().saturating_duration_since(at)
+                });
+                if elapsed >= self.cooldown {
+                    info!("Circuit breaker {:?} half-open, probing handler", self.name);
+                    inner.transition(BreakerState::HalfOpen);
+                    Ok(inner.generation)
+                } else {
+                    inner.total_rejections += 1;
+                    Err(self.cooldown - elapsed)
+                }
+            }
+            BreakerState::HalfOpen => {
+                inner.total_rejections += 1;
+                Err(self.cooldown)
+            }
+        }
+    }
+
+    /// Records the outcome of a request admitted under `generation`. Outcomes
+    /// from before the last state change are counted but can't change the
+    /// state, so a slow success can't close a breaker that has since opened.
+    fn record(&self, generation: u64, failed: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        if failed {
+            inner.total_failures += 1;
+        }
+        if generation != inner.generation {
+            return;
+        }
+
+        if !failed {
+            if inner.state != BreakerState::Closed {
+                info!("Circuit breaker {:?} closed", self.name);
+                inner.transition(BreakerState::Closed);
+            }
+            inner.consecutive_failures = 0;
+            inner.opened_at = None;
+            return;
+        }
+
+        inner.consecutive_failures += 1;
+
+        if inner.state == BreakerState::HalfOpen
+            || inner.consecutive_failures >= self.failure_threshold
+        {
+            warn!(
+                "Circuit breaker {:?} opened after {} consecutive failures",
+                self.name, inner.consecutive_failures
+            );
+            inner.transition(BreakerState::Open);
+            inner.opened_at = Some(self.clock.now());
+        }
+    }
+}
+
+impl BreakerInner {
+    fn transition(&mut self, state: BreakerState) {
+        self.state = state;
+        self.generation += 1;
+    }
+}
+
+/// Cloneable handle to the breakers registered with a server, so their stats
+/// can be served from an admin route (see `Server::serve_admin_stats`).
+#[derive(Clone, Default)]
+pub struct BreakerRegistry {
+    breakers: Arc<Mutex<Vec<Arc<CircuitBreaker>>>>,
+}
+
+impl BreakerRegistry {
+    /// Adds `breaker` to the registry and returns it ready for `wrap`.
+    pub fn register(&self, breaker: CircuitBreaker) -> Arc<CircuitBreaker> {
+        let breaker = Arc::new(breaker);
+        self.breakers.lock().unwrap().push(Arc::clone(&breaker));
+        breaker
+    }
+
+    pub fn stats(&self) -> Vec<BreakerStats> {
+        let breakers = self.breakers.lock().unwrap();
+        breakers.iter().map(|breaker| breaker.stats()).collect()
+    }
+}
+
+/// Records a failure if dropped, i.e. if the handler it guards panicked.
+struct Attempt<'a> {
+    breaker: &'a CircuitBreaker,
+    generation: u64,
+}
+
+impl Drop for Attempt<'_> {
+    fn drop(&mut self) {
+        warn!("Circuit breaker {:?} handler panicked", self.breaker.name);
+        self.breaker.record(self.generation, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn request() -> Request {
+        let mut buffer = BufReader::new("GET /weather HTTP/1.1\r\n\r\n".as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    #[test]
+    fn test_breaker_opens_after_threshold() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&calls);
+        let breaker = Arc::new(
+            CircuitBreaker::new("weather", 2, Duration::from_secs(60))
+                .with_fallback(|_| Ok(HttpResponse::ok().text("cached"))),
+        );
+        let handler = breaker.wrap(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(HttpResponse::bad_gateway())
+        });
+
+        let req = request();
+        assert_eq!(handler(&req).unwrap().status_code, 502);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert_eq!(handler(&req).unwrap().status_code, 502);
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        let response = handler(&req).unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.body, "cached");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let stats = breaker.stats();
+        assert_eq!(stats.total_failures, 2);
+        assert_eq!(stats.total_rejections, 1);
+    }
+
+    #[test]
+    fn test_breaker_default_fallback() {
+        let breaker = Arc::new(CircuitBreaker::new("flaky", 1, Duration::from_secs(60)));
+        let handler = breaker.wrap(|_| Err(std::io::Error::other("downstream unavailable")));
+
+        let req = request();
+        assert!(handler(&req).is_err());
+
+        let response = handler(&req).unwrap();
+        assert_eq!(response.status_code, 503);
+        assert!(response.headers.contains_key("Retry-After"));
+    }
+
+    #[test]
+    fn test_breaker_half_open_recovers() {
+        let healthy = Arc::new(AtomicU32::new(0));
+        let flag = Arc::clone(&healthy);
+        let breaker = Arc::new(CircuitBreaker::new("recovering", 1, Duration::ZERO));
+        let handler = breaker.wrap(move |_| {
+            if flag.load(Ordering::SeqCst) == 1 {
+                Ok(HttpResponse::ok())
+            } else {
+                Ok(HttpResponse::internal_server_error())
+            }
+        });
+
+        let req = request();
+        handler(&req).unwrap();
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        // Cool-down has elapsed, so the trial request fails and re-opens the breaker
+        handler(&req).unwrap();
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        healthy.store(1, Ordering::SeqCst);
+        assert_eq!(handler(&req).unwrap().status_code, 200);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert_eq!(breaker.stats().consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_breaker_waits_for_cooldown() {
+        let clock = Clock::manual();
+        let breaker = Arc::new(
+            CircuitBreaker::new("slow", 1, Duration::from_secs(30)).with_clock(clock.clone()),
+        );
+        let handler = breaker.wrap(|_| Ok(HttpResponse::internal_server_error()));
+
+        let req = request();
+        handler(&req).unwrap();
+
+        clock.advance(Duration::from_secs(20));
+        let response = handler(&req).unwrap();
+        assert_eq!(response.status_code, 503);
+        assert_eq!(response.headers["Retry-After"], "10");
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(handler(&req).unwrap().status_code, 500);
+        assert_eq!(breaker.stats().total_rejections, 1);
+    }
+
+    #[test]
+    fn test_breaker_recovers_from_panicking_trial() {
+        let panics = Arc::new(AtomicU32::new(1));
+        let flag = Arc::clone(&panics);
+        let breaker = Arc::new(CircuitBreaker::new("panicky", 1, Duration::ZERO));
+        let handler = breaker.wrap(move |_| {
+            if flag.load(Ordering::SeqCst) == 1 {
+                panic!("handler bug");
+            }
+            Ok(HttpResponse::ok())
+        });
+
+        let req = request();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| handler(&req)));
+        assert!(result.is_err());
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        // The half-open trial panics too, and must re-open rather than wedge
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| handler(&req)));
+        assert!(result.is_err());
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert_eq!(breaker.stats().total_failures, 2);
+
+        panics.store(0, Ordering::SeqCst);
+        assert_eq!(handler(&req).unwrap().status_code, 200);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn test_stale_success_does_not_close() {
+        let breaker = CircuitBreaker::new("slow", 1, Duration::from_secs(60));
+        // A slow request admitted while closed...
+        let generation = breaker.acquire().unwrap();
+
+        // ...finishes only after another one has opened the breaker
+        let failing = breaker.acquire().unwrap();
+        breaker.record(failing, true);
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        breaker.record(generation, false);
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert_eq!(breaker.stats().consecutive_failures, 1);
+    }
+
+    #[test]
+    fn test_registry_stats() {
+        let registry = BreakerRegistry::default();
+        let breaker = registry.register(CircuitBreaker::new("weather", 1, Duration::from_secs(60)));
+        let handler = breaker.wrap(|_| Ok(HttpResponse::bad_gateway()));
+        handler(&request()).unwrap();
+
+        let stats = registry.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].name, "weather");
+        assert_eq!(stats[0].state, BreakerState::Open);
+    }
+}
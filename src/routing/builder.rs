@@ -1,7 +1,113 @@
-use crate::http::HttpMethod;
-use crate::routing::route::RouteHandler;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::http::{HttpMethod, IntoResponse, Request};
+use crate::routing::mock::{MockSpec, mock_routes};
+use crate::routing::route::{RouteHandler, into_route_handler};
+use crate::routing::versioning::VersionedHandlers;
 
 pub trait RouteBuilder {
     type Error;
     fn register(&mut self, path: &str, method: HttpMethod, handler: RouteHandler);
+
+    fn get<H, R>(&mut self, path: &str, handler: H)
+    where
+        H: Fn(&Request) -> R + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        self.register(path, HttpMethod::GET, into_route_handler(handler))
+    }
+
+    fn post<H, R>(&mut self, path: &str, handler: H)
+    where
+        H: Fn(&Request) -> R + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        self.register(path, HttpMethod::POST, into_route_handler(handler))
+    }
+
+    fn put<H, R>(&mut self, path: &str, handler: H)
+    where
+        H: Fn(&Request) -> R + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        self.register(path, HttpMethod::PUT, into_route_handler(handler))
+    }
+
+    fn patch<H, R>(&mut self, path: &str, handler: H)
+    where
+        H: Fn(&Request) -> R + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        self.register(path, HttpMethod::PATCH, into_route_handler(handler))
+    }
+
+    fn delete<H, R>(&mut self, path: &str, handler: H)
+    where
+        H: Fn(&Request) -> R + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        self.register(path, HttpMethod::DELETE, into_route_handler(handler))
+    }
+
+    fn head<H, R>(&mut self, path: &str, handler: H)
+    where
+        H: Fn(&Request) -> R + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        self.register(path, HttpMethod::HEAD, into_route_handler(handler))
+    }
+
+    fn options<H, R>(&mut self, path: &str, handler: H)
+    where
+        H: Fn(&Request) -> R + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        self.register(path, HttpMethod::OPTIONS, into_route_handler(handler))
+    }
+
+    fn add_route<H, R>(&mut self, method: HttpMethod, path: &str, handler: H)
+    where
+        H: Fn(&Request) -> R + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        self.register(path, method, into_route_handler(handler))
+    }
+
+    /// Registers a GET route that picks its handler by requested API version.
+    fn versioned<F>(&mut self, path: &str, config: F)
+    where
+        F: FnOnce(&mut VersionedHandlers),
+    {
+        self.versioned_route(HttpMethod::GET, path, config)
+    }
+
+    fn versioned_route<F>(&mut self, method: HttpMethod, path: &str, config: F)
+    where
+        F: FnOnce(&mut VersionedHandlers),
+    {
+        let mut versions = VersionedHandlers::default();
+        config(&mut versions);
+        self.register(path, method, versions.into_handler())
+    }
+
+    /// Registers a GET route answered by a stub response.
+    fn mock(&mut self, path: &str, spec: MockSpec) {
+        self.register(
+            path,
+            HttpMethod::GET,
+            Arc::new(move |request| spec.respond(request)),
+        )
+    }
+
+    /// Registers a mock for every fixture file under `dir`, returning how many.
+    /// See `MockSpec` for how file names map to paths.
+    fn mock_dir<P: AsRef<Path>>(&mut self, dir: P) -> std::io::Result<usize> {
+        let routes = mock_routes(dir.as_ref())?;
+        let count = routes.len();
+        for (path, spec) in routes {
+            self.mock(&path, spec);
+        }
+        Ok(count)
+    }
 }
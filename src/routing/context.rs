@@ -0,0 +1,151 @@
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::http::{HttpResponse, Request};
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Per-request view handed to context-style handlers: the request itself, typed
+/// access to params, shared state, a request id and response shortcuts.
+///
+/// ```no_run
+/// # use schnell::routing::{Context, RouteBuilder};
+/// # use schnell::server::Server;
+/// # struct Db;
+/// # impl Db {
+/// #     fn user(&self, id: u32) -> String { id.to_string() }
+/// # }
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// server.get("/users/:id", Context::handler(|ctx| {
+///     let id: u32 = ctx.param("id").unwrap_or_default();
+///     let db = ctx.state::<Db>().unwrap();
+///     Ok(ctx.text(&db.user(id)))
+/// }));
+/// ```
+pub struct Context<'a> {
+    pub request: &'a Request,
+    request_id: String,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(request: &'a Request) -> Self {
+        let request_id = match request.headers.get("x-request-id") {
+            Some(id) => id.clone(),
+            None => generate_request_id(),
+        };
+
+        Self {
+            request,
+            request_id,
+        }
+    }
+
+    /// Adapts a context-style handler so it can be registered like any other route.
+    pub fn handler<H>(
+        handler: H,
+    ) -> impl Fn(&Request) -> std::io::Result<HttpResponse> + Send + Sync + 'static
+    where
+        H: Fn(&Context) -> std::io::Result<HttpResponse> + Send + Sync + 'static,
+    {
+        move |request| handler(&Context::new(request))
+    }
+
+    /// Path parameter `name` parsed as `T`; `None` if missing or unparsable.
+    pub fn param<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.request.params.get(name)?.parse().ok()
+    }
+
+    /// Query parameter `name` parsed as `T`; `None` if missing or unparsable.
+    pub fn query<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.request.query_param(name)?.parse().ok()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.request
+            .headers
+            .get(&name.to_lowercase())
+            .map(|v| v.as_str())
+    }
+
+    pub fn state<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.request.state()
+    }
+
+    /// The client-supplied `X-Request-Id`, or one generated for this request.
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::Serialize>(&self, body: T) -> HttpResponse {
+        HttpResponse::ok().json(body)
+    }
+
+    pub fn text(&self, body: &str) -> HttpResponse {
+        HttpResponse::ok().text(body)
+    }
+
+    pub fn html(&self, body: &str) -> HttpResponse {
+        HttpResponse::ok().html(body)
+    }
+
+    pub fn redirect(&self, url: &str) -> HttpResponse {
+        HttpResponse::ok().redirect(url)
+    }
+}
+
+fn generate_request_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let seq = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}-{:04x}", millis, seq)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn request(raw: &str) -> Request {
+        let mut buffer = BufReader::new(raw.as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    #[test]
+    fn test_typed_params_and_query() {
+        let mut req = request("GET /users/42?limit=10&sort=name HTTP/1.1\r\n\r\n");
+        req.params.insert("id".to_string(), "42".to_string());
+        let ctx = Context::new(&req);
+
+        assert_eq!(ctx.param::<u32>("id"), Some(42));
+        assert_eq!(ctx.param::<u32>("missing"), None);
+        assert_eq!(ctx.query::<usize>("limit"), Some(10));
+        assert_eq!(ctx.query::<usize>("sort"), None);
+        assert_eq!(ctx.query::<String>("sort"), Some("name".to_string()));
+    }
+
+    #[test]
+    fn test_request_id() {
+        let req = request("GET / HTTP/1.1\r\nX-Request-Id: abc-123\r\n\r\n");
+        assert_eq!(Context::new(&req).request_id(), "abc-123");
+
+        let req = request("GET / HTTP/1.1\r\n\r\n");
+        let first = Context::new(&req).request_id().to_string();
+        let second = Context::new(&req).request_id().to_string();
+        assert!(!first.is_empty());
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_handler_adapter() {
+        let handler = Context::handler(|ctx| Ok(ctx.json(vec![ctx.header("host")])));
+        let response = handler(&request("GET / HTTP/1.1\r\nHost: example.com\r\n\r\n")).unwrap();
+
+        assert_eq!(response.content_type, "application/json");
+        assert_eq!(response.body, "[\"example.com\"]");
+    }
+}
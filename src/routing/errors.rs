@@ -0,0 +1,114 @@
+use crate::http::{HttpResponse, Request};
+
+pub type ErrorHandler = Box<dyn Fn(&Request) -> HttpResponse + Send + Sync>;
+
+struct ScopedErrorHandler {
+    prefix: String,
+    status_code: u16,
+    handler: ErrorHandler,
+}
+
+/// Error pages keyed by status code and path prefix. Handlers registered on a
+/// group override the global ones for requests under that group's prefix.
+#[derive(Default)]
+pub struct ErrorHandlers {
+    handlers: Vec<ScopedErrorHandler>,
+}
+
+impl ErrorHandlers {
+    pub fn register<H>(&mut self, prefix: &str, status_code: u16, handler: H)
+    where
+        H: Fn(&Request) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.handlers
+            .retain(|h| !(h.prefix == prefix && h.status_code == status_code));
+        self.handlers.push(ScopedErrorHandler {
+            prefix: prefix.to_string(),
+            status_code,
+            handler: Box::new(handler),
+        });
+    }
+
+    /// Replaces a bare error response (4xx/5xx without a body, as produced for
+    /// unmatched routes and failing handlers) with the most specific registered
+    /// error page. Headers of the original response are kept unless overridden.
+    pub fn apply(&self, request: &Request, response: HttpResponse) -> HttpResponse {
+        if response.status_code < 400 || !response.body.is_empty() || response.is_streaming() {
+            return response;
+        }
+
+        let Some(scoped) = self
+            .handlers
+            .iter()
+            .filter(|h| h.status_code == response.status_code && in_scope(&h.prefix, &request.path))
+            .max_by_key(|h| h.prefix.len())
+        else {
+            return response;
+        };
+
+        let mut page = (scoped.handler)(request);
+        for (key, value) in response.headers {
+            page.headers.entry(key).or_insert(value);
+        }
+        page
+    }
+}
+
+pub(crate) fn in_scope(prefix: &str, path: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    prefix.is_empty()
+        || path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn request(path: &str) -> Request {
+        let raw = format!("GET {} HTTP/1.1\r\n\r\n", path);
+        let mut buffer = BufReader::new(raw.as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    fn handlers() -> ErrorHandlers {
+        let mut handlers = ErrorHandlers::default();
+        handlers.register("/", 404, |_| {
+            HttpResponse::not_found().html("<h1>Not here</h1>")
+        });
+        handlers.register("/api", 404, |_| {
+            HttpResponse::not_found()
+                .body(r#"{"error":"not found"}"#)
+                .content_type("application/json")
+        });
+        handlers
+    }
+
+    #[test]
+    fn test_most_specific_prefix_wins() {
+        let handlers = handlers();
+
+        let page = handlers.apply(&request("/api/users/7"), HttpResponse::not_found());
+        assert_eq!(page.content_type, "application/json");
+
+        let page = handlers.apply(&request("/apiary"), HttpResponse::not_found());
+        assert_eq!(page.content_type, "text/html");
+
+        let page = handlers.apply(&request("/api"), HttpResponse::not_found());
+        assert_eq!(page.content_type, "application/json");
+    }
+
+    #[test]
+    fn test_only_bare_error_responses_are_replaced() {
+        let handlers = handlers();
+
+        let response = handlers.apply(&request("/"), HttpResponse::not_found().text("custom"));
+        assert_eq!(response.body, "custom");
+
+        let response = handlers.apply(&request("/"), HttpResponse::internal_server_error());
+        assert!(response.body.is_empty());
+    }
+}
@@ -0,0 +1,436 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+use serde::de::value::{Error as ValueError, MapDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+
+use crate::http::body::BodyError;
+use crate::http::{HttpResponse, IntoResponse, Request};
+
+/// Why a handler's arguments couldn't be extracted from the request.
+#[derive(Debug)]
+pub enum ExtractError {
+    InvalidPath(String),
+    #[cfg(feature = "forms")]
+    InvalidQuery(String),
+    Body(BodyError),
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractError::InvalidPath(e) => write!(f, "Invalid path parameters: {}", e),
+            #[cfg(feature = "forms")]
+            ExtractError::InvalidQuery(e) => write!(f, "Invalid query string: {}", e),
+            ExtractError::Body(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ExtractError {}
+
+impl From<ExtractError> for HttpResponse {
+    fn from(error: ExtractError) -> Self {
+        match error {
+            ExtractError::Body(e) => e.into_response(),
+            _ => HttpResponse::bad_request().text(&error.to_string()),
+        }
+    }
+}
+
+/// A value built from the request before the handler runs. Handlers adapted
+/// with [`extract`] take any number of these as arguments; the first one that
+/// fails answers the request with its rejection instead.
+pub trait FromRequest: Sized {
+    type Rejection: Into<HttpResponse>;
+
+    fn from_request(request: &Request) -> Result<Self, Self::Rejection>;
+}
+
+/// Path parameters: a single one as a scalar (`Path<u32>`), or all of them by
+/// name as a struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Path<T> {
+    type Rejection = ExtractError;
+
+    fn from_request(request: &Request) -> Result<Self, Self::Rejection> {
+        T::deserialize(PathDeserializer(&request.params))
+            .map(Path)
+            .map_err(|e| ExtractError::InvalidPath(e.to_string()))
+    }
+}
+
+/// The query string, deserialized with the same bracket nesting as forms.
+#[cfg(feature = "forms")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query<T>(pub T);
+
+#[cfg(feature = "forms")]
+impl<T: DeserializeOwned> FromRequest for Query<T> {
+    type Rejection = ExtractError;
+
+    fn from_request(request: &Request) -> Result<Self, Self::Rejection> {
+        let query = request.target().split_once('?').map_or("", |(_, q)| q);
+        serde_qs::Config::new()
+            .max_depth(crate::constants::MAX_FORM_DEPTH)
+            .use_form_encoding(true)
+            .deserialize_str(query)
+            .map(Query)
+            .map_err(|e| ExtractError::InvalidQuery(e.to_string()))
+    }
+}
+
+/// A JSON body, as by `Request::json`.
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json<T>(pub T);
+
+#[cfg(feature = "json")]
+impl<T: DeserializeOwned> FromRequest for Json<T> {
+    type Rejection = ExtractError;
+
+    fn from_request(request: &Request) -> Result<Self, Self::Rejection> {
+        request.json().map(Json).map_err(ExtractError::Body)
+    }
+}
+
+/// A urlencoded form body, as by `Request::form`.
+#[cfg(feature = "forms")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Form<T>(pub T);
+
+#[cfg(feature = "forms")]
+impl<T: DeserializeOwned> FromRequest for Form<T> {
+    type Rejection = ExtractError;
+
+    fn from_request(request: &Request) -> Result<Self, Self::Rejection> {
+        request.form().map(Form).map_err(ExtractError::Body)
+    }
+}
+
+/// Request headers, keyed by lowercased name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Headers(pub HashMap<String, String>);
+
+impl FromRequest for Headers {
+    type Rejection = ExtractError;
+
+    fn from_request(request: &Request) -> Result<Self, Self::Rejection> {
+        Ok(Headers(request.headers.clone()))
+    }
+}
+
+/// A handler taking extractors as arguments. `Args` lists its return type and
+/// then its argument types, and only serves to tell the impls apart.
+pub trait Handler<Args>: Send + Sync + 'static {
+    fn call(&self, request: &Request) -> io::Result<HttpResponse>;
+}
+
+macro_rules! impl_handler {
+    ($($arg:ident),+) => {
+        impl<F, R, $($arg),+> Handler<(R, $($arg,)+)> for F
+        where
+            F: Fn($($arg),+) -> R + Send + Sync + 'static,
+            R: IntoResponse,
+            $($arg: FromRequest,)+
+        {
+            #[allow(non_snake_case)]
+            fn call(&self, request: &Request) -> io::Result<HttpResponse> {
+                $(
+                    let $arg = match $arg::from_request(request) {
+                        Ok(value) => value,
+                        Err(rejection) => return Ok(rejection.into()),
+                    };
+                )+
+                self($($arg),+).into_response()
+            }
+        }
+    };
+}
+
+impl_handler!(A);
+impl_handler!(A, B);
+impl_handler!(A, B, C);
+impl_handler!(A, B, C, D);
+impl_handler!(A, B, C, D, E);
+impl_handler!(A, B, C, D, E, F2);
+
+/// Adapts a handler taking extractors so it can be registered like any other route.
+///
+/// ```no_run
+/// # #[cfg(feature = "json")]
+/// # fn main() {
+/// # use schnell::http::HttpResponse;
+/// # use schnell::routing::{Json, Path, RouteBuilder, extract};
+/// # use schnell::server::Server;
+/// # #[derive(serde::Deserialize)]
+/// # struct UpdateTodo { title: String }
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// server.put("/todos/:id", extract(|Path(id): Path<u32>, Json(todo): Json<UpdateTodo>| {
+///     HttpResponse::ok().text(&format!("{}: {}", id, todo.title))
+/// }));
+/// # }
+/// # #[cfg(not(feature = "json"))]
+/// # fn main() {}
+/// ```
+pub fn extract<H, Args>(
+    handler: H,
+) -> impl Fn(&Request) -> io::Result<HttpResponse> + Send + Sync + 'static
+where
+    H: Handler<Args>,
+    Args: 'static,
+{
+    move |request| handler.call(request)
+}
+
+macro_rules! deserialize_single {
+    ($($method:ident)+) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                self.single()?.$method(visitor)
+            }
+        )+
+    };
+}
+
+/// Deserializes the whole parameter map into a struct or map, or its only
+/// entry into anything else.
+struct PathDeserializer<'a>(&'a HashMap<String, String>);
+
+impl<'a> PathDeserializer<'a> {
+    fn single(&self) -> Result<ParamDeserializer<'a>, ValueError> {
+        let mut values = self.0.values();
+        match (values.next(), values.next()) {
+            (Some(value), None) => Ok(ParamDeserializer(value)),
+            _ => Err(de::Error::custom(format!(
+                "expected 1 path parameter, found {}",
+                self.0.len()
+            ))),
+        }
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for PathDeserializer<'a> {
+    type Error = ValueError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let entries = self
+            .0
+            .iter()
+            .map(|(key, value)| (key.as_str(), ParamDeserializer(value)));
+        visitor.visit_map(MapDeserializer::new(entries))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    deserialize_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_option
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct identifier ignored_any
+    }
+}
+
+/// One path parameter, parsed into whatever scalar the target type asks for.
+struct ParamDeserializer<'a>(&'a str);
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident),+ $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(self.0), &visitor)),
+                }
+            }
+        )+
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ParamDeserializer<'a> {
+    type Error = ValueError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 str string bytes byte_buf unit unit_struct newtype_struct seq
+        tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, ValueError> for ParamDeserializer<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::io::BufReader;
+
+    fn request(raw: &str, params: &[(&str, &str)]) -> Request {
+        let mut buffer = BufReader::new(raw.as_bytes());
+        let mut request = Request::read(&mut buffer).unwrap();
+        for (key, value) in params {
+            request.params.insert(key.to_string(), value.to_string());
+        }
+        request
+    }
+
+    #[cfg(feature = "forms")]
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Filters {
+        status: String,
+        limit: Option<usize>,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn test_path() {
+        let req = request("GET /users/42 HTTP/1.1\r\n\r\n", &[("id", "42")]);
+        assert_eq!(Path::<u32>::from_request(&req).unwrap(), Path(42));
+        assert_eq!(
+            Path::<String>::from_request(&req).unwrap(),
+            Path("42".to_string())
+        );
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Ids {
+            org: String,
+            id: u64,
+        }
+        let req = request("GET / HTTP/1.1\r\n\r\n", &[("org", "acme"), ("id", "7")]);
+        assert_eq!(
+            Path::<Ids>::from_request(&req).unwrap(),
+            Path(Ids {
+                org: "acme".to_string(),
+                id: 7
+            })
+        );
+        assert!(matches!(
+            Path::<u32>::from_request(&req),
+            Err(ExtractError::InvalidPath(_))
+        ));
+
+        let req = request("GET / HTTP/1.1\r\n\r\n", &[("id", "abc")]);
+        let response: HttpResponse = Path::<u32>::from_request(&req).unwrap_err().into();
+        assert_eq!(response.status_code, 400);
+    }
+
+    #[test]
+    #[cfg(feature = "forms")]
+    fn test_query() {
+        let req = request(
+            "GET /todos?status=open+now&tags[]=a&tags[]=b%20c HTTP/1.1\r\n\r\n",
+            &[],
+        );
+        assert_eq!(
+            Query::<Filters>::from_request(&req).unwrap(),
+            Query(Filters {
+                status: "open now".to_string(),
+                limit: None,
+                tags: vec!["a".to_string(), "b c".to_string()],
+            })
+        );
+
+        let req = request("GET /todos?limit=ten HTTP/1.1\r\n\r\n", &[]);
+        assert!(matches!(
+            Query::<Filters>::from_request(&req),
+            Err(ExtractError::InvalidQuery(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_extract_handler() {
+        #[derive(Deserialize)]
+        struct Rename {
+            name: String,
+        }
+
+        let handler = extract(
+            |Path(id): Path<u32>, Json(body): Json<Rename>, Headers(headers): Headers| {
+                let agent = headers.get("user-agent").map_or("-", |v| v.as_str());
+                format!("{} {} {}", id, body.name, agent)
+            },
+        );
+
+        let raw = "PUT /users/7 HTTP/1.1\r\nUser-Agent: curl\r\n\
+                   Content-Type: application/json\r\nContent-Length: 14\r\n\r\n{\"name\":\"Ada\"}";
+        let response = handler(&request(raw, &[("id", "7")])).unwrap();
+        assert_eq!(response.body, "7 Ada curl");
+
+        let raw = "PUT /users/7 HTTP/1.1\r\nContent-Length: 14\r\n\r\n{\"name\":\"Ada\"}";
+        let response = handler(&request(raw, &[("id", "7")])).unwrap();
+        assert_eq!(response.status_code, 415);
+    }
+}
@@ -0,0 +1,246 @@
+/* Canned responses for standing up a stub API, e.g. for frontend development */
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::http::{HttpResponse, Request};
+
+/// A stub response, registered with `RouteBuilder::mock`.
+///
+/// Bodies are templates: `{{params.id}}` and `{{query.page}}` are filled in
+/// from the request, and `{{random.int}}`, `{{random.bool}}` and
+/// `{{random.uuid}}` with a fresh value on every call. Fixture files are read
+/// on each request, so edits show up without a restart.
+///
+/// ```no_run
+/// # use schnell::routing::{MockSpec, RouteBuilder};
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// server.mock("/api/users", MockSpec::json_file("fixtures/users.json").latency_ms(200));
+/// server.mock("/api/users/:id", MockSpec::json(r#"{ "id": {{params.id}}, "name": "User {{params.id}}" }"#));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockSpec {
+    status_code: u16,
+    content_type: String,
+    body: MockBody,
+    latency: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+enum MockBody {
+    Inline(String),
+    File(PathBuf),
+}
+
+impl MockSpec {
+    pub fn json(body: &str) -> Self {
+        Self::inline("application/json", body)
+    }
+
+    pub fn text(body: &str) -> Self {
+        Self::inline("text/plain", body)
+    }
+
+    pub fn json_file<P: AsRef<Path>>(path: P) -> Self {
+        Self::file_with_type("application/json", path)
+    }
+
+    /// A fixture file, served with a content type guessed from its extension.
+    pub fn file<P: AsRef<Path>>(path: P) -> Self {
+        let content_type = content_type_for(path.as_ref());
+        Self::file_with_type(content_type, path)
+    }
+
+    pub fn status(self, status_code: u16) -> Self {
+        let mut spec = self;
+        spec.status_code = status_code;
+        spec
+    }
+
+    /// Delays every response, to see how the frontend copes with a slow API.
+    pub fn latency_ms(self, millis: u64) -> Self {
+        let mut spec = self;
+        spec.latency = Some(Duration::from_millis(millis));
+        spec
+    }
+
+    pub fn respond(&self, request: &Request) -> io::Result<HttpResponse> {
+        if let Some(latency) = self.latency {
+            thread::sleep(latency);
+        }
+
+        let template = match &self.body {
+            MockBody::Inline(body) => body.clone(),
+            MockBody::File(path) => fs::read_to_string(path)?,
+        };
+        Ok(HttpResponse::new(self.status_code)
+            .body(&render(&template, request))
+            .content_type(&self.content_type))
+    }
+
+    fn inline(content_type: &str, body: &str) -> Self {
+        Self {
+            status_code: 200,
+            content_type: content_type.to_string(),
+            body: MockBody::Inline(body.to_string()),
+            latency: None,
+        }
+    }
+
+    fn file_with_type<P: AsRef<Path>>(content_type: &str, path: P) -> Self {
+        Self {
+            status_code: 200,
+            content_type: content_type.to_string(),
+            body: MockBody::File(path.as_ref().to_path_buf()),
+            latency: None,
+        }
+    }
+}
+
+/// GET routes for every file under `dir`, by relative path without the
+/// extension: `users/index.json` serves `/users`, and `users/[id].json` serves
+/// `/users/:id`.
+pub(crate) fn mock_routes(dir: &Path) -> io::Result<Vec<(String, MockSpec)>> {
+    let mut routes = Vec::new();
+    collect_mocks(dir, "", &mut routes)?;
+    routes.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(routes)
+}
+
+fn collect_mocks(dir: &Path, prefix: &str, routes: &mut Vec<(String, MockSpec)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let segment = match name.strip_prefix('[').and_then(|n| n.strip_suffix(']')) {
+            Some(param) => format!(":{}", param),
+            None => name.to_string(),
+        };
+
+        if path.is_dir() {
+            collect_mocks(&path, &format!("{}/{}", prefix, segment), routes)?;
+        } else if segment == "index" {
+            let route = if prefix.is_empty() { "/" } else { prefix };
+            routes.push((route.to_string(), MockSpec::file(&path)));
+        } else {
+            routes.push((format!("{}/{}", prefix, segment), MockSpec::file(&path)));
+        }
+    }
+    Ok(())
+}
+
+fn content_type_for(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => "application/json",
+        Some("html" | "htm") => "text/html",
+        Some("xml") => "application/xml",
+        Some("csv") => "text/csv",
+        _ => "text/plain",
+    }
+}
+
+fn render(template: &str, request: &Request) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let placeholder = &rest[start + 2..start + end];
+        match placeholder_value(placeholder.trim(), request) {
+            Some(value) => output.push_str(&value),
+            None => output.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Unknown placeholders are left as they are, so typos show up in the output.
+fn placeholder_value(placeholder: &str, request: &Request) -> Option<String> {
+    match placeholder.split_once('.')? {
+        ("params", name) => request.params.get(name).cloned(),
+        ("query", name) => request.query.get(name).cloned(),
+        ("random", "int") => Some((random() % 1_000_000).to_string()),
+        ("random", "bool") => Some(random().is_multiple_of(2).to_string()),
+        ("random", "uuid") => {
+            let (high, low) = (random(), random());
+            Some(format!(
+                "{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
+                high >> 32,
+                (high >> 16) & 0xffff,
+                high & 0xfff,
+                0x8000 | (low >> 48) & 0x3fff,
+                low & 0xffff_ffff_ffff
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Not for anything security-related: mocks only need values that vary.
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn request(raw: &str) -> Request {
+        let mut buffer = BufReader::new(raw.as_bytes());
+        let mut request = Request::read(&mut buffer).unwrap();
+        request.params.insert("id".to_string(), "7".to_string());
+        request
+    }
+
+    #[test]
+    fn test_templating() {
+        let spec = MockSpec::json(
+            r#"{"id":{{params.id}},"page":"{{ query.page }}","token":"{{random.uuid}}","x":"{{nope}}"}"#,
+        )
+        .status(201);
+        let response = spec
+            .respond(&request("GET /users/7?page=2 HTTP/1.1\r\n\r\n"))
+            .unwrap();
+
+        assert_eq!(response.status_code, 201);
+        assert_eq!(response.content_type, "application/json");
+        assert!(response.body.starts_with(r#"{"id":7,"page":"2","token":""#));
+        assert!(response.body.ends_with(r#"","x":"{{nope}}"}"#));
+        let token = response.body.split('"').nth(9).unwrap();
+        assert_eq!(token.len(), 36);
+        assert_eq!(&token[14..15], "4");
+    }
+
+    #[test]
+    fn test_fixture_directory() {
+        let dir = std::env::temp_dir().join(format!("schnell-mocks-{}", std::process::id()));
+        fs::create_dir_all(dir.join("users")).unwrap();
+        fs::write(dir.join("health.txt"), "up").unwrap();
+        fs::write(dir.join("users/index.json"), "[]").unwrap();
+        fs::write(dir.join("users/[id].json"), r#"{"id":{{params.id}}}"#).unwrap();
+
+        let routes = mock_routes(&dir).unwrap();
+        let paths: Vec<&str> = routes.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["/health", "/users", "/users/:id"]);
+
+        let response = routes[2]
+            .1
+            .respond(&request("GET /users/7 HTTP/1.1\r\n\r\n"))
+            .unwrap();
+        assert_eq!(response.body, r#"{"id":7}"#);
+        assert_eq!(routes[0].1.content_type, "text/plain");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
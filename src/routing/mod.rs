@@ -1,2 +1,31 @@
+#[cfg(feature = "autoload")]
+pub mod autoload;
+pub mod breaker;
 pub mod builder;
+pub mod context;
+pub mod errors;
+pub mod extract;
+pub mod mock;
+pub mod requirements;
+pub mod resolver;
 pub mod route;
+pub mod router;
+pub mod table;
+pub mod versioning;
+
+pub use breaker::{BreakerRegistry, CircuitBreaker};
+pub use builder::RouteBuilder;
+pub use context::Context;
+pub use errors::{ErrorHandler, ErrorHandlers};
+#[cfg(feature = "json")]
+pub use extract::Json;
+#[cfg(feature = "forms")]
+pub use extract::{Form, Query};
+pub use extract::{FromRequest, Headers, Path, extract};
+pub use mock::MockSpec;
+pub use requirements::RouteRequirements;
+pub use resolver::RouteResolver;
+pub use route::{Route, RouteError, RouteHandler};
+pub use router::{RouteGroup, Router};
+pub use table::{RouteDiff, RouteTable};
+pub use versioning::VersionedHandlers;
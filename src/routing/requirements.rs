@@ -0,0 +1,132 @@
+use crate::http::{HttpResponse, Request};
+
+/// Preconditions checked before a route's handler runs, answered with
+/// consistent 400/411/413 responses when they aren't met.
+///
+/// ```no_run
+/// # use schnell::http::{HttpResponse, Request};
+/// # use schnell::routing::{RouteBuilder, RouteRequirements};
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// # let import_handler = |_: &Request| Ok(HttpResponse::new(202));
+/// let tenant_only = RouteRequirements::new()
+///     .require_header("X-Tenant-Id")
+///     .require_content_length_under(64 * 1024);
+/// server.post("/imports", tenant_only.wrap(import_handler));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RouteRequirements {
+    headers: Vec<String>,
+    max_content_length: Option<usize>,
+}
+
+impl RouteRequirements {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rejects requests without a non-empty `name` header with 400.
+    pub fn require_header(self, name: &str) -> Self {
+        let mut requirements = self;
+        requirements.headers.push(name.to_string());
+        requirements
+    }
+
+    /// Rejects requests without a `Content-Length` with 411 and those declaring
+    /// `max` bytes or more with 413.
+    pub fn require_content_length_under(self, max: usize) -> Self {
+        let mut requirements = self;
+        requirements.max_content_length = Some(max);
+        requirements
+    }
+
+    /// The response for the first unmet requirement, if any.
+    pub fn check(&self, request: &Request) -> Option<HttpResponse> {
+        for name in &self.headers {
+            let present = request
+                .headers
+                .get(&name.to_lowercase())
+                .is_some_and(|v| !v.is_empty());
+            if !present {
+                return Some(
+                    HttpResponse::bad_request().text(&format!("Missing required header {}", name)),
+                );
+            }
+        }
+
+        if let Some(max) = self.max_content_length {
+            let Some(length) = request.headers.get("content-length") else {
+                return Some(HttpResponse::new(411).text("Content-Length is required"));
+            };
+            match length.parse::<usize>() {
+                Ok(length) if length < max => {}
+                Ok(_) => {
+                    return Some(
+                        HttpResponse::request_entity_too_large()
+                            .text(&format!("Request body must be under {} bytes", max)),
+                    );
+                }
+                Err(_) => return Some(HttpResponse::bad_request().text("Invalid Content-Length")),
+            }
+        }
+
+        None
+    }
+
+    /// Wraps `handler` so it only runs once every requirement is met.
+    pub fn wrap<H>(
+        self,
+        handler: H,
+    ) -> impl Fn(&Request) -> std::io::Result<HttpResponse> + Send + Sync + 'static
+    where
+        H: Fn(&Request) -> std::io::Result<HttpResponse> + Send + Sync + 'static,
+    {
+        move |request| match self.check(request) {
+            Some(rejection) => Ok(rejection),
+            None => handler(request),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn request(raw: &str) -> Request {
+        let mut buffer = BufReader::new(raw.as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    #[test]
+    fn test_require_header() {
+        let handler = RouteRequirements::new()
+            .require_header("X-Tenant-Id")
+            .wrap(|_| Ok(HttpResponse::ok()));
+
+        let response = handler(&request("GET / HTTP/1.1\r\n\r\n")).unwrap();
+        assert_eq!(response.status_code, 400);
+        assert_eq!(response.body, "Missing required header X-Tenant-Id");
+
+        let response = handler(&request("GET / HTTP/1.1\r\nX-Tenant-Id: acme\r\n\r\n")).unwrap();
+        assert_eq!(response.status_code, 200);
+    }
+
+    #[test]
+    fn test_require_content_length_under() {
+        let handler = RouteRequirements::new()
+            .require_content_length_under(5)
+            .wrap(|_| Ok(HttpResponse::ok()));
+
+        let status = |raw: &str| handler(&request(raw)).unwrap().status_code;
+        assert_eq!(status("POST / HTTP/1.1\r\n\r\n"), 411);
+        assert_eq!(
+            status("POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\nabcd"),
+            200
+        );
+        assert_eq!(
+            status("POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nabcde"),
+            413
+        );
+    }
+}
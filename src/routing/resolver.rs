@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use crate::http::HttpMethod;
+use crate::routing::route::{Route, RouteError};
+
+pub fn match_route(route: &str, incoming: &str) -> bool {
+    let route_parts = route.split('/').collect::<Vec<&str>>();
+    let incoming_parts = incoming.split('/').collect::<Vec<&str>>();
+
+    if route_parts.len() != incoming_parts.len() {
+        return false;
+    }
+
+    for (route_part, incoming_part) in route_parts.iter().zip(incoming_parts.iter()) {
+        if route_part.starts_with(':') {
+            continue;
+        }
+        if route_part != incoming_part {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Collects the `:name` segments of `route` from a path it matched.
+pub fn extract_params(route: &str, incoming: &str) -> HashMap<String, String> {
+    route
+        .split('/')
+        .zip(incoming.split('/'))
+        .filter_map(|(route_part, incoming_part)| {
+            route_part
+                .strip_prefix(':')
+                .map(|name| (name.to_string(), incoming_part.to_string()))
+        })
+        .collect()
+}
+
+pub trait RouteResolver {
+    fn resolve<'a>(
+        &self,
+        path: &str,
+        method: HttpMethod,
+        routes: &'a [Route],
+    ) -> Result<&'a Route, RouteError> {
+        let mut path_matched = false;
+
+        for route in routes {
+            if match_route(&route.path, path) {
+                if route.method == method {
+                    return Ok(route);
+                }
+                path_matched = true;
+            }
+        }
+
+        if path_matched {
+            Err(RouteError::MethodNotAllowed)
+        } else {
+            Err(RouteError::NotFound)
+        }
+    }
+
+    /// Methods `path` can be requested with, for an `Allow` header: those with a
+    /// route, plus HEAD wherever GET is routed and OPTIONS for any known path.
+    fn allowed_methods(&self, path: &str, routes: &[Route]) -> Vec<HttpMethod> {
+        let mut methods: Vec<HttpMethod> = Vec::new();
+        for route in routes.iter().filter(|route| match_route(&route.path, path)) {
+            if !methods.contains(&route.method) {
+                methods.push(route.method);
+            }
+        }
+
+        if methods.contains(&HttpMethod::GET) && !methods.contains(&HttpMethod::HEAD) {
+            methods.push(HttpMethod::HEAD);
+        }
+        if !methods.is_empty() && !methods.contains(&HttpMethod::OPTIONS) {
+            methods.push(HttpMethod::OPTIONS);
+        }
+        methods
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpResponse;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_match_route() {
+        assert!(match_route("/", "/"));
+        assert!(match_route("/users", "/users"));
+        assert!(match_route("/users/:id", "/users/123"));
+        assert!(!match_route(
+            "/users/messages/:message_id",
+            "/users/:userid"
+        ));
+        assert!(match_route(
+            "/users/:user_id/messages/:message_id",
+            "/users/123/messages/456"
+        ));
+        assert!(!match_route(
+            "/users/messages/:message_id",
+            "/users/123/messages/456/"
+        ));
+    }
+
+    #[test]
+    fn test_extract_params() {
+        let params = extract_params("/users/:id", "/users/123");
+        assert_eq!(params.get("id"), Some(&"123".to_string()));
+
+        let params = extract_params(
+            "/users/:user_id/messages/:message_id",
+            "/users/1/messages/2",
+        );
+        assert_eq!(params.len(), 2);
+        assert_eq!(params.get("user_id"), Some(&"1".to_string()));
+        assert_eq!(params.get("message_id"), Some(&"2".to_string()));
+
+        assert!(extract_params("/users", "/users").is_empty());
+    }
+
+    #[test]
+    fn test_route_resolver() {
+        struct TestRouter {
+            routes: Vec<Route>,
+        }
+
+        impl RouteResolver for TestRouter {}
+
+        let router = TestRouter {
+            routes: vec![
+                Route {
+                    method: HttpMethod::GET,
+                    path: "/users".to_string(),
+                    handler: Arc::new(|_| Ok(HttpResponse::ok())),
+                    middleware: None,
+                },
+                Route {
+                    method: HttpMethod::POST,
+                    path: "/users".to_string(),
+                    handler: Arc::new(|_| Ok(HttpResponse::ok())),
+                    middleware: None,
+                },
+                Route {
+                    method: HttpMethod::GET,
+                    path: "/users/:id".to_string(),
+                    handler: Arc::new(|_| Ok(HttpResponse::ok())),
+                    middleware: None,
+                },
+                Route {
+                    method: HttpMethod::GET,
+                    path: "/users/:id/messages/:message_id".to_string(),
+                    handler: Arc::new(|_| Ok(HttpResponse::ok())),
+                    middleware: None,
+                },
+            ],
+        };
+
+        let route = router.resolve("/users", HttpMethod::GET, &router.routes);
+        assert!(route.is_ok());
+        assert_eq!(route.unwrap().path, "/users");
+
+        let route = router.resolve("/users", HttpMethod::POST, &router.routes);
+        assert!(route.is_ok());
+        assert_eq!(route.unwrap().method, HttpMethod::POST);
+
+        let route = router.resolve("/users/123", HttpMethod::GET, &router.routes);
+        assert!(route.is_ok());
+        assert_eq!(route.unwrap().path, "/users/:id");
+
+        let route = router.resolve("/users/123/messages/456", HttpMethod::GET, &router.routes);
+        assert!(route.is_ok());
+        assert_eq!(route.unwrap().path, "/users/:id/messages/:message_id");
+
+        let route = router.resolve("/users/123/messages/456", HttpMethod::POST, &router.routes);
+        assert!(route.is_err());
+        assert_eq!(route.unwrap_err(), RouteError::MethodNotAllowed);
+
+        let route = router.resolve("/posts", HttpMethod::GET, &router.routes);
+        assert_eq!(route.unwrap_err(), RouteError::NotFound);
+
+        assert_eq!(
+            router.allowed_methods("/users", &router.routes),
+            [
+                HttpMethod::GET,
+                HttpMethod::POST,
+                HttpMethod::HEAD,
+                HttpMethod::OPTIONS
+            ]
+        );
+        assert!(router.allowed_methods("/posts", &router.routes).is_empty());
+    }
+}
@@ -1,12 +1,52 @@
-use crate::http::{HttpMethod, HttpResponse, Request};
+use std::fmt;
+use std::sync::Arc;
 
-pub type RouteHandler = fn(&Request) -> std::io::Result<HttpResponse>;
+use crate::http::{HttpMethod, HttpResponse, IntoResponse, Request};
+use crate::middleware::Next;
+use crate::routing::router::MiddlewareChain;
+
+/// Shared rather than boxed, so a handler that outlives its handler timeout can
+/// finish on a thread of its own.
+pub type RouteHandler = Arc<dyn Fn(&Request) -> std::io::Result<HttpResponse> + Send + Sync>;
+
+pub(crate) fn into_route_handler<H, R>(handler: H) -> RouteHandler
+where
+    H: Fn(&Request) -> R + Send + Sync + 'static,
+    R: IntoResponse,
+{
+    Arc::new(move |request| handler(request).into_response())
+}
 
-#[derive(Debug, PartialEq)]
 pub struct Route {
     pub method: HttpMethod,
     pub path: String,
     pub handler: RouteHandler,
+    /// Middleware of the group the route was registered in.
+    pub(crate) middleware: Option<MiddlewareChain>,
+}
+
+impl Route {
+    /// Runs the route's group middleware on `request`, then `endpoint`, which
+    /// calls the handler.
+    pub(crate) fn run(
+        &self,
+        request: &mut Request,
+        endpoint: &dyn Fn(&mut Request) -> std::io::Result<HttpResponse>,
+    ) -> std::io::Result<HttpResponse> {
+        match &self.middleware {
+            Some(chain) => Next::new(chain, endpoint).run(request),
+            None => endpoint(request),
+        }
+    }
+}
+
+impl fmt::Debug for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Route")
+            .field("method", &self.method)
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -0,0 +1,270 @@
+use std::sync::Arc;
+
+use crate::{
+    http::{HttpMethod, HttpResponse, Request, uri::normalize_path},
+    middleware::Middleware,
+    routing::{ErrorHandlers, Route, RouteBuilder, RouteError, RouteHandler, RouteResolver},
+    utils::join_path,
+};
+
+pub(crate) type MiddlewareChain = Arc<Vec<Box<dyn Middleware>>>;
+
+pub struct Router {
+    prefix: String,
+    routes: Vec<Route>,
+    error_handlers: ErrorHandlers,
+}
+
+pub struct RouteGroup<'a> {
+    pub prefix: String,
+    pub routes: &'a mut Vec<Route>,
+    pub error_handlers: &'a mut ErrorHandlers,
+    pub(crate) middleware: Vec<Box<dyn Middleware>>,
+}
+
+impl<'a> RouteGroup<'a> {
+    pub(crate) fn new(
+        prefix: String,
+        routes: &'a mut Vec<Route>,
+        error_handlers: &'a mut ErrorHandlers,
+    ) -> Self {
+        Self {
+            prefix,
+            routes,
+            error_handlers,
+            middleware: Vec::new(),
+        }
+    }
+
+    /// Runs `config` on the group, then attaches the group's middleware to
+    /// every route it registered. Returns the group's prefix and middleware,
+    /// if it has any, for requests no route handles, such as CORS preflights.
+    pub(crate) fn configure<F>(mut self, config: F) -> Option<(String, MiddlewareChain)>
+    where
+        F: FnOnce(&mut RouteGroup),
+    {
+        let first = self.routes.len();
+        config(&mut self);
+        if self.middleware.is_empty() {
+            return None;
+        }
+
+        let chain = Arc::new(self.middleware);
+        for route in &mut self.routes[first..] {
+            route.middleware = Some(Arc::clone(&chain));
+        }
+        Some((self.prefix, chain))
+    }
+
+    /// Adds `middleware` around this group's routes only, after the
+    /// server-wide chain and once the route has matched, so path params are
+    /// set. It applies to the whole group, whatever order routes are added in.
+    /// CORS preflights to the group's paths also run through it, so a group
+    /// can have a [`CorsConfig`](crate::middleware::CorsConfig) of its own.
+    ///
+    /// ```no_run
+    /// # use schnell::http::{HttpResponse, Request};
+    /// # use schnell::middleware::BasicAuth;
+    /// # use schnell::routing::RouteBuilder;
+    /// # use schnell::server::Server;
+    /// # let mut server = Server::new("0.0.0.0", 8080, None);
+    /// # let check_password = |_: &str, _: &str| false;
+    /// # let stats = |_: &Request| HttpResponse::ok();
+    /// server.group("/admin", |group| {
+    ///     group.with(BasicAuth::new("admin", check_password));
+    ///     group.get("/stats", stats);
+    /// });
+    /// ```
+    pub fn with<M: Middleware + 'static>(&mut self, middleware: M) {
+        self.middleware.push(Box::new(middleware));
+    }
+
+    /// Error page for `status_code` used for requests under this group's prefix,
+    /// in place of the server-wide one.
+    pub fn error_handler<H>(&mut self, status_code: u16, handler: H)
+    where
+        H: Fn(&Request) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.error_handlers
+            .register(&self.prefix, status_code, handler);
+    }
+}
+
+impl RouteBuilder for RouteGroup<'_> {
+    type Error = RouteError;
+
+    fn register(&mut self, path: &str, method: HttpMethod, handler: RouteHandler) {
+        self.routes.push(Route {
+            path: normalize_path(&join_path(&self.prefix, path)),
+            method,
+            handler,
+            middleware: None,
+        });
+    }
+}
+
+impl RouteResolver for Router {}
+
+impl Router {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            routes: Vec::new(),
+            error_handlers: ErrorHandlers::default(),
+        }
+    }
+
+    pub fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+
+    pub fn group<F>(&mut self, prefix: &str, config: F)
+    where
+        F: FnOnce(&mut RouteGroup),
+    {
+        RouteGroup::new(
+            join_path(&self.prefix, prefix),
+            &mut self.routes,
+            &mut self.error_handlers,
+        )
+        .configure(config);
+    }
+}
+
+impl RouteBuilder for Router {
+    type Error = RouteError;
+
+    fn register(&mut self, path: &str, method: HttpMethod, handler: RouteHandler) {
+        let path = normalize_path(&join_path(&self.prefix, path));
+        if let Some(matching_route_idx) = self
+            .routes
+            .iter()
+            .position(|r| r.path == path && r.method == method)
+        {
+            log::warn!(
+                "Route {} {:?} already exists and will be overwritten",
+                method,
+                path
+            );
+            self.routes[matching_route_idx] = Route {
+                path,
+                method,
+                handler,
+                middleware: None,
+            };
+        } else {
+            self.routes.push(Route {
+                path,
+                method,
+                handler,
+                middleware: None,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::Next;
+    use std::io::BufReader;
+
+    #[test]
+    fn test_group() {
+        let mut router = Router::new("/api");
+        router.group("/v1", |group| group.get("/users", |_| HttpResponse::ok()));
+
+        assert_eq!(router.routes.len(), 1);
+        assert_eq!(router.routes[0].method, HttpMethod::GET);
+        assert_eq!(router.routes[0].path, "/api/v1/users");
+    }
+
+    #[test]
+    fn test_group_middleware() {
+        let mut router = Router::new("/");
+        router.get("/status", |_| HttpResponse::ok());
+        router.group("/admin", |group| {
+            group.get("/stats", |req| {
+                HttpResponse::ok().text(&req.headers["x-user"])
+            });
+            group.with(
+                |req: &mut Request, next: Next| match req.headers.get("authorization") {
+                    Some(_) => {
+                        req.headers.insert("x-user".to_string(), "root".to_string());
+                        next.run(req)
+                    }
+                    None => Ok(HttpResponse::unauthorized()),
+                },
+            );
+        });
+
+        let call = |index: usize, raw: &str| {
+            let mut buffer = BufReader::new(raw.as_bytes());
+            let mut request = Request::read(&mut buffer).unwrap();
+            let route = &router.routes[index];
+            let endpoint = |request: &mut Request| (route.handler)(request);
+            route.run(&mut request, &endpoint).unwrap()
+        };
+        assert_eq!(call(0, "GET /status HTTP/1.1\r\n\r\n").status_code, 200);
+        assert_eq!(
+            call(1, "GET /admin/stats HTTP/1.1\r\n\r\n").status_code,
+            401
+        );
+        let response = call(
+            1,
+            "GET /admin/stats HTTP/1.1\r\nAuthorization: Basic x\r\n\r\n",
+        );
+        assert_eq!(response.body, "root");
+    }
+
+    #[test]
+    fn test_router_register_route() {
+        let mut router = Router::new("/api");
+        router.register(
+            "/users",
+            HttpMethod::GET,
+            Arc::new(|_| Ok(HttpResponse::ok())),
+        );
+
+        assert_eq!(router.routes.len(), 1);
+        assert_eq!(router.routes[0].method, HttpMethod::GET);
+        assert_eq!(router.routes[0].path, "/api/users");
+    }
+
+    #[test]
+    fn test_router_register_route_overwrites() {
+        let mut router = Router::new("/api");
+        router.get("/users", |_| HttpResponse::ok());
+        router.get("/users", |_| HttpResponse::created());
+
+        assert_eq!(router.routes.len(), 1);
+    }
+
+    #[test]
+    fn test_router_http_verbs() {
+        let mut router = Router::new("/api");
+        router.get("/users", |_| HttpResponse::ok());
+        router.post("/users", |_| HttpResponse::ok());
+        router.put("/users", |_| HttpResponse::ok());
+        router.patch("/users", |_| HttpResponse::ok());
+        router.delete("/users", |_| HttpResponse::ok());
+        router.head("/users", |_| HttpResponse::ok());
+        router.options("/users", |_| HttpResponse::ok());
+
+        assert_eq!(router.routes.len(), 7);
+        assert_eq!(router.routes[0].method, HttpMethod::GET);
+        assert_eq!(router.routes[0].path, "/api/users");
+        assert_eq!(router.routes[1].method, HttpMethod::POST);
+        assert_eq!(router.routes[1].path, "/api/users");
+        assert_eq!(router.routes[2].method, HttpMethod::PUT);
+        assert_eq!(router.routes[2].path, "/api/users");
+        assert_eq!(router.routes[3].method, HttpMethod::PATCH);
+        assert_eq!(router.routes[3].path, "/api/users");
+        assert_eq!(router.routes[4].method, HttpMethod::DELETE);
+        assert_eq!(router.routes[4].path, "/api/users");
+        assert_eq!(router.routes[5].method, HttpMethod::HEAD);
+        assert_eq!(router.routes[5].path, "/api/users");
+        assert_eq!(router.routes[6].method, HttpMethod::OPTIONS);
+        assert_eq!(router.routes[6].path, "/api/users");
+    }
+}
@@ -0,0 +1,186 @@
+/* Route table export and diffing, e.g. to catch removed endpoints in CI */
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::http::HttpMethod;
+use crate::routing::route::Route;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteEntry {
+    pub method: HttpMethod,
+    pub path: String,
+    /// Names of the `:param` segments, in order.
+    pub params: Vec<String>,
+}
+
+impl RouteEntry {
+    /// Identity across exports: the method plus the path with parameter names
+    /// blanked out, so renaming `:id` to `:user_id` is a change, not a removal.
+    fn key(&self) -> (HttpMethod, String) {
+        let shape = self
+            .path
+            .split('/')
+            .map(|part| if part.starts_with(':') { ":" } else { part })
+            .collect::<Vec<_>>()
+            .join("/");
+        (self.method, shape)
+    }
+}
+
+impl fmt::Display for RouteEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.path)
+    }
+}
+
+/// Every registered route, sorted by path then method so exports of the same
+/// server are byte-for-byte identical.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RouteTable {
+    pub routes: Vec<RouteEntry>,
+}
+
+impl RouteTable {
+    pub fn from_routes(routes: &[Route]) -> Self {
+        let mut routes: Vec<RouteEntry> = routes
+            .iter()
+            .map(|route| RouteEntry {
+                method: route.method,
+                path: route.path.clone(),
+                params: route
+                    .path
+                    .split('/')
+                    .filter_map(|part| part.strip_prefix(':'))
+                    .map(str::to_string)
+                    .collect(),
+            })
+            .collect();
+        routes.sort_by(|a, b| (&a.path, a.method.as_str()).cmp(&(&b.path, b.method.as_str())));
+        Self { routes }
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("route tables always serialize")
+    }
+
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// What changed going from `self` to `newer`.
+    pub fn diff(&self, newer: &RouteTable) -> RouteDiff {
+        let mut diff = RouteDiff::default();
+
+        for old in &self.routes {
+            match newer.routes.iter().find(|new| new.key() == old.key()) {
+                None => diff.removed.push(old.clone()),
+                Some(new) if new != old => diff.changed.push((old.clone(), new.clone())),
+                Some(_) => {}
+            }
+        }
+        diff.added = newer
+            .routes
+            .iter()
+            .filter(|new| !self.routes.iter().any(|old| old.key() == new.key()))
+            .cloned()
+            .collect();
+
+        diff
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RouteDiff {
+    pub added: Vec<RouteEntry>,
+    pub removed: Vec<RouteEntry>,
+    /// `(before, after)` pairs for routes whose parameter names changed.
+    pub changed: Vec<(RouteEntry, RouteEntry)>,
+}
+
+impl RouteDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Whether clients of the old table could break: something was removed or changed.
+    pub fn is_breaking(&self) -> bool {
+        !self.removed.is_empty() || !self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for RouteDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for route in &self.added {
+            writeln!(f, "+ {}", route)?;
+        }
+        for route in &self.removed {
+            writeln!(f, "- {}", route)?;
+        }
+        for (before, after) in &self.changed {
+            writeln!(f, "~ {} -> {}", before, after.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpResponse;
+    use std::sync::Arc;
+
+    fn table(routes: &[(HttpMethod, &str)]) -> RouteTable {
+        let routes: Vec<Route> = routes
+            .iter()
+            .map(|(method, path)| Route {
+                method: *method,
+                path: path.to_string(),
+                handler: Arc::new(|_| Ok(HttpResponse::ok())),
+                middleware: None,
+            })
+            .collect();
+        RouteTable::from_routes(&routes)
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_export_round_trip() {
+        let table = table(&[
+            (HttpMethod::POST, "/users"),
+            (HttpMethod::GET, "/users/:id"),
+            (HttpMethod::GET, "/users"),
+        ]);
+
+        assert_eq!(table.routes[0].to_string(), "GET /users");
+        assert_eq!(table.routes[2].params, ["id"]);
+        assert_eq!(RouteTable::from_json(&table.to_json()).unwrap(), table);
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = table(&[
+            (HttpMethod::GET, "/users"),
+            (HttpMethod::GET, "/users/:id"),
+            (HttpMethod::DELETE, "/users/:id"),
+        ]);
+        let after = table(&[
+            (HttpMethod::GET, "/users"),
+            (HttpMethod::GET, "/users/:user_id"),
+            (HttpMethod::POST, "/users"),
+        ]);
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.changed.len(), 1);
+        assert!(diff.is_breaking());
+        assert_eq!(
+            diff.to_string(),
+            "+ POST /users\n- DELETE /users/:id\n~ GET /users/:id -> /users/:user_id\n"
+        );
+        assert!(after.diff(&after).is_empty());
+    }
+}
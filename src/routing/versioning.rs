@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::http::{HttpResponse, IntoResponse, Request};
+use crate::routing::route::{RouteHandler, into_route_handler};
+
+/// Handlers for one route, keyed by API version. The version is taken from an
+/// `X-Api-Version` header (`2` or `v2`) or from the `Accept` header, either as a
+/// vendor media type (`application/vnd.acme.v2+json`) or a `version=2` parameter.
+///
+/// ```no_run
+/// # use schnell::http::{HttpResponse, Request};
+/// # use schnell::routing::RouteBuilder;
+/// # use schnell::server::Server;
+/// # let mut server = Server::new("0.0.0.0", 8080, None);
+/// # let list_users_v1 = |_: &Request| HttpResponse::ok();
+/// # let list_users_v2 = |_: &Request| HttpResponse::ok();
+/// server.versioned("/users", |v| {
+///     v.v1(list_users_v1);
+///     v.v2(list_users_v2);
+/// });
+/// ```
+#[derive(Default)]
+pub struct VersionedHandlers {
+    handlers: BTreeMap<u32, RouteHandler>,
+    default_version: Option<u32>,
+}
+
+impl VersionedHandlers {
+    pub fn version<H, R>(&mut self, version: u32, handler: H)
+    where
+        H: Fn(&Request) -> R + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        self.handlers.insert(version, into_route_handler(handler));
+    }
+
+    pub fn v1<H, R>(&mut self, handler: H)
+    where
+        H: Fn(&Request) -> R + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        self.version(1, handler)
+    }
+
+    pub fn v2<H, R>(&mut self, handler: H)
+    where
+        H: Fn(&Request) -> R + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        self.version(2, handler)
+    }
+
+    pub fn v3<H, R>(&mut self, handler: H)
+    where
+        H: Fn(&Request) -> R + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        self.version(3, handler)
+    }
+
+    /// Version served when the request doesn't ask for one. Defaults to the
+    /// oldest registered version, so existing clients keep their behaviour.
+    pub fn default_version(&mut self, version: u32) {
+        self.default_version = Some(version);
+    }
+
+    /// Collapses the handlers into a single route handler. Requests for a version
+    /// that isn't registered or can't be parsed get a 400; every response
+    /// carries `X-Api-Version`.
+    pub fn into_handler(self) -> RouteHandler {
+        Arc::new(move |request| {
+            let requested = match requested_version(request) {
+                Ok(requested) => requested,
+                Err(value) => {
+                    return Ok(HttpResponse::bad_request()
+                        .text(&format!("Invalid API version {:?}", value)));
+                }
+            };
+            let version = match requested {
+                Some(version) => version,
+                None => match self
+                    .default_version
+                    .or(self.handlers.keys().next().copied())
+                {
+                    Some(version) => version,
+                    None => return Ok(HttpResponse::not_found()),
+                },
+            };
+
+            match self.handlers.get(&version) {
+                Some(handler) => {
+                    Ok(handler(request)?.header("X-Api-Version", &version.to_string()))
+                }
+                None => Ok(HttpResponse::bad_request()
+                    .text(&format!("Unsupported API version {}", version))),
+            }
+        })
+    }
+}
+
+/// The API version a request asks for, if any, or the `X-Api-Version` value
+/// if it isn't a version number.
+pub fn requested_version(request: &Request) -> Result<Option<u32>, &str> {
+    if let Some(value) = request.headers.get("x-api-version") {
+        return parse_version(value).map(Some).ok_or(value.as_str());
+    }
+
+    let Some(accept) = request.headers.get("accept") else {
+        return Ok(None);
+    };
+    Ok(accept.split(',').find_map(|media_type| {
+        let mut parts = media_type.split(';').map(str::trim);
+        let essence = parts.next()?;
+
+        // application/vnd.acme.v2+json
+        let vendor = essence
+            .split_once("/vnd.")
+            .and_then(|(_, subtype)| subtype.split('+').next())
+            .and_then(|subtype| subtype.rsplit('.').next())
+            .and_then(parse_version);
+
+        // application/json; version=2
+        vendor.or_else(|| {
+            parts
+                .filter_map(|param| param.split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("version"))
+                .and_then(|(_, value)| parse_version(value))
+        })
+    }))
+}
+
+fn parse_version(value: &str) -> Option<u32> {
+    let value = value.trim().trim_matches('"');
+    let value = value
+        .strip_prefix('v')
+        .or_else(|| value.strip_prefix('V'))
+        .unwrap_or(value);
+    value.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn request(headers: &str) -> Request {
+        let raw = format!("GET /users HTTP/1.1\r\n{}\r\n", headers);
+        let mut buffer = BufReader::new(raw.as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    #[test]
+    fn test_requested_version() {
+        assert_eq!(requested_version(&request("")), Ok(None));
+        assert_eq!(
+            requested_version(&request("X-Api-Version: 2\r\n")),
+            Ok(Some(2))
+        );
+        assert_eq!(
+            requested_version(&request("X-Api-Version: v3\r\n")),
+            Ok(Some(3))
+        );
+        assert_eq!(
+            requested_version(&request("Accept: application/vnd.acme.v2+json\r\n")),
+            Ok(Some(2))
+        );
+        assert_eq!(
+            requested_version(&request(
+                "Accept: text/html, application/json; version=4\r\n"
+            )),
+            Ok(Some(4))
+        );
+        assert_eq!(
+            requested_version(&request("Accept: application/json\r\n")),
+            Ok(None)
+        );
+        assert_eq!(
+            requested_version(&request("X-Api-Version: latest\r\n")),
+            Err("latest")
+        );
+    }
+
+    #[test]
+    fn test_versioned_dispatch() {
+        let mut versions = VersionedHandlers::default();
+        versions.v1(|_| HttpResponse::ok().text("v1"));
+        versions.v2(|_| HttpResponse::ok().text("v2"));
+        let handler = versions.into_handler();
+
+        let response = handler(&request("")).unwrap();
+        assert_eq!(response.body, "v1");
+        assert_eq!(response.get_header("X-Api-Version"), Some("1"));
+
+        assert_eq!(
+            handler(&request("X-Api-Version: 2\r\n")).unwrap().body,
+            "v2"
+        );
+        assert_eq!(
+            handler(&request("X-Api-Version: 9\r\n"))
+                .unwrap()
+                .status_code,
+            400
+        );
+        assert_eq!(
+            handler(&request("X-Api-Version: two\r\n"))
+                .unwrap()
+                .status_code,
+            400
+        );
+    }
+
+    #[test]
+    fn test_default_version() {
+        let mut versions = VersionedHandlers::default();
+        versions.v1(|_| HttpResponse::ok().text("v1"));
+        versions.v2(|_| HttpResponse::ok().text("v2"));
+        versions.default_version(2);
+
+        assert_eq!(versions.into_handler()(&request("")).unwrap().body, "v2");
+    }
+}
@@ -0,0 +1,50 @@
+/* Allow-list HTML sanitization for user-generated content */
+use std::collections::HashSet;
+
+/// Cleans untrusted HTML with a conservative allow-list: formatting tags,
+/// lists, links and images survive; scripts, styles, event handlers and
+/// `javascript:` URLs are removed. Links get `rel="noopener noreferrer"`.
+pub fn sanitize_html(input: &str) -> String {
+    ammonia::clean(input)
+}
+
+/// Like [`sanitize_html`], but only `allowed_tags` are kept (without attributes,
+/// except `href` on `a`). Everything else is stripped down to its text content.
+pub fn sanitize_html_with_tags(input: &str, allowed_tags: &[&str]) -> String {
+    let tags: HashSet<&str> = allowed_tags.iter().copied().collect();
+
+    ammonia::Builder::new()
+        .tags(tags)
+        .generic_attributes(HashSet::new())
+        .clean(input)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_html_removes_scripts_and_handlers() {
+        let dirty = r#"<p onclick="steal()">Hi <b>there</b><script>alert(1)</script></p><a href="javascript:alert(1)">x</a>"#;
+        let clean = sanitize_html(dirty);
+
+        assert!(clean.contains("<p>Hi <b>there</b></p>"));
+        assert!(!clean.contains("script"));
+        assert!(!clean.contains("onclick"));
+        assert!(!clean.contains("javascript:"));
+    }
+
+    #[test]
+    fn test_sanitize_html_with_tags() {
+        let clean = sanitize_html_with_tags(
+            r#"<p>Read <a href="https://example.com" title="t">this</a> <img src="x.png"></p>"#,
+            &["a"],
+        );
+
+        assert_eq!(
+            clean,
+            r#"Read <a href="https://example.com" rel="noopener noreferrer">this</a> "#
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::io::{self, ErrorKind};
+use std::time::Duration;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+/// How the accept loop reacts to a failed `accept()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcceptErrorKind {
+    /// Out of file descriptors, buffers or memory. The loop backs off and retries.
+    ResourceExhausted,
+    /// The pending connection died before it was accepted. The loop moves on.
+    Connection,
+    /// The listener itself is broken. The loop stops and `listen` returns the error.
+    Fatal,
+}
+
+/// Called for every accept error, e.g. to raise an alert when descriptors run out.
+pub type AcceptErrorHook = Box<dyn Fn(&io::Error, AcceptErrorKind) + Send + Sync>;
+
+pub fn classify_accept_error(e: &io::Error) -> AcceptErrorKind {
+    // EMFILE, ENFILE, ENOBUFS and ENOMEM on Linux
+    #[cfg(target_os = "linux")]
+    if let Some(23 | 24 | 105 | 12) = e.raw_os_error() {
+        return AcceptErrorKind::ResourceExhausted;
+    }
+
+    match e.kind() {
+        ErrorKind::OutOfMemory => AcceptErrorKind::ResourceExhausted,
+        ErrorKind::ConnectionAborted
+        | ErrorKind::ConnectionReset
+        | ErrorKind::ConnectionRefused
+        | ErrorKind::Interrupted
+        | ErrorKind::WouldBlock
+        | ErrorKind::TimedOut
+        | ErrorKind::PermissionDenied => AcceptErrorKind::Connection,
+        // EPROTO: protocol error on the pending connection
+        _ if e.raw_os_error() == Some(71) => AcceptErrorKind::Connection,
+        _ => AcceptErrorKind::Fatal,
+    }
+}
+
+/// Exponential backoff used while resources are exhausted.
+#[derive(Debug)]
+pub(crate) struct Backoff {
+    current: Duration,
+}
+
+impl Backoff {
+    pub(crate) fn new() -> Self {
+        Self {
+            current: INITIAL_BACKOFF,
+        }
+    }
+
+    /// The delay to wait now; the next one will be twice as long, up to a second.
+    pub(crate) fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(MAX_BACKOFF);
+        delay
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.current = INITIAL_BACKOFF;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_accept_error() {
+        let transient = io::Error::from(ErrorKind::ConnectionAborted);
+        assert_eq!(
+            classify_accept_error(&transient),
+            AcceptErrorKind::Connection
+        );
+
+        let fatal = io::Error::from(ErrorKind::InvalidInput);
+        assert_eq!(classify_accept_error(&fatal), AcceptErrorKind::Fatal);
+
+        #[cfg(target_os = "linux")]
+        {
+            let emfile = io::Error::from_raw_os_error(24);
+            assert_eq!(
+                classify_accept_error(&emfile),
+                AcceptErrorKind::ResourceExhausted
+            );
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new();
+        assert_eq!(backoff.next_delay(), Duration::from_millis(10));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(20));
+        for _ in 0..10 {
+            backoff.next_delay();
+        }
+        assert_eq!(backoff.next_delay(), MAX_BACKOFF);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), INITIAL_BACKOFF);
+    }
+}
@@ -0,0 +1,244 @@
+use log::{debug, warn};
+
+use crate::http::{HttpResponse, Request};
+use crate::utils::sanitize_header_key;
+
+type Check = Box<dyn Fn(&HttpResponse) -> bool + Send + Sync>;
+type Fix = Box<dyn Fn(HttpResponse) -> HttpResponse + Send + Sync>;
+
+/// What the audit does with a response that breaks a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditMode {
+    /// Logs a warning and sends the response as it is.
+    Log,
+    /// Repairs the response where the rule knows how, and logs otherwise.
+    Fix,
+    /// Replaces the response with a 500 naming the broken rules in debug
+    /// builds, so misconfigured handlers show up during development. Release
+    /// builds only log.
+    Fail,
+}
+
+struct Rule {
+    name: String,
+    check: Check,
+    fix: Option<Fix>,
+}
+
+/// Invariants checked on every outgoing response, once middleware and error
+/// pages are done with it. Installed with `Server::with_response_audit`.
+///
+/// ```no_run
+/// # use schnell::server::Server;
+/// # use schnell::server::audit::{AuditMode, ResponseAudit};
+/// let audit = ResponseAudit::new(AuditMode::Fail)
+///     .require_content_type()
+///     .no_cookies_on_cacheable()
+///     .security_headers();
+/// let server = Server::new("0.0.0.0", 8080, None).with_response_audit(audit);
+/// ```
+pub struct ResponseAudit {
+    mode: AuditMode,
+    rules: Vec<Rule>,
+}
+
+impl ResponseAudit {
+    pub fn new(mode: AuditMode) -> Self {
+        Self {
+            mode,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Responses with a body must say what it is. Fixed by labelling it
+    /// `application/octet-stream`.
+    pub fn require_content_type(self) -> Self {
+        self.rule_with_fix(
+            "Content-Type missing",
+            |response| {
+                !response.content_type.is_empty()
+                    || (response.body.is_empty() && !response.is_streaming())
+            },
+            |response| response.content_type("application/octet-stream"),
+        )
+    }
+
+    /// Shared caches must not store a response that sets cookies. Fixed by
+    /// marking the response `private`.
+    pub fn no_cookies_on_cacheable(self) -> Self {
+        self.rule_with_fix(
+            "Set-Cookie on a publicly cacheable response",
+            |response| response.cookies.is_empty() || !is_publicly_cacheable(response),
+            |response| {
+                let directives = response
+                    .headers
+                    .get("Cache-Control")
+                    .map_or("", |v| v.as_str())
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|d| {
+                        !d.is_empty()
+                            && !d.eq_ignore_ascii_case("public")
+                            && !d.to_ascii_lowercase().starts_with("s-maxage")
+                    })
+                    .fold("private".to_string(), |value, d| {
+                        format!("{}, {}", value, d)
+                    });
+                response.header("Cache-Control", &directives)
+            },
+        )
+    }
+
+    /// `name` must be set on every response. Fixed by setting it to `value`.
+    pub fn require_header(self, name: &str, value: &str) -> Self {
+        let key = sanitize_header_key(name);
+        let value = value.to_string();
+        let fix_key = key.clone();
+        self.rule_with_fix(
+            &format!("{} missing", key),
+            move |response| response.headers.contains_key(&key),
+            move |response| response.header(&fix_key, &value),
+        )
+    }
+
+    /// `X-Content-Type-Options`, `X-Frame-Options` and `Referrer-Policy`, with
+    /// conservative values when fixing.
+    pub fn security_headers(self) -> Self {
+        self.require_header("X-Content-Type-Options", "nosniff")
+            .require_header("X-Frame-Options", "DENY")
+            .require_header("Referrer-Policy", "strict-origin-when-cross-origin")
+    }
+
+    /// A custom invariant `check` must hold. It has no fix, so it is only logged
+    /// or failed.
+    pub fn rule<F>(self, name: &str, check: F) -> Self
+    where
+        F: Fn(&HttpResponse) -> bool + Send + Sync + 'static,
+    {
+        let mut audit = self;
+        audit.rules.push(Rule {
+            name: name.to_string(),
+            check: Box::new(check),
+            fix: None,
+        });
+        audit
+    }
+
+    fn rule_with_fix<C, F>(self, name: &str, check: C, fix: F) -> Self
+    where
+        C: Fn(&HttpResponse) -> bool + Send + Sync + 'static,
+        F: Fn(HttpResponse) -> HttpResponse + Send + Sync + 'static,
+    {
+        let mut audit = self;
+        audit.rules.push(Rule {
+            name: name.to_string(),
+            check: Box::new(check),
+            fix: Some(Box::new(fix)),
+        });
+        audit
+    }
+
+    pub(crate) fn apply(&self, request: &Request, response: HttpResponse) -> HttpResponse {
+        let mut response = response;
+        let mut broken = Vec::new();
+        for rule in &self.rules {
+            if (rule.check)(&response) {
+                continue;
+            }
+
+            match (&self.mode, &rule.fix) {
+                (AuditMode::Fix, Some(fix)) => {
+                    debug!(
+                        "Response audit fixed {} {}: {}",
+                        request.method, request.path, rule.name
+                    );
+                    response = fix(response);
+                }
+                _ => {
+                    warn!(
+                        "Response audit failed for {} {} ({}): {}",
+                        request.method, request.path, response.status_code, rule.name
+                    );
+                    broken.push(rule.name.as_str());
+                }
+            }
+        }
+
+        if self.mode == AuditMode::Fail && cfg!(debug_assertions) && !broken.is_empty() {
+            return HttpResponse::internal_server_error()
+                .text(&format!("Response audit failed: {}", broken.join("; ")));
+        }
+        response
+    }
+}
+
+fn is_publicly_cacheable(response: &HttpResponse) -> bool {
+    let Some(cache_control) = response.headers.get("Cache-Control") else {
+        return false;
+    };
+    let cache_control = cache_control.to_ascii_lowercase();
+    let directives: Vec<&str> = cache_control.split(',').map(str::trim).collect();
+    if directives
+        .iter()
+        .any(|d| *d == "private" || *d == "no-store")
+    {
+        return false;
+    }
+    directives
+        .iter()
+        .any(|d| *d == "public" || d.starts_with("max-age") || d.starts_with("s-maxage"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::cookie::Cookie;
+    use std::io::BufReader;
+
+    fn request() -> Request {
+        let mut buffer = BufReader::new("GET /profile HTTP/1.1\r\n\r\n".as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    fn cached_with_cookie() -> HttpResponse {
+        HttpResponse::ok()
+            .text("hi")
+            .header("Cache-Control", "public, max-age=60")
+            .cookie(Cookie::new("session", "abc"))
+    }
+
+    #[test]
+    fn test_fix_mode() {
+        let audit = ResponseAudit::new(AuditMode::Fix)
+            .no_cookies_on_cacheable()
+            .security_headers();
+        let response = audit.apply(&request(), cached_with_cookie());
+
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.headers["Cache-Control"], "private, max-age=60");
+        assert_eq!(response.headers["X-Content-Type-Options"], "nosniff");
+        assert_eq!(response.headers["X-Frame-Options"], "DENY");
+    }
+
+    #[test]
+    fn test_log_and_fail_modes() {
+        let response = ResponseAudit::new(AuditMode::Log)
+            .no_cookies_on_cacheable()
+            .apply(&request(), cached_with_cookie());
+        assert_eq!(response.headers["Cache-Control"], "public, max-age=60");
+
+        let audit = ResponseAudit::new(AuditMode::Fail)
+            .require_content_type()
+            .rule("body not empty", |response| !response.body.is_empty());
+        let response = audit.apply(&request(), HttpResponse::ok().text("fine"));
+        assert_eq!(response.status_code, 200);
+
+        let response = audit.apply(&request(), HttpResponse::ok());
+        if cfg!(debug_assertions) {
+            assert_eq!(response.status_code, 500);
+            assert_eq!(response.body, "Response audit failed: body not empty");
+        } else {
+            assert_eq!(response.status_code, 200);
+        }
+    }
+}
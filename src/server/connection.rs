@@ -0,0 +1,42 @@
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+
+/// A client connection requests can be served over: a plain TCP stream, or a
+/// TLS session on top of one.
+pub trait Connection: Read + Write + Send {
+    /// The underlying socket, for timeouts, TCP_NODELAY and the peer address.
+    fn socket(&self) -> &TcpStream;
+
+    /// Whether traffic on this connection is encrypted.
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    /// Stops sending. The peer sees end-of-stream once pending data is delivered.
+    fn close_write(&mut self) -> io::Result<()> {
+        self.socket().shutdown(Shutdown::Write)
+    }
+}
+
+impl Connection for TcpStream {
+    fn socket(&self) -> &TcpStream {
+        self
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Connection for rustls::StreamOwned<rustls::ServerConnection, TcpStream> {
+    fn socket(&self) -> &TcpStream {
+        &self.sock
+    }
+
+    fn is_secure(&self) -> bool {
+        true
+    }
+
+    fn close_write(&mut self) -> io::Result<()> {
+        self.conn.send_close_notify();
+        self.flush()?;
+        self.sock.shutdown(Shutdown::Write)
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Snapshot of a server's effective configuration, as returned by
+/// [`Server::describe`](super::Server::describe).
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerDescription {
+    pub bind_addrs: Vec<String>,
+    pub pool_size: usize,
+    pub accept_queue: usize,
+    pub read_timeout_ms: Option<u128>,
+    pub write_timeout_ms: Option<u128>,
+    pub handler_timeout_ms: Option<u128>,
+    pub max_request_line: usize,
+    pub max_uri_length: usize,
+    pub output_buffer_size: usize,
+    pub features: Vec<&'static str>,
+    pub route_count: usize,
+    pub subsystems: Vec<String>,
+}
+
+fn or_none(value: Option<u128>) -> String {
+    value.map_or_else(|| "none".to_string(), |ms| format!("{}ms", ms))
+}
+
+impl fmt::Display for ServerDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "schnell {}", env!("CARGO_PKG_VERSION"))?;
+        writeln!(f, "  bind:      {}", self.bind_addrs.join(", "))?;
+        writeln!(
+            f,
+            "  workers:   {} (queue {})",
+            self.pool_size, self.accept_queue
+        )?;
+        writeln!(
+            f,
+            "  timeouts:  read {}, write {}, handler {}",
+            or_none(self.read_timeout_ms),
+            or_none(self.write_timeout_ms),
+            or_none(self.handler_timeout_ms)
+        )?;
+        writeln!(
+            f,
+            "  limits:    request line {}B, uri {}B, output buffer {}B",
+            self.max_request_line, self.max_uri_length, self.output_buffer_size
+        )?;
+        writeln!(f, "  features:  {}", self.features.join(", "))?;
+        writeln!(f, "  routes:    {}", self.route_count)?;
+        write!(f, "  subsystems: {}", self.subsystems.join(", "))
+    }
+}
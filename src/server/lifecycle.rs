@@ -0,0 +1,193 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{error, info, warn};
+
+pub type HookError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Runs once the listener is bound, before the first connection is accepted.
+/// Returning an error aborts startup.
+pub type StartupHook = Box<dyn Fn() -> Result<(), HookError> + Send + Sync>;
+
+/// Runs after the accept loop has stopped and in-flight connections have finished.
+pub type ShutdownHook = Box<dyn Fn() + Send + Sync>;
+
+/// A background component (session store, cache, scheduler, metrics, ...) whose
+/// lifetime is managed by the server. Subsystems start in registration order
+/// before the startup hooks run, and stop in reverse order after the shutdown hooks.
+pub trait Subsystem: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn start(&self) -> Result<(), HookError>;
+
+    /// Stops the subsystem, giving up on outstanding work once `deadline` passes.
+    fn stop(&self, deadline: Instant) -> Result<(), HookError>;
+}
+
+/// Starts `subsystems` in order. If one fails, those already started are stopped
+/// again before the error is returned.
+pub(crate) fn start_subsystems(
+    subsystems: &[Box<dyn Subsystem>],
+    stop_timeout: Duration,
+) -> Result<(), HookError> {
+    for (idx, subsystem) in subsystems.iter().enumerate() {
+        info!("Starting subsystem {:?}", subsystem.name());
+        if let Err(e) = subsystem.start() {
+            error!("Subsystem {:?} failed to start: {}", subsystem.name(), e);
+            stop_subsystems(&subsystems[..idx], stop_timeout);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Stops `subsystems` in reverse order, all sharing a single deadline.
+pub(crate) fn stop_subsystems(subsystems: &[Box<dyn Subsystem>], stop_timeout: Duration) {
+    let deadline = Instant::now() + stop_timeout;
+
+    for subsystem in subsystems.iter().rev() {
+        info!("Stopping subsystem {:?}", subsystem.name());
+        if let Err(e) = subsystem.stop(deadline) {
+            error!(
+                "Subsystem {:?} failed to stop cleanly: {}",
+                subsystem.name(),
+                e
+            );
+        }
+        if Instant::now() > deadline {
+            warn!(
+                "Subsystem {:?} overran the shutdown deadline",
+                subsystem.name()
+            );
+        }
+    }
+}
+
+/// Cloneable handle used to stop a running server from another thread.
+#[derive(Clone, Default)]
+pub struct ShutdownHandle {
+    inner: Arc<ShutdownState>,
+}
+
+#[derive(Default)]
+struct ShutdownState {
+    requested: AtomicBool,
+    local_addr: Mutex<Option<SocketAddr>>,
+}
+
+impl ShutdownHandle {
+    /// Stops accepting new connections. Connections already being served run to
+    /// completion before `listen` returns.
+    pub fn shutdown(&self) {
+        self.inner.requested.store(true, Ordering::SeqCst);
+
+        // Wake up the accept loop, which is blocked waiting for a connection
+        if let Some(mut addr) = self.local_addr() {
+            if addr.ip().is_unspecified() {
+                addr.set_ip(match addr.ip() {
+                    IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+                });
+            }
+            let _ = TcpStream::connect(addr);
+        }
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.inner.requested.load(Ordering::SeqCst)
+    }
+
+    /// Address the server is bound to, once it is listening.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        *self.inner.local_addr.lock().unwrap()
+    }
+
+    pub(crate) fn set_local_addr(&self, addr: SocketAddr) {
+        *self.inner.local_addr.lock().unwrap() = Some(addr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Recorder {
+        name: &'static str,
+        fail_start: bool,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Subsystem for Recorder {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn start(&self) -> Result<(), HookError> {
+            if self.fail_start {
+                return Err(format!("{} unavailable", self.name).into());
+            }
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("start {}", self.name));
+            Ok(())
+        }
+
+        fn stop(&self, deadline: Instant) -> Result<(), HookError> {
+            assert!(deadline > Instant::now());
+            self.log.lock().unwrap().push(format!("stop {}", self.name));
+            Ok(())
+        }
+    }
+
+    fn subsystems(
+        names: &[(&'static str, bool)],
+        log: &Arc<Mutex<Vec<String>>>,
+    ) -> Vec<Box<dyn Subsystem>> {
+        names
+            .iter()
+            .map(|&(name, fail_start)| {
+                Box::new(Recorder {
+                    name,
+                    fail_start,
+                    log: Arc::clone(log),
+                }) as Box<dyn Subsystem>
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_subsystems_stop_in_reverse_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let subsystems = subsystems(&[("cache", false), ("scheduler", false)], &log);
+
+        start_subsystems(&subsystems, Duration::from_secs(1)).unwrap();
+        stop_subsystems(&subsystems, Duration::from_secs(1));
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "start cache",
+                "start scheduler",
+                "stop scheduler",
+                "stop cache"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failed_start_stops_started_subsystems() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let subsystems = subsystems(
+            &[("cache", false), ("metrics", true), ("scheduler", false)],
+            &log,
+        );
+
+        let err = start_subsystems(&subsystems, Duration::from_secs(1)).unwrap_err();
+
+        assert_eq!(err.to_string(), "metrics unavailable");
+        assert_eq!(*log.lock().unwrap(), ["start cache", "stop cache"]);
+    }
+}
@@ -0,0 +1,2510 @@
+pub mod accept;
+pub mod audit;
+pub mod connection;
+pub mod describe;
+pub mod lifecycle;
+#[cfg(feature = "tls")]
+pub mod tls;
+
+use crate::bus::Bus;
+use crate::constants::{
+    DEFAULT_ACCEPT_QUEUE_PER_WORKER, DEFAULT_MAX_TIMED_OUT_HANDLERS, DEFAULT_OUTPUT_BUFFER_SIZE,
+    DEFAULT_SUBSYSTEM_STOP_TIMEOUT_SECS,
+};
+use crate::http::request::{RequestError, RequestLimits, SharedState};
+use crate::http::response::{FlushMode, write_response_buffered};
+use crate::http::uri::{normalize_path, normalize_path_strict};
+use crate::http::{HttpMethod, HttpResponse, Request, conditional};
+use crate::keyring::Keyring;
+#[cfg(feature = "compression")]
+use crate::middleware::Compression;
+use crate::middleware::{CorsConfig, Middleware, Next};
+use crate::redact::Redaction;
+use crate::routing::errors::in_scope;
+use crate::routing::resolver::extract_params;
+use crate::routing::router::MiddlewareChain;
+use crate::routing::{
+    BreakerRegistry, ErrorHandlers, Route, RouteBuilder, RouteError, RouteGroup, RouteHandler,
+    RouteResolver, RouteTable,
+};
+use crate::testing::Clock;
+use crate::utils::{http_date, join_path};
+use accept::{AcceptErrorHook, AcceptErrorKind, Backoff, classify_accept_error};
+use audit::ResponseAudit;
+use connection::Connection;
+use describe::ServerDescription;
+use lifecycle::{
+    HookError, ShutdownHandle, ShutdownHook, StartupHook, Subsystem, start_subsystems,
+    stop_subsystems,
+};
+
+use log::{debug, error, info, warn};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How much unread input is discarded, and for how long, before closing a
+/// connection after an error response.
+const ERROR_DRAIN_LIMIT: u64 = 64 * 1024;
+const ERROR_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often an idle keep-alive connection checks whether shutdown was requested.
+const IDLE_SHUTDOWN_POLL: Duration = Duration::from_millis(50);
+
+pub struct Server {
+    addrs: Vec<SocketAddr>,
+    routes: Vec<Route>,
+    middleware: Vec<Box<dyn Middleware>>,
+    /// Middleware of each group, by prefix, for preflights to the group's paths.
+    group_middleware: Vec<(String, MiddlewareChain)>,
+    error_handlers: ErrorHandlers,
+    pool_size: Option<usize>,
+    accept_queue: Option<usize>,
+    queue_timeout: Option<Duration>,
+    read_timeout_ms: Option<Duration>,
+    write_timeout_ms: Option<Duration>,
+    handler_timeout: Option<Duration>,
+    max_timed_out_handlers: usize,
+    bind_retry: Option<Duration>,
+    fallback_ports: Vec<u16>,
+    keep_alive: bool,
+    output_buffer_size: usize,
+    spill_threshold: Option<usize>,
+    audit: Option<ResponseAudit>,
+    server_header: Option<String>,
+    clock: Clock,
+    tcp_nodelay: bool,
+    strict_paths: bool,
+    request_logging: Option<(usize, Redaction)>,
+    request_limits: RequestLimits,
+    state: SharedState,
+    keyring: Option<Arc<Keyring>>,
+    startup_hooks: Vec<StartupHook>,
+    shutdown_hooks: Vec<ShutdownHook>,
+    accept_error_hooks: Vec<AcceptErrorHook>,
+    subsystems: Vec<Box<dyn Subsystem>>,
+    subsystem_stop_timeout: Duration,
+    shutdown: ShutdownHandle,
+    bus: Bus,
+    breakers: BreakerRegistry,
+    client_aborts: AtomicU64,
+    queued_connections: AtomicUsize,
+    rejected_connections: AtomicU64,
+    timed_out_handlers: Arc<AtomicUsize>,
+    #[cfg(feature = "tls")]
+    tls: Option<std::sync::Arc<rustls::ServerConfig>>,
+    #[cfg(feature = "tls")]
+    tls_handshake_timeout: Duration,
+    #[cfg(feature = "tls")]
+    tls_handshake_failures: AtomicU64,
+}
+
+#[derive(Debug)]
+pub enum ServerError {
+    ResponseError(std::io::Error),
+    BindError(std::io::Error),
+    AcceptError(std::io::Error),
+    StartupError(HookError),
+    TlsError(std::io::Error),
+}
+
+impl RouteResolver for Server {}
+
+impl RouteBuilder for Server {
+    type Error = RouteError;
+
+    fn register(&mut self, path: &str, method: HttpMethod, handler: RouteHandler) {
+        // Stored the way request paths are decoded, so the two compare equal
+        let path = normalize_path(path);
+        if let Some(matching_route_idx) = self
+            .routes
+            .iter()
+            .position(|r| r.path == path && r.method == method)
+        {
+            log::warn!(
+                "Route {} {:?} already exists and will be overwritten",
+                method,
+                path
+            );
+            self.routes[matching_route_idx] = Route {
+                path,
+                method,
+                handler,
+                middleware: None,
+            };
+        } else {
+            self.routes.push(Route {
+                path,
+                method,
+                handler,
+                middleware: None,
+            });
+        }
+    }
+}
+
+impl Server {
+    pub fn new(ip_addr: &str, port: u16, pool_size: Option<usize>) -> Self {
+        // Resolving the pair rather than formatting "ip:port" keeps IPv6 literals intact
+        let addrs = match (ip_addr, port).to_socket_addrs() {
+            Ok(addrs) => addrs.collect(),
+            Err(e) => {
+                error!("Could not resolve {:?}: {}", ip_addr, e);
+                Vec::new()
+            }
+        };
+        Self::with_addrs(addrs, pool_size)
+    }
+
+    /// Creates a server for anything that resolves to socket addresses, e.g.
+    /// `"[::1]:8080"`, `("0.0.0.0", 80)` or a `SocketAddr`.
+    pub fn from_addr<A: ToSocketAddrs>(addr: A, pool_size: Option<usize>) -> std::io::Result<Self> {
+        Ok(Self::with_addrs(
+            addr.to_socket_addrs()?.collect(),
+            pool_size,
+        ))
+    }
+
+    /// Creates a server that terminates HTTPS itself, using a PEM certificate
+    /// chain and private key.
+    #[cfg(feature = "tls")]
+    pub fn new_tls<P: AsRef<std::path::Path>>(
+        ip_addr: &str,
+        port: u16,
+        cert_path: P,
+        key_path: P,
+    ) -> Result<Self, ServerError> {
+        let config = tls::load_config(cert_path, key_path).map_err(ServerError::TlsError)?;
+        Ok(Self::new(ip_addr, port, None).with_tls_config(config))
+    }
+
+    #[cfg(feature = "tls")]
+    pub fn with_tls_config(self, config: std::sync::Arc<rustls::ServerConfig>) -> Self {
+        let mut server = self;
+        server.tls = Some(config);
+        server
+    }
+
+    /// How long a client gets to complete the TLS handshake, in total, before
+    /// the connection is dropped. Kept apart from the read timeout so sockets
+    /// that never finish a handshake can't hold a worker for long. Defaults to 10s.
+    #[cfg(feature = "tls")]
+    pub fn with_tls_handshake_timeout(self, timeout: Duration) -> Self {
+        let mut server = self;
+        server.tls_handshake_timeout = timeout;
+        server
+    }
+
+    /// TLS handshakes that failed or timed out.
+    #[cfg(feature = "tls")]
+    pub fn tls_handshake_failures(&self) -> u64 {
+        self.tls_handshake_failures.load(Ordering::Relaxed)
+    }
+
+    fn with_addrs(addrs: Vec<SocketAddr>, pool_size: Option<usize>) -> Self {
+        Self {
+            addrs,
+            routes: Vec::new(),
+            middleware: Vec::new(),
+            group_middleware: Vec::new(),
+            error_handlers: ErrorHandlers::default(),
+            pool_size,
+            accept_queue: None,
+            queue_timeout: None,
+            read_timeout_ms: Some(Duration::from_millis(100_000)),
+            write_timeout_ms: Some(Duration::from_millis(100_000)),
+            handler_timeout: None,
+            max_timed_out_handlers: DEFAULT_MAX_TIMED_OUT_HANDLERS,
+            bind_retry: None,
+            fallback_ports: Vec::new(),
+            keep_alive: false,
+            output_buffer_size: DEFAULT_OUTPUT_BUFFER_SIZE,
+            spill_threshold: None,
+            audit: None,
+            server_header: Some("schnell".to_string()),
+            clock: Clock::system(),
+            tcp_nodelay: false,
+            strict_paths: false,
+            request_logging: None,
+            request_limits: RequestLimits::default(),
+            state: SharedState::default(),
+            keyring: None,
+            startup_hooks: Vec::new(),
+            shutdown_hooks: Vec::new(),
+            accept_error_hooks: Vec::new(),
+            subsystems: Vec::new(),
+            subsystem_stop_timeout: Duration::from_secs(DEFAULT_SUBSYSTEM_STOP_TIMEOUT_SECS),
+            shutdown: ShutdownHandle::default(),
+            bus: Bus::default(),
+            breakers: BreakerRegistry::default(),
+            client_aborts: AtomicU64::new(0),
+            queued_connections: AtomicUsize::new(0),
+            rejected_connections: AtomicU64::new(0),
+            timed_out_handlers: Arc::new(AtomicUsize::new(0)),
+            #[cfg(feature = "tls")]
+            tls: None,
+            #[cfg(feature = "tls")]
+            tls_handshake_timeout: Duration::from_secs(
+                crate::constants::DEFAULT_TLS_HANDSHAKE_TIMEOUT_SECS,
+            ),
+            #[cfg(feature = "tls")]
+            tls_handshake_failures: AtomicU64::new(0),
+        }
+    }
+
+    /// How many accepted connections may wait for a free worker. Past that, new
+    /// connections are answered with a 503 straight away. Defaults to 16 per worker.
+    pub fn with_accept_queue(self, size: usize) -> Self {
+        let mut server = self;
+        server.accept_queue = Some(size);
+        server
+    }
+
+    /// Longest a connection may wait in the accept queue. A worker that picks
+    /// up an older one answers it with a 503 without reading the request, since
+    /// the client has likely given up on it by then.
+    pub fn with_queue_timeout(self, timeout: Duration) -> Self {
+        let mut server = self;
+        server.queue_timeout = Some(timeout);
+        server
+    }
+
+    pub fn with_read_timeout(self, timeout_ms: Duration) -> Self {
+        let mut server = self;
+        server.read_timeout_ms = Some(timeout_ms);
+        server
+    }
+
+    pub fn with_write_timeout(self, timeout_ms: Duration) -> Self {
+        let mut server = self;
+        server.write_timeout_ms = Some(timeout_ms);
+        server
+    }
+
+    pub fn with_timeout(self, timeout_ms: Duration) -> Self {
+        let mut server = self;
+        server.read_timeout_ms = Some(timeout_ms);
+        server.write_timeout_ms = Some(timeout_ms);
+        server
+    }
+
+    /// Answers 503 when a route handler hasn't returned within `timeout`, so a
+    /// slow handler can't tie up a worker. Handlers then run on a thread of their
+    /// own, which the worker stops waiting for; a handler can't be killed, so one
+    /// that overruns still runs to completion in the background.
+    pub fn with_handler_timeout(self, timeout: Duration) -> Self {
+        let mut server = self;
+        server.handler_timeout = Some(timeout);
+        server
+    }
+
+    /// How many handlers that overran the handler timeout may still be running
+    /// in the background. Past that, requests are answered with a 503 without
+    /// starting their handler, so a hung dependency can't pile up threads
+    /// without bound. Defaults to 64.
+    pub fn with_max_timed_out_handlers(self, max: usize) -> Self {
+        let mut server = self;
+        server.max_timed_out_handlers = max;
+        server
+    }
+
+    /// Keeps retrying, with backoff, for up to `duration` while the port is in
+    /// use, e.g. by the previous instance draining during a rolling restart.
+    pub fn with_bind_retry(self, duration: Duration) -> Self {
+        let mut server = self;
+        server.bind_retry = Some(duration);
+        server
+    }
+
+    /// Ports to try in turn, on the same interfaces, if the configured one is
+    /// still in use once any bind retry has run out. The port finally bound is
+    /// reported by `local_addr` and the startup banner.
+    pub fn with_fallback_ports(self, ports: &[u16]) -> Self {
+        let mut server = self;
+        server.fallback_ports = ports.to_vec();
+        server
+    }
+
+    /// Keep connections open between requests (including pipelined ones) until the
+    /// client sends `Connection: close` or the read timeout expires. A kept-alive
+    /// connection holds on to its worker for that whole time.
+    pub fn with_keep_alive(self, keep_alive: bool) -> Self {
+        let mut server = self;
+        server.keep_alive = keep_alive;
+        server
+    }
+
+    /// Size of the buffer responses are written through before hitting the socket.
+    pub fn with_output_buffer_size(self, size: usize) -> Self {
+        let mut server = self;
+        server.output_buffer_size = size;
+        server
+    }
+
+    /// Response bodies larger than `bytes` are moved to a temp file once the
+    /// handler returns and streamed from disk, so slow downloads of big
+    /// responses don't each hold their body in memory. Handlers that build
+    /// bodies incrementally can bound their own memory with `SpooledBody`.
+    pub fn with_spill_threshold(self, bytes: usize) -> Self {
+        let mut server = self;
+        server.spill_threshold = Some(bytes);
+        server
+    }
+
+    /// Checks every outgoing response against `audit`'s rules, after middleware
+    /// and error pages have run.
+    pub fn with_response_audit(self, audit: ResponseAudit) -> Self {
+        let mut server = self;
+        server.audit = Some(audit);
+        server
+    }
+
+    /// Value of the `Server` header sent with every response, or `None` to leave
+    /// it out. Defaults to `schnell`; handlers that set their own take precedence.
+    pub fn with_server_header(self, value: Option<&str>) -> Self {
+        let mut server = self;
+        server.server_header = value.map(str::to_string);
+        server
+    }
+
+    /// Clock for the `Date` header, e.g. a manual one so tests get the same
+    /// responses on every run.
+    pub fn with_clock(self, clock: Clock) -> Self {
+        let mut server = self;
+        server.clock = clock;
+        server
+    }
+
+    /// Set `TCP_NODELAY` on every accepted connection. When off, only responses
+    /// marked with `HttpResponse::flush_immediately` bypass Nagle's algorithm.
+    pub fn with_tcp_nodelay(self, nodelay: bool) -> Self {
+        let mut server = self;
+        server.tcp_nodelay = nodelay;
+        server
+    }
+
+    /// Reject request paths with malformed percent-escapes or invalid (including
+    /// overlong) UTF-8 with 400, instead of routing a repaired path.
+    pub fn with_strict_paths(self, strict: bool) -> Self {
+        let mut server = self;
+        server.strict_paths = strict;
+        server
+    }
+
+    /// Logs every request's headers and up to `max_body` bytes of its body at
+    /// debug level, with the values `redaction` covers masked.
+    pub fn with_request_logging(self, max_body: usize, redaction: Redaction) -> Self {
+        let mut server = self;
+        server.request_logging = Some((max_body, redaction));
+        server
+    }
+
+    /// Limits on the request line and URI length; requests exceeding them get a 414.
+    pub fn with_request_limits(self, limits: RequestLimits) -> Self {
+        let mut server = self;
+        server.request_limits = limits;
+        server
+    }
+
+    /// Shares `state` with every handler, via `Request::state` or `Context::state`.
+    pub fn with_state<T: Send + Sync + 'static>(self, state: T) -> Self {
+        let mut server = self;
+        server.state = SharedState::new(state);
+        server
+    }
+
+    /// Secret for `Cookie::signed` and `Request::signed_cookie`.
+    pub fn with_secret_key(self, key: impl AsRef<[u8]>) -> Self {
+        self.with_keyring(Keyring::new(key))
+    }
+
+    /// Like `with_secret_key`, with retired keys still accepted during rotation.
+    pub fn with_keyring(self, keyring: Keyring) -> Self {
+        let mut server = self;
+        server.keyring = Some(Arc::new(keyring));
+        server
+    }
+
+    /// Adds `middleware` to the chain run around every request, including ones
+    /// that end up as 404 or 405. Middleware runs in registration order.
+    pub fn use_middleware<M: Middleware + 'static>(&mut self, middleware: M) {
+        self.middleware.push(Box::new(middleware));
+    }
+
+    /// Answers CORS preflights and adds `Access-Control-*` headers to responses
+    /// for allowed origins. Runs ahead of all other middleware, so rejections
+    /// from e.g. an auth check still carry the headers browsers need to read them.
+    /// A config that fails [`CorsConfig::validate`] makes `listen` fail.
+    pub fn with_cors(self, config: CorsConfig) -> Self {
+        let mut server = self;
+        if let Err(e) = config.validate() {
+            server.on_startup(move || Err(e.into()));
+        }
+        server.middleware.insert(0, Box::new(config));
+        server
+    }
+
+    /// Gzip- or deflate-compresses response bodies of 1 KiB or more for clients
+    /// that accept it. Use `use_middleware(Compression::new(min_size))` for a
+    /// different threshold.
+    #[cfg(feature = "compression")]
+    pub fn enable_compression(self) -> Self {
+        let mut server = self;
+        server.middleware.insert(
+            0,
+            Box::new(Compression::new(
+                crate::constants::DEFAULT_COMPRESSION_MIN_SIZE,
+            )),
+        );
+        server
+    }
+
+    /// Error page for `status_code`, used whenever a request ends in a bare error
+    /// response: unmatched routes, failing handlers, or e.g. `HttpResponse::not_found()`.
+    /// Groups can override it with [`RouteGroup::error_handler`].
+    pub fn error_handler<H>(&mut self, status_code: u16, handler: H)
+    where
+        H: Fn(&Request) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.error_handlers.register("/", status_code, handler);
+    }
+
+    /// Registers a hook that runs after the listener is bound but before any
+    /// connection is accepted, e.g. to warm caches. An error aborts startup.
+    pub fn on_startup<F>(&mut self, hook: F)
+    where
+        F: Fn() -> Result<(), HookError> + Send + Sync + 'static,
+    {
+        self.startup_hooks.push(Box::new(hook));
+    }
+
+    /// Registers a hook that runs once the server has shut down gracefully.
+    pub fn on_shutdown<F>(&mut self, hook: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.shutdown_hooks.push(Box::new(hook));
+    }
+
+    /// Registers a hook called whenever accepting a connection fails, with the
+    /// error and how the accept loop classified it.
+    pub fn on_accept_error<F>(&mut self, hook: F)
+    where
+        F: Fn(&std::io::Error, AcceptErrorKind) + Send + Sync + 'static,
+    {
+        self.accept_error_hooks.push(Box::new(hook));
+    }
+
+    /// Registers a background subsystem whose start and stop are tied to `listen`.
+    pub fn add_subsystem<S: Subsystem + 'static>(&mut self, subsystem: S) {
+        self.subsystems.push(Box::new(subsystem));
+    }
+
+    /// How long subsystems get, in total, to stop once the server shuts down.
+    pub fn with_subsystem_stop_timeout(self, timeout: Duration) -> Self {
+        let mut server = self;
+        server.subsystem_stop_timeout = timeout;
+        server
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Address the server is bound to, once it is listening.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.shutdown.local_addr()
+    }
+
+    /// Responses cut short because the client closed or reset the connection
+    /// mid-write. These are logged at info level rather than as server errors.
+    pub fn client_aborts(&self) -> u64 {
+        self.client_aborts.load(Ordering::Relaxed)
+    }
+
+    /// Accepted connections waiting for a free worker.
+    pub fn queued_connections(&self) -> usize {
+        self.queued_connections.load(Ordering::Relaxed)
+    }
+
+    /// Handlers that overran the handler timeout and are still running.
+    pub fn timed_out_handlers(&self) -> usize {
+        self.timed_out_handlers.load(Ordering::Relaxed)
+    }
+
+    /// Connections answered with a 503 because the accept queue was full or they
+    /// waited in it past the queue timeout.
+    pub fn rejected_connections(&self) -> u64 {
+        self.rejected_connections.load(Ordering::Relaxed)
+    }
+
+    /// Handle to the server's in-process event bus, to be moved into handlers,
+    /// subsystems and background threads.
+    pub fn bus(&self) -> Bus {
+        self.bus.clone()
+    }
+
+    /// Registry for circuit breakers whose stats `serve_admin_stats` reports.
+    pub fn breakers(&self) -> BreakerRegistry {
+        self.breakers.clone()
+    }
+
+    /// Serves the stats of every registered circuit breaker as JSON on `path`,
+    /// e.g. `/admin/stats`. To keep them private, serve `breakers().stats()`
+    /// from a route in a group with an auth middleware instead.
+    #[cfg(feature = "json")]
+    pub fn serve_admin_stats(&mut self, path: &str) {
+        let breakers = self.breakers.clone();
+        self.get(path, move |_| {
+            HttpResponse::ok().json(serde_json::json!({ "breakers": breakers.stats() }))
+        });
+    }
+
+    /// Summary of the effective configuration: bind addresses, pool size,
+    /// timeouts, limits, enabled features, routes and subsystems.
+    pub fn describe(&self) -> ServerDescription {
+        let mut features = Vec::new();
+        if self.keep_alive {
+            features.push("keep-alive");
+        }
+        if self.tcp_nodelay {
+            features.push("tcp-nodelay");
+        }
+        if self.state.is_set() {
+            features.push("shared-state");
+        }
+        if self.request_logging.is_some() {
+            features.push("request-logging");
+        }
+        if self.spill_threshold.is_some() {
+            features.push("response-spillover");
+        }
+        if self.audit.is_some() {
+            features.push("response-audit");
+        }
+        #[cfg(feature = "tls")]
+        if self.tls.is_some() {
+            features.push("tls");
+        }
+
+        ServerDescription {
+            bind_addrs: match self.local_addr() {
+                Some(addr) => vec![addr.to_string()],
+                None => self.addrs.iter().map(|addr| addr.to_string()).collect(),
+            },
+            pool_size: self.pool_size.unwrap_or_else(num_cpus::get),
+            accept_queue: self.accept_queue_size(self.pool_size.unwrap_or_else(num_cpus::get)),
+            read_timeout_ms: self.read_timeout_ms.map(|t| t.as_millis()),
+            write_timeout_ms: self.write_timeout_ms.map(|t| t.as_millis()),
+            handler_timeout_ms: self.handler_timeout.map(|t| t.as_millis()),
+            max_request_line: self.request_limits.max_request_line,
+            max_uri_length: self.request_limits.max_uri_length,
+            output_buffer_size: self.output_buffer_size,
+            features,
+            route_count: self.routes.len(),
+            subsystems: self
+                .subsystems
+                .iter()
+                .map(|s| s.name().to_string())
+                .collect(),
+        }
+    }
+
+    /// Every registered route as a [`RouteTable`], e.g. to diff against the
+    /// table of the previous release.
+    pub fn route_table(&self) -> RouteTable {
+        RouteTable::from_routes(&self.routes)
+    }
+
+    #[cfg(feature = "json")]
+    pub fn export_routes_json(&self) -> String {
+        self.route_table().to_json()
+    }
+
+    pub fn listen(&self) -> Result<(), ServerError> {
+        let listener = self.bind().map_err(ServerError::BindError)?;
+
+        if let Ok(addr) = listener.local_addr() {
+            self.shutdown.set_local_addr(addr);
+        }
+        info!("{}", self.describe());
+        if let Ok(addr) = listener.local_addr() {
+            info!("Server listening on {}", addr);
+        }
+
+        self.listen_with_pool(self.pool_size, listener)
+    }
+
+    /// Binds the configured addresses, retrying and then falling back to other
+    /// ports as configured while they are in use.
+    fn bind(&self) -> std::io::Result<TcpListener> {
+        let deadline = Instant::now() + self.bind_retry.unwrap_or_default();
+        let mut backoff = Backoff::new();
+        let mut error = loop {
+            match TcpListener::bind(&self.addrs[..]) {
+                Err(e) if e.kind() == ErrorKind::AddrInUse && Instant::now() < deadline => {
+                    let delay = backoff
+                        .next_delay()
+                        .min(deadline.saturating_duration_since(Instant::now()));
+                    warn!("Address in use, retrying bind in {:?}", delay);
+                    thread::sleep(delay);
+                }
+                Err(e) if e.kind() == ErrorKind::AddrInUse => break e,
+                result => return result,
+            }
+        };
+
+        for &port in &self.fallback_ports {
+            let addrs: Vec<SocketAddr> = self
+                .addrs
+                .iter()
+                .map(|addr| SocketAddr::new(addr.ip(), port))
+                .collect();
+            match TcpListener::bind(&addrs[..]) {
+                Ok(listener) => {
+                    warn!("Configured port in use, falling back to port {}", port);
+                    return Ok(listener);
+                }
+                Err(e) if e.kind() == ErrorKind::AddrInUse => error = e,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(error)
+    }
+
+    pub fn handle_connection(&self, stream: TcpStream) {
+        self.serve_connection(stream)
+    }
+
+    /// Serves requests off `connection` until either side closes it.
+    pub fn serve_connection<C: Connection>(&self, connection: C) {
+        let peer_addr = connection.socket().peer_addr().ok();
+        let mut reader = BufReader::new(connection);
+
+        loop {
+            let mut request = match Request::read_with_limits(&mut reader, &self.request_limits) {
+                Err(RequestError::ReadError) => {
+                    error!("Error reading request");
+                    self.close_after_error(&mut reader, HttpResponse::internal_server_error());
+                    return;
+                }
+                Err(RequestError::ParseError | RequestError::InvalidRequest) => {
+                    error!("Error parsing request");
+                    self.close_after_error(&mut reader, HttpResponse::bad_request());
+                    return;
+                }
+                Err(RequestError::UriTooLong) => {
+                    error!("Request URI too long");
+                    self.close_after_error(&mut reader, HttpResponse::uri_too_long());
+                    return;
+                }
+                Err(RequestError::HeadersTooLarge) => {
+                    error!("Request header fields too large");
+                    self.close_after_error(
+                        &mut reader,
+                        HttpResponse::request_header_fields_too_large(),
+                    );
+                    return;
+                }
+                Err(RequestError::RequestTooLarge) => {
+                    error!("Request too large");
+                    self.close_after_error(&mut reader, self.payload_too_large());
+                    return;
+                }
+                Err(RequestError::ConnectionClosed) => {
+                    info!("Client connection closed");
+                    return;
+                }
+                Err(RequestError::ConnectionTimedOut) => {
+                    error!("Client connection timed out");
+                    return;
+                }
+                Ok(request) => request,
+            };
+            request.set_remote_addr(peer_addr);
+            request.set_secure(reader.get_ref().is_secure());
+            if let Some((max_body, redaction)) = &self.request_logging {
+                log_request(&request, *max_body, redaction);
+            }
+
+            let mut response = self
+                .check_target(&request)
+                .unwrap_or_else(|| self.dispatch(&mut request));
+            if let Some(audit) = &self.audit {
+                response = audit.apply(&request, response);
+            }
+            let mut response = conditional::evaluate(&request, response);
+            if request.method == HttpMethod::HEAD {
+                response = response.head_only();
+            }
+            if let Some(threshold) = self.spill_threshold {
+                response = response.spill_over(threshold);
+            }
+
+            info!(
+                "{} {} {} {}",
+                request
+                    .client_ip()
+                    .map_or_else(|| "-".to_string(), |ip| ip.to_string()),
+                request.method,
+                request.path,
+                response.status_code
+            );
+
+            response = response.for_version(request.version);
+
+            // Once shutdown has been requested, tell keep-alive clients to move on
+            // so their next request doesn't hit a closed listener mid-flight.
+            let keep_alive = self.keep_alive
+                && request.keep_alive()
+                && !self.shutdown.is_shutdown()
+                && !response
+                    .headers
+                    .get("Connection")
+                    .is_some_and(|v| v.eq_ignore_ascii_case("close"));
+            response = match keep_alive {
+                true => response.header("Connection", "keep-alive"),
+                false => response.header("Connection", "close"),
+            };
+
+            let toggle_nodelay = response.flush_mode == FlushMode::Immediate && !self.tcp_nodelay;
+            if toggle_nodelay && let Err(e) = reader.get_ref().socket().set_nodelay(true) {
+                error!("Error setting TCP_NODELAY: {:?}", e);
+            }
+
+            // A failed write (e.g. a streamed body erroring halfway) leaves the
+            // client with a truncated response, so the connection can't be reused
+            let sent = self.send_response(reader.get_mut(), response);
+
+            if toggle_nodelay && let Err(e) = reader.get_ref().socket().set_nodelay(false) {
+                error!("Error clearing TCP_NODELAY: {:?}", e);
+            }
+
+            if !sent {
+                return;
+            }
+            if !keep_alive {
+                // Lets TLS clients tell a complete response from a truncated one
+                let _ = reader.get_mut().close_write();
+                return;
+            }
+            if !self.await_next_request(&mut reader) {
+                return;
+            }
+        }
+    }
+
+    /// Waits up to the read timeout for a keep-alive client to send its next
+    /// request, giving up early once shutdown is requested so an idle client
+    /// doesn't hold up a graceful shutdown. Returns whether a request arrived.
+    fn await_next_request<C: Connection>(&self, reader: &mut BufReader<C>) -> bool {
+        if !reader.buffer().is_empty() {
+            return true;
+        }
+
+        let deadline = self.read_timeout_ms.map(|timeout| Instant::now() + timeout);
+        let arrived = loop {
+            if self.shutdown.is_shutdown() {
+                info!("Closing idle connection for shutdown");
+                break false;
+            }
+            let wait = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => IDLE_SHUTDOWN_POLL,
+            };
+            if wait.is_zero() {
+                error!("Client connection timed out");
+                break false;
+            }
+            if let Err(e) = reader
+                .get_ref()
+                .socket()
+                .set_read_timeout(Some(wait.min(IDLE_SHUTDOWN_POLL)))
+            {
+                error!("Error setting read timeout: {:?}", e);
+                break false;
+            }
+
+            match reader.fill_buf() {
+                Ok([]) => {
+                    info!("Client connection closed");
+                    break false;
+                }
+                Ok(_) => break true,
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    info!("Error waiting for next request: {:?}", e);
+                    break false;
+                }
+            }
+        };
+
+        if arrived
+            && let Err(e) = reader
+                .get_ref()
+                .socket()
+                .set_read_timeout(self.read_timeout_ms)
+        {
+            error!("Error setting read timeout: {:?}", e);
+            return false;
+        }
+        arrived
+    }
+
+    /// Serves connections from `listener` until shutdown is requested through
+    /// [`Server::shutdown_handle`].
+    pub fn listen_with_pool(
+        &self,
+        pool_size: Option<usize>,
+        listener: TcpListener,
+    ) -> Result<(), ServerError> {
+        if let Ok(addr) = listener.local_addr() {
+            self.shutdown.set_local_addr(addr);
+        }
+
+        start_subsystems(&self.subsystems, self.subsystem_stop_timeout)
+            .map_err(ServerError::StartupError)?;
+
+        for hook in &self.startup_hooks {
+            if let Err(e) = hook() {
+                error!("Startup hook failed: {}", e);
+                stop_subsystems(&self.subsystems, self.subsystem_stop_timeout);
+                return Err(ServerError::StartupError(e));
+            }
+        }
+
+        let pool_size = pool_size.unwrap_or_else(num_cpus::get).max(1);
+        let (sender, receiver) = mpsc::sync_channel(self.accept_queue_size(pool_size));
+        let receiver = Mutex::new(receiver);
+
+        // Workers live as long as the accept loop. Once it stops, the queue is
+        // closed and they drain the connections still waiting in it
+        let result = thread::scope(|scope| {
+            for id in 0..pool_size {
+                let receiver = &receiver;
+                thread::Builder::new()
+                    .name(format!("schnell-worker-{}", id))
+                    .spawn_scoped(scope, move || self.work(receiver))
+                    .map_err(|e| ServerError::StartupError(Box::new(e)))?;
+            }
+            self.accept_loop(&listener, sender)
+        });
+
+        info!("Server shutting down");
+        for hook in &self.shutdown_hooks {
+            hook();
+        }
+        stop_subsystems(&self.subsystems, self.subsystem_stop_timeout);
+
+        result
+    }
+
+    /// Accepts connections and queues them for the workers until shutdown is
+    /// requested or the listener fails.
+    fn accept_loop(
+        &self,
+        listener: &TcpListener,
+        queue: SyncSender<(TcpStream, Instant)>,
+    ) -> Result<(), ServerError> {
+        let mut backoff = Backoff::new();
+
+        // A shutdown requested before the address was published found nothing
+        // to connect to, so no connection will come to wake `accept` for it
+        if self.shutdown.is_shutdown() {
+            return Ok(());
+        }
+
+        loop {
+            let accepted = listener.accept();
+            if self.shutdown.is_shutdown() {
+                return Ok(());
+            }
+
+            let mut stream = match accepted {
+                Ok((stream, _)) => {
+                    backoff.reset();
+                    stream
+                }
+                Err(e) => {
+                    let kind = classify_accept_error(&e);
+                    for hook in &self.accept_error_hooks {
+                        hook(&e, kind);
+                    }
+
+                    match kind {
+                        AcceptErrorKind::Connection => {
+                            warn!("Error accepting connection: {}", e);
+                        }
+                        AcceptErrorKind::ResourceExhausted => {
+                            let delay = backoff.next_delay();
+                            warn!("Error accepting connection: {}, retrying in {:?}", e, delay);
+                            std::thread::sleep(delay);
+                        }
+                        AcceptErrorKind::Fatal => {
+                            error!("Listener failed: {}", e);
+                            return Err(ServerError::AcceptError(e));
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            if let Err(e) = stream.set_read_timeout(self.read_timeout_ms) {
+                error!("Error setting read timeout: {:?}", e);
+                self.send_response(&mut stream, HttpResponse::internal_server_error());
+            }
+
+            if let Err(e) = stream.set_write_timeout(self.write_timeout_ms) {
+                error!("Error setting write timeout: {:?}", e);
+                self.send_response(&mut stream, HttpResponse::internal_server_error());
+            }
+
+            if let Err(e) = stream.set_nodelay(self.tcp_nodelay) {
+                error!("Error setting TCP_NODELAY: {:?}", e);
+            }
+
+            self.queued_connections.fetch_add(1, Ordering::Relaxed);
+            match queue.try_send((stream, Instant::now())) {
+                Ok(()) => {}
+                Err(TrySendError::Full((stream, _))) => {
+                    self.queued_connections.fetch_sub(1, Ordering::Relaxed);
+                    warn!("Accept queue full, rejecting connection");
+                    self.reject_overloaded(stream, false);
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    self.queued_connections.fetch_sub(1, Ordering::Relaxed);
+                    error!("All workers have stopped");
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Serves queued connections until the accept loop closes the queue.
+    fn work(&self, queue: &Mutex<Receiver<(TcpStream, Instant)>>) {
+        loop {
+            let next = match queue.lock() {
+                Ok(receiver) => receiver.recv(),
+                Err(_) => return,
+            };
+            let Ok((stream, queued_at)) = next else {
+                return;
+            };
+            self.queued_connections.fetch_sub(1, Ordering::Relaxed);
+
+            let waited = queued_at.elapsed();
+            if let Some(timeout) = self.queue_timeout
+                && waited > timeout
+            {
+                warn!("Connection waited {:?} for a worker, rejecting it", waited);
+                self.reject_overloaded(stream, true);
+                continue;
+            }
+
+            // Keeps the worker alive when a handler panics
+            let served = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                self.serve_accepted(stream)
+            }));
+            if served.is_err() {
+                error!("Worker recovered from a panic while serving a connection");
+            }
+        }
+    }
+
+    /// Answers a connection no worker has room for with a 503 and closes it.
+    /// With `drain`, a request the client already sent is read first, so that
+    /// closing doesn't reset the connection before the 503 arrives. The accept
+    /// loop skips it to stay free for the next connection.
+    fn reject_overloaded(&self, mut stream: TcpStream, drain: bool) {
+        self.rejected_connections.fetch_add(1, Ordering::Relaxed);
+
+        // A TLS client can't read a response before its handshake
+        #[cfg(feature = "tls")]
+        if self.tls.is_some() {
+            return;
+        }
+        let response = HttpResponse::new(503).header("Retry-After", "1");
+        if drain {
+            self.close_after_error(&mut BufReader::new(stream), response);
+        } else {
+            self.send_response(&mut stream, response.header("Connection", "close"));
+        }
+    }
+
+    fn accept_queue_size(&self, pool_size: usize) -> usize {
+        self.accept_queue
+            .unwrap_or(pool_size * DEFAULT_ACCEPT_QUEUE_PER_WORKER)
+    }
+
+    pub fn group<F>(&mut self, prefix: &str, config: F)
+    where
+        F: FnOnce(&mut RouteGroup),
+    {
+        let chain = RouteGroup::new(
+            join_path("/", prefix),
+            &mut self.routes,
+            &mut self.error_handlers,
+        )
+        .configure(config);
+        self.group_middleware.extend(chain);
+    }
+
+    /// A 400 for request targets this server won't route.
+    fn check_target(&self, request: &Request) -> Option<HttpResponse> {
+        // Absolute-form targets are meant for proxies, which this server isn't
+        if request.absolute_authority().is_some() {
+            return Some(
+                HttpResponse::bad_request()
+                    .text("Absolute-form request targets are only accepted by proxies"),
+            );
+        }
+
+        if self.strict_paths {
+            let path = request.target().split('?').next().unwrap_or("");
+            if let Err(e) = normalize_path_strict(path) {
+                return Some(HttpResponse::bad_request().text(&e.to_string()));
+            }
+        }
+        None
+    }
+
+    fn dispatch(&self, request: &mut Request) -> HttpResponse {
+        request.set_state(self.state.clone());
+        request.set_keyring(self.keyring.clone());
+        if request.method == HttpMethod::OPTIONS {
+            request.set_routed_methods(self.allowed_methods(&request.path, &self.routes));
+        }
+
+        let endpoint = |request: &mut Request| self.route(request);
+        let response = match Next::new(&self.middleware, &endpoint).run(request) {
+            Ok(response) => response,
+            Err(err) => {
+                error!("Error handling request: {:?}", err);
+                HttpResponse::internal_server_error()
+            }
+        };
+
+        self.error_handlers.apply(request, response)
+    }
+
+    fn route(&self, request: &mut Request) -> std::io::Result<HttpResponse> {
+        let mut resolved = self.resolve(&request.path, request.method, &self.routes);
+
+        // HEAD falls back to the GET handler; the body is dropped when sending
+        if request.method == HttpMethod::HEAD {
+            resolved =
+                resolved.or_else(|_| self.resolve(&request.path, HttpMethod::GET, &self.routes));
+        }
+
+        let route = match resolved {
+            Ok(route) => route,
+            Err(RouteError::MethodNotAllowed) => {
+                let allow = self
+                    .allowed_methods(&request.path, &self.routes)
+                    .iter()
+                    .map(HttpMethod::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                // Known path without an OPTIONS handler of its own
+                let method = request.method;
+                let endpoint = |_: &mut Request| {
+                    let response = match method {
+                        HttpMethod::OPTIONS => HttpResponse::new(204),
+                        _ => HttpResponse::method_not_allowed(),
+                    };
+                    Ok(response.header("Allow", &allow))
+                };
+                // The group's own CORS policy answers preflights to its paths
+                return match self.preflight_group(request) {
+                    Some(chain) => Next::new(chain, &endpoint).run(request),
+                    None => endpoint(request),
+                };
+            }
+            Err(RouteError::NotFound) => return Ok(HttpResponse::not_found()),
+        };
+
+        request.params = extract_params(&route.path, &request.path);
+        let endpoint = |request: &mut Request| match self.handler_timeout {
+            Some(timeout) => self.call_with_timeout(&route.handler, request, timeout),
+            None => (route.handler)(request),
+        };
+        route.run(request, &endpoint)
+    }
+
+    /// Middleware of the most specific group covering a CORS preflight's path.
+    fn preflight_group(&self, request: &Request) -> Option<&MiddlewareChain> {
+        if request.method != HttpMethod::OPTIONS
+            || !request
+                .headers
+                .contains_key("access-control-request-method")
+        {
+            return None;
+        }
+        self.group_middleware
+            .iter()
+            .filter(|(prefix, _)| in_scope(prefix, &request.path))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, chain)| chain)
+    }
+
+    /// Runs `handler` on a thread of its own and gives up on it after `timeout`.
+    fn call_with_timeout(
+        &self,
+        handler: &RouteHandler,
+        request: &Request,
+        timeout: Duration,
+    ) -> std::io::Result<HttpResponse> {
+        if self.timed_out_handlers.load(Ordering::Relaxed) >= self.max_timed_out_handlers {
+            warn!(
+                "Too many timed-out handlers still running, rejecting {} {:?}",
+                request.method, request.path
+            );
+            return Ok(HttpResponse::new(503).header("Retry-After", "1"));
+        }
+
+        let handler = Arc::clone(handler);
+        let request = request.clone();
+        let (method, path) = (request.method, request.path.clone());
+        let (sender, receiver) = mpsc::channel();
+        // Set by whichever comes first, the handler finishing or the wait for
+        // it timing out; the other side then settles the timed-out count
+        let settled = Arc::new(AtomicBool::new(false));
+        let timed_out_handlers = Arc::clone(&self.timed_out_handlers);
+        let handler_settled = Arc::clone(&settled);
+        thread::Builder::new()
+            .name("schnell-handler".to_string())
+            .spawn(move || {
+                // A panic drops `sender` unsent, which the caller reports
+                let result =
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| handler(&request)));
+                if let Ok(result) = result {
+                    let _ = sender.send(result);
+                }
+                if handler_settled.swap(true, Ordering::SeqCst) {
+                    timed_out_handlers.fetch_sub(1, Ordering::SeqCst);
+                }
+            })?;
+
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                self.timed_out_handlers.fetch_add(1, Ordering::SeqCst);
+                if settled.swap(true, Ordering::SeqCst) {
+                    self.timed_out_handlers.fetch_sub(1, Ordering::SeqCst);
+                }
+                warn!(
+                    "Handler for {} {:?} timed out after {}ms",
+                    method,
+                    path,
+                    timeout.as_millis()
+                );
+                Ok(HttpResponse::new(503).header("Retry-After", "1"))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(std::io::Error::other("handler panicked")),
+        }
+    }
+
+    /// 413 with an RFC 7807 problem body stating the limit.
+    fn payload_too_large(&self) -> HttpResponse {
+        let limit = self.request_limits.max_body_size;
+        HttpResponse::request_entity_too_large()
+            .body(&format!(
+                r#"{{"type":"about:blank","title":"Payload Too Large","status":413,"detail":"Request body exceeds the limit of {limit} bytes","max_body_size":{limit}}}"#
+            ))
+            .content_type("application/problem+json")
+    }
+
+    /// Sends a framework-level error response and closes the connection. After a
+    /// malformed or rejected request the rest of the byte stream can't be trusted,
+    /// so nothing more is read as a request: the write side is shut down and
+    /// whatever the client already sent is drained, so the kernel doesn't answer
+    /// unread bytes with a RST that could destroy the response in flight.
+    fn close_after_error<C: Connection>(&self, reader: &mut BufReader<C>, response: HttpResponse) {
+        self.send_response(reader.get_mut(), response.header("Connection", "close"));
+
+        if let Err(e) = reader.get_mut().close_write() {
+            info!("Error half-closing connection: {:?}", e);
+            return;
+        }
+        if let Err(e) = reader
+            .get_ref()
+            .socket()
+            .set_read_timeout(Some(ERROR_DRAIN_TIMEOUT))
+        {
+            error!("Error setting read timeout: {:?}", e);
+        }
+        let _ = std::io::copy(&mut reader.take(ERROR_DRAIN_LIMIT), &mut std::io::sink());
+    }
+
+    /// Serves a freshly accepted socket, wrapping it in a TLS session first when
+    /// the server terminates HTTPS.
+    fn serve_accepted(&self, stream: TcpStream) {
+        #[cfg(feature = "tls")]
+        if let Some(config) = &self.tls {
+            let mut stream = stream;
+            let mut session = match rustls::ServerConnection::new(std::sync::Arc::clone(config)) {
+                Ok(session) => session,
+                Err(e) => {
+                    error!("Error creating TLS session: {:?}", e);
+                    return;
+                }
+            };
+            if let Err(e) = tls::handshake(&mut session, &mut stream, self.tls_handshake_timeout) {
+                self.tls_handshake_failures.fetch_add(1, Ordering::Relaxed);
+                warn!(
+                    "TLS handshake with {} failed: {}",
+                    stream
+                        .peer_addr()
+                        .map_or_else(|_| "-".to_string(), |addr| addr.to_string()),
+                    e
+                );
+                return;
+            }
+            if let Err(e) = stream
+                .set_read_timeout(self.read_timeout_ms)
+                .and_then(|_| stream.set_write_timeout(self.write_timeout_ms))
+            {
+                error!("Error restoring timeouts after TLS handshake: {:?}", e);
+            }
+            self.serve_connection(rustls::StreamOwned::new(session, stream));
+            return;
+        }
+
+        self.serve_connection(stream)
+    }
+
+    /// Writes `response`, returning whether it made it onto the wire completely.
+    fn send_response<W: Write>(&self, stream: &mut W, response: HttpResponse) -> bool {
+        let response = self.standard_headers(response);
+        match write_response_buffered(stream, response, self.output_buffer_size) {
+            Ok(()) => true,
+            Err(err) if is_client_abort(&err) => {
+                self.client_aborts.fetch_add(1, Ordering::Relaxed);
+                info!("Client went away mid-response: {}", err);
+                false
+            }
+            Err(err) => {
+                error!("Error writing response: {:?}", err);
+                false
+            }
+        }
+    }
+
+    /// `Date` and `Server`, unless the handler set its own.
+    fn standard_headers(&self, response: HttpResponse) -> HttpResponse {
+        let mut response = response;
+        if !response.headers.contains_key("Date") {
+            response = response.header("Date", &http_date(self.clock.system_time()));
+        }
+        if let Some(server) = &self.server_header
+            && !response.headers.contains_key("Server")
+        {
+            response = response.header("Server", server);
+        }
+        response
+    }
+}
+
+/// The client closed or reset the connection, so nothing more can reach it.
+fn is_client_abort(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::BrokenPipe | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted
+    )
+}
+
+/// Headers and the start of the body, for `Server::with_request_logging`.
+fn log_request(request: &Request, max_body: usize, redaction: &Redaction) {
+    if !log::log_enabled!(log::Level::Debug) {
+        return;
+    }
+
+    let headers = request
+        .raw_headers()
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, redaction.header_value(name, value)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let content_type = request
+        .headers
+        .get("content-type")
+        .map_or("", |v| v.as_str());
+    debug!(
+        "{} {} [{}] {}",
+        request.method,
+        request.path,
+        headers,
+        redaction.body(content_type, request.raw_body(), max_body)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Shutdown;
+    use std::thread;
+
+    fn serve_one(server: Server, input: &[u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let input = input.to_vec();
+
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(&input).unwrap();
+            stream.shutdown(Shutdown::Write).unwrap();
+            let mut output = String::new();
+            stream.read_to_string(&mut output).unwrap();
+            output
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        server.handle_connection(stream);
+        client.join().unwrap()
+    }
+
+    #[test]
+    fn test_server_group() {
+        let mut server = Server::new("127.0.0.1", 8080, None);
+        server.group("/api", |group| {
+            group.get("/users", |_| HttpResponse::ok());
+        });
+
+        assert_eq!(server.routes.len(), 1);
+        assert_eq!(server.routes[0].path, "/api/users");
+        assert_eq!(server.routes[0].method, HttpMethod::GET);
+    }
+
+    #[test]
+    fn test_route_params_are_extracted() {
+        let mut server = Server::new("127.0.0.1", 8080, None);
+        server.get("/users/:id", |req| {
+            HttpResponse::ok().text(&req.params["id"])
+        });
+
+        let output = serve_one(server, b"GET /users/42 HTTP/1.1\r\n\r\n");
+        assert!(output.starts_with("HTTP/1.1 200 OK"));
+        assert!(output.ends_with("\r\n\r\n42"));
+    }
+
+    #[test]
+    fn test_pipelined_requests_with_keep_alive() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        server.get("/a", |_| HttpResponse::ok().text("first"));
+        server.post("/b", |req| HttpResponse::ok().text(&req.body));
+
+        let output = serve_one(
+            server,
+            b"GET /a HTTP/1.1\r\n\r\nPOST /b HTTP/1.1\r\nContent-Length: 6\r\n\r\nsecondGET /missing HTTP/1.1\r\n\r\n",
+        );
+
+        let responses: Vec<&str> = output.matches("HTTP/1.1 ").collect();
+        assert_eq!(responses.len(), 3);
+        assert!(output.contains("\r\n\r\nfirst"));
+        assert!(output.contains("\r\n\r\nsecond"));
+        assert!(output.contains("HTTP/1.1 404 Not Found"));
+    }
+
+    #[test]
+    fn test_context_handler_with_state() {
+        use crate::routing::Context;
+
+        struct Greeting(&'static str);
+
+        let mut server = Server::new("127.0.0.1", 8080, None).with_state(Greeting("Hello"));
+        server.get("/plain", |req| {
+            HttpResponse::ok().text(req.state::<Greeting>().unwrap().0)
+        });
+        server.get(
+            "/users/:id",
+            Context::handler(|ctx| {
+                let id: u32 = ctx.param("id").unwrap_or_default();
+                let greeting = ctx.state::<Greeting>().unwrap();
+                Ok(ctx.text(&format!("{} user {}", greeting.0, id + 1)))
+            }),
+        );
+
+        let output = serve_one(
+            server.with_keep_alive(true),
+            b"GET /plain HTTP/1.1\r\n\r\nGET /users/41 HTTP/1.1\r\n\r\n",
+        );
+        assert!(output.contains("\r\n\r\nHelloHTTP/1.1"));
+        assert!(output.ends_with("\r\n\r\nHello user 42"));
+    }
+
+    #[test]
+    fn test_signed_cookies() {
+        use crate::http::Cookie;
+
+        let mut server = Server::new("127.0.0.1", 8080, None).with_secret_key("s3cr3t");
+        server.get("/me", |req| {
+            HttpResponse::ok().text(req.signed_cookie("uid").unwrap_or("anonymous"))
+        });
+
+        let signed = Cookie::new("uid", "42").signed(&Keyring::new("s3cr3t"));
+        let tampered = signed.value().replacen("42", "1", 1);
+        for (cookie, expected) in [(signed.value(), "42"), (tampered.as_str(), "anonymous")] {
+            let raw = format!(
+                "GET /me HTTP/1.1\r\nCookie: theme=dark; uid={}\r\n\r\n",
+                cookie
+            );
+            let mut request = Request::read(&mut BufReader::new(raw.as_bytes())).unwrap();
+            assert_eq!(server.dispatch(&mut request).body, expected);
+        }
+    }
+
+    #[test]
+    fn test_client_ip_is_set_from_peer() {
+        let mut server = Server::new("127.0.0.1", 8080, None);
+        server.get("/ip", |req| {
+            HttpResponse::ok().text(&req.client_ip().unwrap().to_string())
+        });
+
+        let output = serve_one(server, b"GET /ip HTTP/1.1\r\n\r\n");
+        assert!(output.ends_with("127.0.0.1"));
+    }
+
+    #[test]
+    fn test_from_addr_accepts_socket_addrs() {
+        let server = Server::from_addr("127.0.0.1:0", Some(1)).unwrap();
+        let handle = server.shutdown_handle();
+        let server_thread = thread::spawn(move || server.listen());
+
+        assert!(wait_for_addr(&handle).ip().is_loopback());
+        handle.shutdown();
+        assert!(server_thread.join().unwrap().is_ok());
+
+        assert!(Server::from_addr("not an address", None).is_err());
+        assert!(!Server::new("::1", 8080, None).addrs.is_empty());
+    }
+
+    #[test]
+    fn test_body_over_limit_is_rejected_early() {
+        let mut server = Server::new("127.0.0.1", 8080, None)
+            .with_keep_alive(true)
+            .with_request_limits(RequestLimits {
+                max_body_size: 16,
+                ..RequestLimits::default()
+            });
+        server.post("/upload", |_| HttpResponse::ok());
+
+        let output = serve_one(
+            server,
+            b"POST /upload HTTP/1.1\r\nContent-Length: 1000000\r\n\r\nGET / HTTP/1.1\r\n\r\n",
+        );
+
+        assert!(output.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+        assert!(output.contains("Content-Type: application/problem+json\r\n"));
+        assert!(output.contains("Connection: close\r\n"));
+        assert!(output.contains("\"max_body_size\":16"));
+        assert_eq!(output.matches("HTTP/1.1 ").count(), 1);
+    }
+
+    #[test]
+    fn test_cors_wraps_other_middleware() {
+        let mut server = Server::new("127.0.0.1", 8080, None)
+            .with_keep_alive(true)
+            .with_cors(CorsConfig::default());
+        server.put("/items", |_| HttpResponse::ok());
+        server.use_middleware(|req: &mut Request, next: Next| {
+            if !req.headers.contains_key("authorization") {
+                return Ok(HttpResponse::unauthorized());
+            }
+            next.run(req)
+        });
+
+        let output = serve_one(
+            server,
+            b"OPTIONS /items HTTP/1.1\r\nOrigin: https://a.test\r\nAccess-Control-Request-Method: PUT\r\n\r\n\
+              PUT /items HTTP/1.1\r\nOrigin: https://a.test\r\nConnection: close\r\n\r\n",
+        );
+
+        assert!(output.starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(output.contains("HTTP/1.1 401 Unauthorized\r\n"));
+        assert_eq!(
+            output.matches("Access-Control-Allow-Origin: *\r\n").count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_cors_credentials_with_any_origin_abort_listen() {
+        let server = Server::new("127.0.0.1", 0, Some(1)).with_cors(CorsConfig {
+            credentials: true,
+            ..CorsConfig::default()
+        });
+
+        match server.listen() {
+            Err(ServerError::StartupError(e)) => assert!(e.to_string().contains("credentials")),
+            other => panic!("Expected StartupError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_cors_preflight_reflects_routes() {
+        let mut server = Server::new("127.0.0.1", 8080, None)
+            .with_keep_alive(true)
+            .with_cors(CorsConfig::default());
+        server.get("/items", |_| HttpResponse::ok());
+        server.post("/items", |_| HttpResponse::ok());
+
+        let output = serve_one(
+            server,
+            b"OPTIONS /items HTTP/1.1\r\nOrigin: https://a.test\r\nAccess-Control-Request-Method: POST\r\n\r\n\
+              OPTIONS /items HTTP/1.1\r\nOrigin: https://a.test\r\nAccess-Control-Request-Method: DELETE\r\n\r\n\
+              OPTIONS /missing HTTP/1.1\r\nOrigin: https://a.test\r\nAccess-Control-Request-Method: GET\r\n\
+              Connection: close\r\n\r\n",
+        );
+
+        assert!(output.starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(output.contains("Access-Control-Allow-Methods: GET, HEAD, POST\r\n"));
+        assert_eq!(output.matches("HTTP/1.1 403 Forbidden\r\n").count(), 2);
+    }
+
+    #[test]
+    fn test_group_cors_answers_preflights() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        server.put("/public", |_| HttpResponse::ok());
+        server.group("/api", |group| {
+            group.with(CorsConfig {
+                allowed_origins: vec!["https://a.test".to_string()],
+                ..CorsConfig::default()
+            });
+            group.put("/items", |_| HttpResponse::ok());
+        });
+
+        let output = serve_one(
+            server,
+            b"OPTIONS /api/items HTTP/1.1\r\nOrigin: https://a.test\r\nAccess-Control-Request-Method: PUT\r\n\r\n\
+              OPTIONS /api/items HTTP/1.1\r\nOrigin: https://b.test\r\nAccess-Control-Request-Method: PUT\r\n\r\n\
+              OPTIONS /public HTTP/1.1\r\nOrigin: https://a.test\r\nAccess-Control-Request-Method: PUT\r\n\
+              Connection: close\r\n\r\n",
+        );
+
+        let responses: Vec<&str> = output.split("HTTP/1.1 ").skip(1).collect();
+        assert_eq!(responses.len(), 3);
+        assert!(responses[0].starts_with("204 No Content\r\n"));
+        assert!(responses[0].contains("Access-Control-Allow-Origin: https://a.test\r\n"));
+        assert!(responses[0].contains("Access-Control-Allow-Methods: PUT\r\n"));
+        assert!(responses[1].starts_with("403 Forbidden\r\n"));
+        assert!(responses[2].starts_with("204 No Content\r\n"));
+        assert!(!responses[2].contains("Access-Control-"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_admin_stats_report_breakers() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        let breaker = server
+            .breakers()
+            .register(crate::routing::CircuitBreaker::new(
+                "weather",
+                1,
+                Duration::from_secs(60),
+            ));
+        server.get(
+            "/weather",
+            breaker.wrap(|_| Ok(HttpResponse::bad_gateway())),
+        );
+        server.serve_admin_stats("/admin/stats");
+
+        let output = serve_one(
+            server,
+            b"GET /weather HTTP/1.1\r\n\r\nGET /admin/stats HTTP/1.1\r\nConnection: close\r\n\r\n",
+        );
+
+        assert!(output.contains("HTTP/1.1 502 Bad Gateway\r\n"));
+        assert!(output.contains(r#""name":"weather""#));
+        assert!(output.contains(r#""state":"Open""#));
+    }
+
+    #[test]
+    fn test_handler_timeout() {
+        let mut server = Server::new("127.0.0.1", 8080, None)
+            .with_keep_alive(true)
+            .with_handler_timeout(Duration::from_millis(50));
+        server.get("/fast", |_| HttpResponse::ok().text("done"));
+        server.get("/slow", |_| {
+            thread::sleep(Duration::from_millis(500));
+            HttpResponse::ok()
+        });
+        server.get("/panics", |_| -> std::io::Result<HttpResponse> {
+            panic!("handler bug")
+        });
+
+        let started = std::time::Instant::now();
+        let output = serve_one(
+            server,
+            b"GET /slow HTTP/1.1\r\n\r\nGET /panics HTTP/1.1\r\n\r\n\
+              GET /fast HTTP/1.1\r\nConnection: close\r\n\r\n",
+        );
+
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert!(output.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(output.contains("Retry-After: 1\r\n"));
+        assert!(output.contains("HTTP/1.1 500 Internal Server Error\r\n"));
+        assert!(output.ends_with("\r\n\r\ndone"));
+    }
+
+    #[test]
+    fn test_timed_out_handlers_are_capped() {
+        let entered = Arc::new(AtomicUsize::new(0));
+        let (release, released) = mpsc::channel::<()>();
+        let released = Mutex::new(released);
+        let mut server = Server::new("127.0.0.1", 0, Some(1))
+            .with_handler_timeout(Duration::from_millis(20))
+            .with_max_timed_out_handlers(1);
+        let counter = Arc::clone(&entered);
+        server.get("/hang", move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let _ = released.lock().unwrap().recv();
+            "late"
+        });
+        server.get("/fast", |_| "done");
+
+        let server = Arc::new(server);
+        let handle = server.shutdown_handle();
+        let server_thread = thread::spawn({
+            let server = Arc::clone(&server);
+            move || server.listen()
+        });
+        let addr = wait_for_addr(&handle);
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nConnection: close\r\n\r\n", path).unwrap();
+            let mut output = String::new();
+            stream.read_to_string(&mut output).unwrap();
+            output
+        };
+
+        assert!(get("/hang").starts_with("HTTP/1.1 503 "));
+        assert_eq!(server.timed_out_handlers(), 1);
+
+        // The cap is reached: no new handler thread is started at all
+        let output = get("/fast");
+        assert!(output.starts_with("HTTP/1.1 503 "));
+        assert!(output.contains("Retry-After: 1\r\n"));
+        assert!(get("/hang").starts_with("HTTP/1.1 503 "));
+        assert_eq!(entered.load(Ordering::SeqCst), 1);
+
+        release.send(()).unwrap();
+        while server.timed_out_handlers() > 0 {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(get("/fast").ends_with("done"));
+
+        handle.shutdown();
+        assert!(server_thread.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_bind_retry_and_fallback_ports() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let spare = TcpListener::bind("127.0.0.1:0").unwrap();
+        let spare_port = spare.local_addr().unwrap().port();
+        drop(spare);
+
+        let server = Server::new("127.0.0.1", port, None);
+        let error = server.bind().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::AddrInUse);
+
+        let server = server.with_fallback_ports(&[port, spare_port]);
+        let listener = server.bind().unwrap();
+        assert_eq!(listener.local_addr().unwrap().port(), spare_port);
+        drop(listener);
+
+        // Freed while retrying, as by an instance finishing its shutdown
+        let releaser = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            drop(taken);
+        });
+        let server = server.with_bind_retry(Duration::from_secs(5));
+        let listener = server.bind().unwrap();
+        assert_eq!(listener.local_addr().unwrap().port(), port);
+        releaser.join().unwrap();
+    }
+
+    #[test]
+    fn test_spill_threshold() {
+        let mut server = Server::new("127.0.0.1", 8080, None)
+            .with_keep_alive(true)
+            .with_spill_threshold(64);
+        server.get("/small", |_| "tiny");
+        server.get("/export", |_| "row\n".repeat(100));
+
+        let output = serve_one(
+            server,
+            b"GET /small HTTP/1.1\r\n\r\nGET /export HTTP/1.1\r\nConnection: close\r\n\r\n",
+        );
+
+        let (small, export) = output.split_once("\r\n\r\ntiny").unwrap();
+        assert!(small.contains("Content-Length: 4"));
+        assert!(export.contains("Transfer-Encoding: chunked\r\n"));
+        assert_eq!(export.matches("row\n").count(), 100);
+    }
+
+    #[test]
+    fn test_client_abort_mid_stream() {
+        let mut server = Server::new("127.0.0.1", 8080, None);
+        server.get("/feed", |_| {
+            HttpResponse::ok().stream(std::io::repeat(b'x').take(64 * 1024 * 1024))
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(b"GET /feed HTTP/1.1\r\n\r\n").unwrap();
+            let mut head = [0; 64];
+            stream.read_exact(&mut head).unwrap();
+            // Closing with unread data makes the kernel reset the connection
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        let started = Instant::now();
+        server.handle_connection(stream);
+        client.join().unwrap();
+
+        assert_eq!(server.client_aborts(), 1);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_standard_headers() {
+        let clock = Clock::manual_at(std::time::UNIX_EPOCH + Duration::from_secs(784111777));
+        let mut server = Server::new("127.0.0.1", 8080, None)
+            .with_keep_alive(true)
+            .with_clock(clock);
+        server.get("/", |_| HttpResponse::ok());
+        server.get("/bye", |_| {
+            HttpResponse::ok()
+                .header("Server", "edge")
+                .header("Connection", "close")
+        });
+
+        let output = serve_one(
+            server,
+            b"GET / HTTP/1.1\r\n\r\nGET /bye HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\n",
+        );
+
+        let responses: Vec<&str> = output.split("HTTP/1.1 ").skip(1).collect();
+        let (first, second) = (responses[0], responses[1]);
+        assert!(first.contains("Date: Sun, 06 Nov 1994 08:49:37 GMT\r\n"));
+        assert!(first.contains("Server: schnell\r\n"));
+        assert!(first.contains("Connection: keep-alive\r\n"));
+        // The handler's own headers win, and its close ends the connection
+        assert!(second.contains("Server: edge\r\n"));
+        assert!(second.contains("Connection: close\r\n"));
+        assert_eq!(responses.len(), 2);
+
+        let server = Server::new("127.0.0.1", 8080, None).with_server_header(None);
+        let output = serve_one(server, b"GET / HTTP/1.1\r\n\r\n");
+        assert!(!output.contains("Server:"));
+    }
+
+    #[test]
+    fn test_head_falls_back_to_get() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        server.get("/health", |_| HttpResponse::ok().text("alive"));
+        server.post("/jobs", |_| HttpResponse::ok());
+
+        let output = serve_one(
+            server,
+            b"HEAD /health HTTP/1.1\r\n\r\nHEAD /jobs HTTP/1.1\r\nConnection: close\r\n\r\n",
+        );
+
+        assert!(output.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(output.contains("Content-Length: 5\r\n"));
+        assert!(!output.contains("\r\n\r\nalive"));
+        assert!(output.contains("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+
+    #[test]
+    fn test_options_and_405_list_allowed_methods() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        server.get("/items/:id", |_| HttpResponse::ok());
+        server.delete("/items/:id", |_| HttpResponse::ok());
+
+        let output = serve_one(
+            server,
+            b"OPTIONS /items/1 HTTP/1.1\r\n\r\nPUT /items/1 HTTP/1.1\r\nConnection: close\r\n\r\n",
+        );
+
+        assert!(output.starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(output.contains("HTTP/1.1 405 Method Not Allowed\r\n"));
+        assert_eq!(
+            output
+                .matches("Allow: GET, DELETE, HEAD, OPTIONS\r\n")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_absolute_form_rejected() {
+        let mut server = Server::new("127.0.0.1", 8080, None);
+        server.get("/a", |_| HttpResponse::ok());
+
+        let output = serve_one(server, b"GET http://example.com/a HTTP/1.1\r\n\r\n");
+
+        assert!(output.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    }
+
+    #[test]
+    fn test_unicode_paths() {
+        let mut server = Server::new("127.0.0.1", 8080, None)
+            .with_keep_alive(true)
+            .with_strict_paths(true);
+        server.get("/cafe\u{301}", |_| HttpResponse::ok().text("menu"));
+
+        let output = serve_one(
+            server,
+            b"GET /caf%C3%A9 HTTP/1.1\r\n\r\nGET /caf%C0%AF HTTP/1.1\r\nConnection: close\r\n\r\n",
+        );
+
+        assert!(output.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(output.contains("menu"));
+        assert!(output.contains("HTTP/1.1 400 Bad Request\r\n"));
+    }
+
+    #[test]
+    fn test_middleware_wraps_unmatched_routes() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        server.get("/private", |_| HttpResponse::ok().text("secret"));
+        server.use_middleware(|req: &mut Request, next: Next| {
+            let response = next.run(req)?;
+            Ok(response.header("X-Powered-By", "schnell"))
+        });
+        server.use_middleware(|req: &mut Request, next: Next| {
+            if req.path.starts_with("/private") && !req.headers.contains_key("authorization") {
+                return Ok(HttpResponse::unauthorized());
+            }
+            next.run(req)
+        });
+
+        let output = serve_one(
+            server,
+            b"GET /private HTTP/1.1\r\n\r\nGET /private HTTP/1.1\r\nAuthorization: yes\r\n\r\nGET /missing HTTP/1.1\r\n\r\n",
+        );
+
+        assert!(output.contains("HTTP/1.1 401 Unauthorized"));
+        assert!(output.contains("secret"));
+        assert!(output.contains("HTTP/1.1 404 Not Found"));
+        assert_eq!(output.matches("X-Powered-By: schnell").count(), 3);
+    }
+
+    #[test]
+    fn test_group_error_handlers_override_global() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        server.error_handler(404, |_| {
+            HttpResponse::not_found().html("<h1>Page not found</h1>")
+        });
+        server.group("/api", |group| {
+            group.get("/users", |_| -> std::io::Result<HttpResponse> {
+                Err(std::io::Error::other("database down"))
+            });
+            group.error_handler(404, |req| {
+                HttpResponse::not_found().text(&format!("no route for {}", req.path))
+            });
+            group.error_handler(500, |_| {
+                HttpResponse::internal_server_error().text("internal error")
+            });
+        });
+
+        let output = serve_one(
+            server,
+            b"GET /api/missing HTTP/1.1\r\n\r\nGET /api/users HTTP/1.1\r\n\r\nGET /missing HTTP/1.1\r\n\r\n",
+        );
+
+        assert!(output.contains("\r\n\r\nno route for /api/missing"));
+        assert!(output.contains("\r\n\r\ninternal error"));
+        assert!(output.contains("<h1>Page not found</h1>"));
+    }
+
+    #[test]
+    fn test_connection_closed_after_malformed_request() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        server.get("/", |_| HttpResponse::ok());
+
+        let output = serve_one(
+            server,
+            b"GET / HTTP/1.1\r\n\r\nNOT A REQUEST\r\n\r\nGET / HTTP/1.1\r\n\r\n",
+        );
+
+        assert!(output.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert_eq!(output.matches("HTTP/1.1 ").count(), 2);
+        assert!(output.contains("HTTP/1.1 400 Bad Request\r\n"));
+        let last = output.rsplit("HTTP/1.1 ").next().unwrap();
+        assert!(last.contains("Connection: close\r\n"));
+    }
+
+    #[test]
+    fn test_invalid_content_length_is_not_smuggled() {
+        for length in ["abc", "5, 6", "-1", ""] {
+            let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+            server.post("/", |_| HttpResponse::ok());
+            server.get("/admin", |_| HttpResponse::ok().text("smuggled"));
+
+            let input = format!(
+                "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\nGET /admin HTTP/1.1\r\n\r\n",
+                length
+            );
+            let output = serve_one(server, input.as_bytes());
+
+            assert!(
+                output.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+                "{}",
+                length
+            );
+            assert!(output.contains("Connection: close\r\n"));
+            assert!(!output.contains("smuggled"));
+        }
+    }
+
+    #[test]
+    fn test_conflicting_content_lengths_are_rejected() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        server.post("/", |req| HttpResponse::ok().text(&req.body));
+        server.get("/admin", |_| HttpResponse::ok().text("smuggled"));
+
+        let output = serve_one(
+            server,
+            b"POST / HTTP/1.1\r\nContent-Length: 0\r\nContent-Length: 21\r\n\r\n\
+              GET /admin HTTP/1.1\r\n\r\n",
+        );
+        assert!(output.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(output.contains("Connection: close\r\n"));
+        assert!(!output.contains("smuggled"));
+
+        // Repeating the same length is harmless
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        server.post("/", |req| HttpResponse::ok().text(&req.body));
+        let output = serve_one(
+            server,
+            b"POST / HTTP/1.1\r\nContent-Length: 2\r\ncontent-length: 2\r\nConnection: close\r\n\r\nok",
+        );
+        assert!(output.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(output.ends_with("ok"));
+    }
+
+    #[test]
+    fn test_chunked_with_content_length_is_rejected() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        server.post("/", |req| HttpResponse::ok().text(&req.body));
+        server.get("/admin", |_| HttpResponse::ok().text("smuggled"));
+
+        let output = serve_one(
+            server,
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 4\r\n\r\n\
+              0\r\n\r\nGET /admin HTTP/1.1\r\n\r\n",
+        );
+
+        assert!(output.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(output.contains("Connection: close\r\n"));
+        assert_eq!(output.matches("HTTP/1.1 ").count(), 1);
+        assert!(!output.contains("smuggled"));
+    }
+
+    #[test]
+    fn test_streamed_response_keeps_connection_usable() {
+        let mut server = Server::new("127.0.0.1", 8080, None)
+            .with_keep_alive(true)
+            .with_output_buffer_size(4);
+        server.get("/export", |_| {
+            HttpResponse::ok().stream(std::io::Cursor::new("id,name\n1,alice\n"))
+        });
+        server.get("/after", |_| HttpResponse::ok().text("done"));
+
+        let output = serve_one(
+            server,
+            b"GET /export HTTP/1.1\r\n\r\nGET /after HTTP/1.1\r\nConnection: close\r\n\r\n",
+        );
+
+        assert!(output.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(output.contains("4\r\nid,n\r\n"));
+        assert!(output.contains("0\r\n\r\nHTTP/1.1 200 OK"));
+        assert!(output.ends_with("done"));
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_tls_round_trip() {
+        use rustls::pki_types::pem::PemObject;
+        use rustls::pki_types::{CertificateDer, ServerName};
+        use std::sync::Arc;
+
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls");
+        let mut server = Server::new_tls(
+            "127.0.0.1",
+            0,
+            format!("{}/cert.pem", fixtures),
+            format!("{}/key.pem", fixtures),
+        )
+        .unwrap();
+        server.get("/", |_| HttpResponse::ok().text("secure hello"));
+        assert!(server.describe().features.contains(&"tls"));
+
+        let handle = server.shutdown_handle();
+        let server_thread = thread::spawn(move || server.listen());
+        let addr = wait_for_addr(&handle);
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots
+            .add(CertificateDer::from_pem_file(format!("{}/ca.pem", fixtures)).unwrap())
+            .unwrap();
+        let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        let session = rustls::ClientConnection::new(
+            Arc::new(config),
+            ServerName::try_from("localhost").unwrap(),
+        )
+        .unwrap();
+        let mut stream = rustls::StreamOwned::new(session, TcpStream::connect(addr).unwrap());
+
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut output = String::new();
+        stream.read_to_string(&mut output).unwrap();
+        assert!(output.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(output.ends_with("secure hello"));
+
+        handle.shutdown();
+        assert!(server_thread.join().unwrap().is_ok());
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_tls_handshake_timeout() {
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls");
+        let server = Server::new_tls(
+            "127.0.0.1",
+            0,
+            format!("{}/cert.pem", fixtures),
+            format!("{}/key.pem", fixtures),
+        )
+        .unwrap()
+        .with_tls_handshake_timeout(Duration::from_millis(100));
+
+        let server = Arc::new(server);
+        let handle = server.shutdown_handle();
+        let server_thread = thread::spawn({
+            let server = Arc::clone(&server);
+            move || server.listen()
+        });
+        let addr = wait_for_addr(&handle);
+
+        // Opens a socket and never starts the handshake
+        let started = Instant::now();
+        let mut idle = TcpStream::connect(addr).unwrap();
+        let mut output = Vec::new();
+        let _ = idle.read_to_end(&mut output);
+        assert!(output.is_empty());
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(server.tls_handshake_failures(), 1);
+
+        handle.shutdown();
+        assert!(server_thread.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_oversized_headers_get_431() {
+        let server = Server::new("127.0.0.1", 8080, None)
+            .with_keep_alive(true)
+            .with_request_limits(RequestLimits {
+                max_header_size: 64,
+                ..RequestLimits::default()
+            });
+
+        let output = serve_one(
+            server,
+            b"GET / HTTP/1.1\r\nCookie: aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\r\n\r\n",
+        );
+
+        assert!(output.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+        assert!(output.contains("Connection: close\r\n"));
+    }
+
+    #[test]
+    fn test_describe() {
+        let mut server = Server::new("127.0.0.1", 8080, Some(4))
+            .with_keep_alive(true)
+            .with_read_timeout(Duration::from_secs(5));
+        server.get("/", |_| HttpResponse::ok());
+        server.get("/about", |_| HttpResponse::ok());
+
+        let description = server.describe();
+        assert_eq!(description.bind_addrs, ["127.0.0.1:8080"]);
+        assert_eq!(description.pool_size, 4);
+        assert_eq!(description.read_timeout_ms, Some(5000));
+        assert_eq!(description.features, ["keep-alive"]);
+        assert_eq!(description.route_count, 2);
+        assert!(
+            description
+                .to_string()
+                .contains("bind:      127.0.0.1:8080")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_export_routes_json() {
+        let mut server = Server::new("127.0.0.1", 8080, None);
+        server.group("/api", |api| {
+            api.get("/users/:id", |_| HttpResponse::ok());
+        });
+
+        let exported = RouteTable::from_json(&server.export_routes_json()).unwrap();
+        assert_eq!(exported, server.route_table());
+        assert_eq!(exported.routes[0].path, "/api/users/:id");
+        assert_eq!(exported.routes[0].params, ["id"]);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_conditional_get_with_etag() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        server.get("/todos", |_| {
+            HttpResponse::ok().json_with_etag(vec!["write tests"])
+        });
+
+        let etag = HttpResponse::ok()
+            .json_with_etag(vec!["write tests"])
+            .get_header("ETag")
+            .unwrap()
+            .to_string();
+        let input = format!(
+            "GET /todos HTTP/1.1\r\n\r\nGET /todos HTTP/1.1\r\nIf-None-Match: {}\r\n\r\n",
+            etag
+        );
+
+        let output = serve_one(server, input.as_bytes());
+        assert!(output.contains("HTTP/1.1 200 OK"));
+        assert!(output.contains("HTTP/1.1 304 Not Modified"));
+        assert_eq!(output.matches("[\"write tests\"]").count(), 1);
+    }
+
+    #[test]
+    fn test_flush_immediately_response() {
+        // Hands out chunks as the test sends them, blocking in between
+        struct Chunks(mpsc::Receiver<&'static str>);
+        impl Read for Chunks {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let Ok(chunk) = self.0.recv() else {
+                    return Ok(0);
+                };
+                buf[..chunk.len()].copy_from_slice(chunk.as_bytes());
+                Ok(chunk.len())
+            }
+        }
+
+        let (producer, chunks) = mpsc::channel();
+        let chunks = Mutex::new(Some(Chunks(chunks)));
+        // Large enough that a buffered response would hold back the first chunk
+        let mut server = Server::new("127.0.0.1", 0, Some(1)).with_output_buffer_size(64 * 1024);
+        server.get("/events", move |_| {
+            let chunks = chunks.lock().unwrap().take().unwrap();
+            HttpResponse::ok().stream(chunks).flush_immediately()
+        });
+
+        let server = Arc::new(server);
+        let handle = server.shutdown_handle();
+        let server_thread = thread::spawn({
+            let server = Arc::clone(&server);
+            move || server.listen()
+        });
+        let addr = wait_for_addr(&handle);
+
+        producer.send("first").unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream
+            .write_all(b"GET /events HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+
+        // The producer hasn't sent the second chunk yet, so the first one can
+        // only arrive if it was flushed on its own
+        let mut received = Vec::new();
+        let mut buffer = [0; 1024];
+        while !String::from_utf8_lossy(&received).contains("5\r\nfirst\r\n") {
+            let read = stream
+                .read(&mut buffer)
+                .expect("first chunk was not flushed");
+            assert!(read > 0);
+            received.extend_from_slice(&buffer[..read]);
+        }
+        assert!(!String::from_utf8_lossy(&received).contains("second"));
+
+        producer.send("second").unwrap();
+        drop(producer);
+        stream.read_to_end(&mut received).unwrap();
+        let output = String::from_utf8(received).unwrap();
+        assert!(output.starts_with("HTTP/1.1 200 OK"));
+        assert!(output.ends_with("5\r\nfirst\r\n6\r\nsecond\r\n0\r\n\r\n"));
+
+        handle.shutdown();
+        assert!(server_thread.join().unwrap().is_ok());
+    }
+
+    fn wait_for_addr(handle: &ShutdownHandle) -> std::net::SocketAddr {
+        loop {
+            if let Some(addr) = handle.local_addr() {
+                return addr;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_full_accept_queue_answers_503() {
+        let (entered, handler_entered) = mpsc::channel();
+        let (release, handler_released) = mpsc::channel::<()>();
+        let (entered, handler_released) = (Mutex::new(entered), Mutex::new(handler_released));
+        let mut server = Server::new("127.0.0.1", 0, Some(1)).with_accept_queue(1);
+        server.get("/slow", move |_| {
+            entered.lock().unwrap().send(()).unwrap();
+            handler_released.lock().unwrap().recv().unwrap();
+            "done"
+        });
+
+        let server = Arc::new(server);
+        let handle = server.shutdown_handle();
+        let server_thread = thread::spawn({
+            let server = Arc::clone(&server);
+            move || server.listen()
+        });
+        let addr = wait_for_addr(&handle);
+
+        let request = |stream: &mut TcpStream| {
+            stream
+                .write_all(b"GET /slow HTTP/1.1\r\nConnection: close\r\n\r\n")
+                .unwrap();
+        };
+        let read = |mut stream: TcpStream| {
+            let mut output = String::new();
+            stream.read_to_string(&mut output).unwrap();
+            output
+        };
+
+        let mut busy = TcpStream::connect(addr).unwrap();
+        request(&mut busy);
+        handler_entered.recv().unwrap();
+
+        let mut queued = TcpStream::connect(addr).unwrap();
+        request(&mut queued);
+        while server.queued_connections() == 0 {
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        let rejected = TcpStream::connect(addr).unwrap();
+        let output = read(rejected);
+        assert!(output.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(output.contains("Retry-After: 1\r\n"));
+        assert_eq!(server.rejected_connections(), 1);
+
+        release.send(()).unwrap();
+        assert!(read(busy).ends_with("done"));
+        handler_entered.recv().unwrap();
+        release.send(()).unwrap();
+        assert!(read(queued).ends_with("done"));
+
+        handle.shutdown();
+        assert!(server_thread.join().unwrap().is_ok());
+        assert_eq!(server.queued_connections(), 0);
+    }
+
+    #[test]
+    fn test_stale_queued_connection_answers_503() {
+        let (entered, handler_entered) = mpsc::channel();
+        let (release, handler_released) = mpsc::channel::<()>();
+        let (entered, handler_released) = (Mutex::new(entered), Mutex::new(handler_released));
+        let mut server =
+            Server::new("127.0.0.1", 0, Some(1)).with_queue_timeout(Duration::from_millis(50));
+        server.get("/slow", move |_| {
+            entered.lock().unwrap().send(()).unwrap();
+            handler_released.lock().unwrap().recv().unwrap();
+            "done"
+        });
+
+        let server = Arc::new(server);
+        let handle = server.shutdown_handle();
+        let server_thread = thread::spawn({
+            let server = Arc::clone(&server);
+            move || server.listen()
+        });
+        let addr = wait_for_addr(&handle);
+
+        let request = |stream: &mut TcpStream| {
+            stream
+                .write_all(b"GET /slow HTTP/1.1\r\nConnection: close\r\n\r\n")
+                .unwrap();
+        };
+        let mut busy = TcpStream::connect(addr).unwrap();
+        request(&mut busy);
+        handler_entered.recv().unwrap();
+
+        let mut stale = TcpStream::connect(addr).unwrap();
+        request(&mut stale);
+        while server.queued_connections() == 0 {
+            thread::sleep(Duration::from_millis(5));
+        }
+        thread::sleep(Duration::from_millis(100));
+        release.send(()).unwrap();
+
+        let mut output = String::new();
+        stale.read_to_string(&mut output).unwrap();
+        assert!(output.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert_eq!(server.rejected_connections(), 1);
+        assert!(handler_entered.try_recv().is_err());
+
+        let mut output = String::new();
+        busy.read_to_string(&mut output).unwrap();
+        assert!(output.ends_with("done"));
+
+        handle.shutdown();
+        assert!(server_thread.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_lifecycle_hooks() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let started = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let mut server = Server::new("127.0.0.1", 0, Some(1));
+        server.get("/", |_| HttpResponse::ok().text("hello"));
+        let flag = Arc::clone(&started);
+        server.on_startup(move || {
+            flag.store(true, Ordering::SeqCst);
+            Ok(())
+        });
+        let flag = Arc::clone(&stopped);
+        server.on_shutdown(move || flag.store(true, Ordering::SeqCst));
+
+        let handle = server.shutdown_handle();
+        let server_thread = thread::spawn(move || server.listen());
+        let addr = wait_for_addr(&handle);
+        assert!(started.load(Ordering::SeqCst));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut output = String::new();
+        stream.read_to_string(&mut output).unwrap();
+        assert!(output.ends_with("hello"));
+        assert!(!stopped.load(Ordering::SeqCst));
+
+        handle.shutdown();
+        assert!(server_thread.join().unwrap().is_ok());
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_shutdown_before_listen() {
+        let server = Server::new("127.0.0.1", 0, Some(1));
+        let handle = server.shutdown_handle();
+        handle.shutdown();
+
+        let (done, listen_returned) = mpsc::channel();
+        thread::spawn(move || done.send(server.listen().is_ok()).unwrap());
+        assert_eq!(
+            listen_returned.recv_timeout(Duration::from_secs(5)),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_failing_startup_hook_aborts_listen() {
+        let mut server = Server::new("127.0.0.1", 0, Some(1));
+        server.on_startup(|| Err("migrations pending".into()));
+
+        match server.listen() {
+            Err(ServerError::StartupError(e)) => assert_eq!(e.to_string(), "migrations pending"),
+            other => panic!("Expected StartupError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_keep_alive_connections_drain_on_shutdown() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        let handle = server.shutdown_handle();
+        server.get("/a", |_| HttpResponse::ok());
+        server.post("/stop", move |_| {
+            handle.shutdown();
+            HttpResponse::ok()
+        });
+
+        let output = serve_one(
+            server,
+            b"GET /a HTTP/1.1\r\n\r\nPOST /stop HTTP/1.1\r\n\r\nGET /a HTTP/1.1\r\n\r\n",
+        );
+
+        assert_eq!(output.matches("HTTP/1.1 200 OK").count(), 2);
+        assert_eq!(output.matches("Connection: close\r\n").count(), 1);
+        let last = output.rsplit("HTTP/1.1 ").next().unwrap();
+        assert!(last.contains("Connection: close\r\n"));
+    }
+
+    #[test]
+    fn test_shutdown_closes_idle_keep_alive_connections() {
+        let mut server = Server::new("127.0.0.1", 0, Some(1))
+            .with_keep_alive(true)
+            .with_read_timeout(Duration::from_secs(30));
+        server.get("/a", |_| "a");
+
+        let server = Arc::new(server);
+        let handle = server.shutdown_handle();
+        let server_thread = thread::spawn({
+            let server = Arc::clone(&server);
+            move || server.listen()
+        });
+        let addr = wait_for_addr(&handle);
+
+        let mut idle = TcpStream::connect(addr).unwrap();
+        idle.write_all(b"GET /a HTTP/1.1\r\n\r\n").unwrap();
+        let mut buffer = [0; 1024];
+        let read = idle.read(&mut buffer).unwrap();
+        assert!(String::from_utf8_lossy(&buffer[..read]).contains("keep-alive"));
+
+        let started = Instant::now();
+        handle.shutdown();
+        assert!(server_thread.join().unwrap().is_ok());
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(idle.read(&mut buffer).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_connection_closed_without_keep_alive() {
+        let mut server = Server::new("127.0.0.1", 8080, None);
+        server.get("/a", |_| HttpResponse::ok());
+
+        let output = serve_one(server, b"GET /a HTTP/1.1\r\n\r\nGET /a HTTP/1.1\r\n\r\n");
+
+        assert_eq!(output.matches("HTTP/1.1 ").count(), 1);
+        assert!(output.contains("Connection: close\r\n"));
+    }
+
+    #[test]
+    fn test_http_1_0_client_is_answered_in_kind() {
+        let mut server = Server::new("127.0.0.1", 8080, None).with_keep_alive(true);
+        server.get("/a", |_| HttpResponse::ok().text("a"));
+
+        let output = serve_one(server, b"GET /a HTTP/1.0\r\n\r\nGET /a HTTP/1.0\r\n\r\n");
+
+        assert!(output.starts_with("HTTP/1.0 200 OK\r\n"));
+        assert_eq!(output.matches("HTTP/1.0 ").count(), 1);
+        assert!(output.contains("Connection: close\r\n"));
+    }
+}
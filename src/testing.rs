@@ -0,0 +1,120 @@
+/* Deterministic time for tests of time-dependent components */
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Source of the current time for components with cool-downs, expiries and
+/// TTLs. The default reads the system clock; a manual clock stands still until
+/// `advance` is called, so tests can step past a deadline instead of sleeping.
+///
+/// Clones share the same time, so keep one handle in the test (or in server
+/// state) and pass clones to the components under test:
+///
+/// ```
+/// # use std::time::Duration;
+/// # use schnell::keyring::Keyring;
+/// # use schnell::testing::Clock;
+/// # use schnell::tokens::{TokenError, Tokens};
+/// # let keyring = Keyring::new("secret");
+/// let clock = Clock::manual();
+/// let tokens = Tokens::new(keyring, "verify-email").with_clock(clock.clone());
+/// let token = tokens.issue("alice", Duration::from_secs(3600));
+/// clock.advance(Duration::from_secs(3600));
+/// assert_eq!(tokens.verify(&token), Err(TokenError::Expired));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Clock {
+    manual: Option<Arc<ManualTime>>,
+}
+
+#[derive(Debug)]
+struct ManualTime {
+    instant: Instant,
+    system: SystemTime,
+    elapsed: Mutex<Duration>,
+}
+
+impl Clock {
+    pub fn system() -> Self {
+        Self::default()
+    }
+
+    /// A clock frozen at the current time.
+    pub fn manual() -> Self {
+        Self::manual_at(SystemTime::now())
+    }
+
+    /// A clock frozen at `start` wall-clock time.
+    pub fn manual_at(start: SystemTime) -> Self {
+        Self {
+            manual: Some(Arc::new(ManualTime {
+                instant: Instant::now(),
+                system: start,
+                elapsed: Mutex::new(Duration::ZERO),
+            })),
+        }
+    }
+
+    pub fn is_manual(&self) -> bool {
+        self.manual.is_some()
+    }
+
+    /// Moves a manual clock forward. Has no effect on the system clock.
+    pub fn advance(&self, by: Duration) {
+        if let Some(manual) = &self.manual {
+            *manual.elapsed.lock().unwrap() += by;
+        }
+    }
+
+    /// Monotonic time, for measuring durations.
+    pub fn now(&self) -> Instant {
+        match &self.manual {
+            Some(manual) => manual.instant + *manual.elapsed.lock().unwrap(),
+            None => Instant::now(),
+        }
+    }
+
+    /// Wall-clock time, for expiries shared with clients.
+    pub fn system_time(&self) -> SystemTime {
+        match &self.manual {
+            Some(manual) => manual.system + *manual.elapsed.lock().unwrap(),
+            None => SystemTime::now(),
+        }
+    }
+
+    /// Whole seconds since the Unix epoch.
+    pub fn unix_secs(&self) -> u64 {
+        self.system_time()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock_advances() {
+        let clock = Clock::manual_at(UNIX_EPOCH + Duration::from_secs(1_000));
+        let shared = clock.clone();
+        let start = clock.now();
+
+        assert_eq!(clock.unix_secs(), 1_000);
+        assert_eq!(clock.now(), start);
+
+        shared.advance(Duration::from_secs(90));
+        assert_eq!(clock.now() - start, Duration::from_secs(90));
+        assert_eq!(clock.unix_secs(), 1_090);
+    }
+
+    #[test]
+    fn test_system_clock_ignores_advance() {
+        let clock = Clock::system();
+        let before = clock.now();
+        clock.advance(Duration::from_secs(3600));
+
+        assert!(!clock.is_manual());
+        assert!(clock.now() - before < Duration::from_secs(3600));
+    }
+}
@@ -0,0 +1,181 @@
+/* Signed, expiring tokens for email verification and password reset links */
+use std::time::Duration;
+
+use crate::keyring::Keyring;
+use crate::testing::Clock;
+use crate::utils::{base64url_decode, base64url_encode};
+
+#[derive(Debug, PartialEq)]
+pub enum TokenError {
+    Malformed,
+    InvalidSignature,
+    Expired,
+}
+
+impl std::fmt::Display for TokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TokenError::Malformed => "Malformed token",
+            TokenError::InvalidSignature => "Invalid token signature",
+            TokenError::Expired => "Token expired",
+        })
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+/// Issues and verifies URL-safe tokens binding a subject (user id, email
+/// address, ...) to a purpose and an expiry. The purpose is part of the
+/// signature, so a token issued for one purpose is rejected for any other.
+///
+/// Tokens are stateless: one stays valid until it expires. Include something
+/// that changes once the token is used (e.g. a password hash fingerprint) in the
+/// subject to make it single-use.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use schnell::keyring::Keyring;
+/// # use schnell::tokens::{TokenError, Tokens};
+/// # fn main() -> Result<(), TokenError> {
+/// # let keyring = Keyring::new("secret");
+/// let resets = Tokens::new(keyring.clone(), "password-reset");
+/// let token = resets.issue("user-42", Duration::from_secs(3600));
+/// let link = format!("https://example.com/reset?token={}", token);
+/// // later, with the token from the link
+/// let user_id = resets.verify(&token)?;
+/// # assert_eq!(user_id, "user-42");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tokens {
+    keyring: Keyring,
+    purpose: String,
+    clock: Clock,
+}
+
+impl Tokens {
+    pub fn new(keyring: Keyring, purpose: &str) -> Self {
+        Self {
+            keyring,
+            purpose: purpose.to_string(),
+            clock: Clock::system(),
+        }
+    }
+
+    /// Reads the time from `clock` instead of the system clock.
+    pub fn with_clock(self, clock: Clock) -> Self {
+        let mut tokens = self;
+        tokens.clock = clock;
+        tokens
+    }
+
+    /// A token for `subject` that expires after `ttl`.
+    pub fn issue(&self, subject: &str, ttl: Duration) -> String {
+        let expires = self.clock.unix_secs().saturating_add(ttl.as_secs());
+        let signature = self.keyring.sign(&self.payload(subject, expires));
+        format!(
+            "{}.{}.{}",
+            base64url_encode(subject.as_bytes()),
+            expires,
+            base64url_encode(&signature)
+        )
+    }
+
+    /// The subject `token` was issued for, if it is intact, meant for this
+    /// purpose and not yet expired.
+    pub fn verify(&self, token: &str) -> Result<String, TokenError> {
+        let mut fields = token.split('.');
+        let (Some(subject), Some(expires), Some(signature), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(TokenError::Malformed);
+        };
+
+        let subject = base64url_decode(subject)
+            .and_then(|subject| String::from_utf8(subject).ok())
+            .ok_or(TokenError::Malformed)?;
+        let expires: u64 = expires.parse().map_err(|_| TokenError::Malformed)?;
+        let signature = base64url_decode(signature).ok_or(TokenError::Malformed)?;
+
+        if !self
+            .keyring
+            .verify(&self.payload(&subject, expires), &signature)
+        {
+            return Err(TokenError::InvalidSignature);
+        }
+        if self.clock.unix_secs() >= expires {
+            return Err(TokenError::Expired);
+        }
+        Ok(subject)
+    }
+
+    fn payload(&self, subject: &str, expires: u64) -> Vec<u8> {
+        format!("{}\0{}\0{}", self.purpose, subject, expires).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    fn tokens(purpose: &str) -> Tokens {
+        Tokens::new(Keyring::new("s3cr3t"), purpose).with_clock(Clock::manual())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let verify = tokens("verify-email");
+        let token = verify.issue("alice@example.com", HOUR);
+
+        assert!(
+            token
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        );
+        assert_eq!(verify.verify(&token).unwrap(), "alice@example.com");
+    }
+
+    #[test]
+    fn test_rejected_tokens() {
+        let verify = tokens("verify-email");
+        let token = verify.issue("alice", HOUR);
+
+        assert_eq!(
+            tokens("password-reset").verify(&token),
+            Err(TokenError::InvalidSignature)
+        );
+        assert_eq!(
+            verify.verify(&token.replacen("YWxpY2U", "bWFsbG9y", 1)),
+            Err(TokenError::InvalidSignature)
+        );
+        assert_eq!(verify.verify("not-a-token"), Err(TokenError::Malformed));
+    }
+
+    #[test]
+    fn test_expired_tokens() {
+        let clock = Clock::manual();
+        let verify = tokens("verify-email").with_clock(clock.clone());
+        let token = verify.issue("alice", HOUR);
+
+        clock.advance(HOUR - Duration::from_secs(1));
+        assert_eq!(verify.verify(&token).unwrap(), "alice");
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(verify.verify(&token), Err(TokenError::Expired));
+
+        let forever = verify.issue("alice", Duration::MAX);
+        assert_eq!(verify.verify(&forever).unwrap(), "alice");
+    }
+
+    #[test]
+    fn test_rotated_keys() {
+        let token = tokens("verify-email").issue("alice", HOUR);
+        let rotated = Tokens::new(
+            Keyring::new("n3w").with_retired_key("s3cr3t"),
+            "verify-email",
+        );
+        assert_eq!(rotated.verify(&token).unwrap(), "alice");
+    }
+}
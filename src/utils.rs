@@ -1,6 +1,6 @@
 /* Utility functions */
-use regex::Regex;
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn get_status_text(code: u16) -> &'static str {
     match code {
@@ -82,16 +82,58 @@ pub fn to_title_case(s: &str) -> String {
 }
 
 pub fn sanitize_header_key(key: &str) -> String {
-    let re = Regex::new(r"[^a-zA-Z0-9]+").unwrap();
-
-    let result = re.replace_all(key, "-").to_string();
-    result
-        .split("-")
-        .map(|s| to_title_case(s))
-        .collect::<Vec<String>>()
-        .join("-")
-        .trim_end_matches("-")
-        .to_string()
+    // Each run of characters other than ASCII letters and digits becomes one '-'
+    let mut sanitized = String::with_capacity(key.len());
+    let mut word_start = true;
+    for c in key.chars() {
+        if c.is_ascii_alphanumeric() {
+            sanitized.push(match word_start {
+                true => c.to_ascii_uppercase(),
+                false => c.to_ascii_lowercase(),
+            });
+            word_start = false;
+        } else if !word_start || sanitized.is_empty() {
+            sanitized.push('-');
+            word_start = true;
+        }
+    }
+    sanitized.trim_end_matches('-').to_string()
+}
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// `time` as an IMF-fixdate (`Sun, 06 Nov 1994 08:49:37 GMT`), the format of
+/// `Date`, `Expires` and `Last-Modified`. Times before 1970 are clamped to it.
+pub fn http_date(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since the epoch, with years starting in March so
+    // the leap day comes last (Howard Hinnant's `civil_from_days`)
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = (shifted_month + 2) % 12;
+    let year = era * 400 + year_of_era + u64::from(month < 2);
+
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[(days % 7) as usize],
+        day,
+        MONTHS[month as usize],
+        year,
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
 }
 
 pub fn join_path<'a>(prefix: &'a str, path: &'a str) -> String {
@@ -101,6 +143,161 @@ pub fn join_path<'a>(prefix: &'a str, path: &'a str) -> String {
         .to_string()
 }
 
+/// Splits a `Host` header value into host and port. Bracketed IPv6 literals
+/// (`[::1]:8080`, `[fe80::1%25eth0]`) come back without their brackets; a bare
+/// IPv6 address is taken to have no port. Returns `None` for malformed values.
+pub fn split_host_port(value: &str) -> Option<(&str, Option<u16>)> {
+    let value = value.trim();
+
+    if let Some(rest) = value.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']')?;
+        return match rest {
+            "" => Some((host, None)),
+            _ => Some((host, Some(rest.strip_prefix(':')?.parse().ok()?))),
+        };
+    }
+
+    match value.split_once(':') {
+        Some((host, port)) if !port.contains(':') => Some((host, Some(port.parse().ok()?))),
+        Some(_) => Some((value, None)),
+        None if value.is_empty() => None,
+        None => Some((value, None)),
+    }
+}
+
+/// Decodes `%XX` escapes in a request path. `%2F` is left encoded so an escaped
+/// slash can't introduce an extra path segment. Malformed escapes are kept
+/// as-is and invalid UTF-8 is replaced.
+pub fn percent_decode_path(input: &str) -> String {
+    String::from_utf8_lossy(&percent_decode_bytes(input, false, b"/")).into_owned()
+}
+
+/// Decodes a query string key or value: `%XX` escapes, and `+` as a space.
+pub fn percent_decode_query(input: &str) -> String {
+    String::from_utf8_lossy(&percent_decode_bytes(input, true, b"")).into_owned()
+}
+
+/// Raw bytes behind `%XX` escapes, leaving bytes in `keep_encoded` and
+/// malformed escapes encoded.
+pub(crate) fn percent_decode_bytes(
+    input: &str,
+    plus_as_space: bool,
+    keep_encoded: &[u8],
+) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let escape = input
+                    .get(i + 1..i + 3)
+                    // `from_str_radix` would also take `%+5` as a signed 5
+                    .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match escape {
+                    Some(byte) if !keep_encoded.contains(&byte) => {
+                        decoded.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            b'+' if plus_as_space => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    decoded
+}
+
+/// Percent-encodes everything but unreserved characters (`A-Z a-z 0-9 - . _ ~`),
+/// so `input` can be placed in a path segment or query string of a URL.
+pub fn percent_encode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Percent-encodes the bytes that may not appear in a URL (controls including
+/// CR/LF, space, non-ASCII and ``"<>\^`{|}``), leaving reserved characters and
+/// existing escapes alone, so a URL assembled from user input is safe to put
+/// in a header.
+pub fn encode_uri(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'"' | b'<' | b'>' | b'\\' | b'^' | b'`' | b'{' | b'|' | b'}' => {
+                encoded.push_str(&format!("%{:02X}", byte))
+            }
+            b'!'..=b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+const BASE64URL_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Unpadded URL-safe base64, as used in tokens and signed cookie values.
+pub fn base64url_encode(input: &[u8]) -> String {
+    let mut encoded = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, b)| acc | (*b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            encoded.push(BASE64URL_ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    encoded
+}
+
+/// Decodes unpadded URL-safe base64; `None` if `input` isn't valid.
+pub fn base64url_decode(input: &str) -> Option<Vec<u8>> {
+    decode_base64(input, BASE64URL_ALPHABET)
+}
+
+/// Decodes standard base64 (`+` and `/`), padded or not, as in `Authorization:
+/// Basic` credentials; `None` if `input` isn't valid.
+pub(crate) fn base64_decode(input: &str) -> Option<Vec<u8>> {
+    let unpadded = input.trim_end_matches('=');
+    if input.len() - unpadded.len() > 2 {
+        return None;
+    }
+    decode_base64(unpadded, BASE64_ALPHABET)
+}
+
+fn decode_base64(input: &str, alphabet: &[u8; 64]) -> Option<Vec<u8>> {
+    if input.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut decoded = Vec::with_capacity(input.len() * 3 / 4);
+    for chunk in input.as_bytes().chunks(4) {
+        let mut bits = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = alphabet.iter().position(|a| a == c)? as u32;
+            bits |= value << (18 - 6 * i);
+        }
+        decoded.extend_from_slice(&bits.to_be_bytes()[1..chunk.len()]);
+    }
+    Some(decoded)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,6 +314,15 @@ mod tests {
         assert_eq!(sanitize_header_key("access $^&^&#$& TOKEN"), "Access-Token");
     }
 
+    #[test]
+    fn test_http_date() {
+        let at = |secs| http_date(UNIX_EPOCH + std::time::Duration::from_secs(secs));
+        assert_eq!(at(0), "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(at(784_111_777), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(at(951_868_799), "Tue, 29 Feb 2000 23:59:59 GMT");
+        assert_eq!(at(1_893_553_445), "Wed, 02 Jan 2030 03:04:05 GMT");
+    }
+
     #[test]
     fn test_join_path() {
         assert_eq!(join_path("/api", "/v1/users"), "/api/v1/users");
@@ -128,4 +334,87 @@ mod tests {
         assert_eq!(join_path("/api", "v1/users/"), "/api/v1/users/"); // path with trailing slash
         assert_eq!(join_path("api", "v1/users/"), "api/v1/users/");
     }
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(split_host_port("example.com"), Some(("example.com", None)));
+        assert_eq!(
+            split_host_port("example.com:8080"),
+            Some(("example.com", Some(8080)))
+        );
+        assert_eq!(split_host_port("[::1]:8080"), Some(("::1", Some(8080))));
+        assert_eq!(split_host_port("[::1]"), Some(("::1", None)));
+        assert_eq!(
+            split_host_port("[fe80::1%25eth0]:80"),
+            Some(("fe80::1%25eth0", Some(80)))
+        );
+        assert_eq!(split_host_port("::1"), Some(("::1", None)));
+        assert_eq!(split_host_port("[::1]x"), None);
+        assert_eq!(split_host_port("example.com:http"), None);
+        assert_eq!(split_host_port(""), None);
+    }
+
+    #[test]
+    fn test_percent_decoding() {
+        assert_eq!(percent_decode_query("John%20Doe"), "John Doe");
+        assert_eq!(percent_decode_query("John+Doe"), "John Doe");
+        assert_eq!(percent_decode_query("a%2Bb%3D%26"), "a+b=&");
+        assert_eq!(percent_decode_query("caf%C3%A9"), "café");
+        assert_eq!(percent_decode_query("100%"), "100%");
+        assert_eq!(percent_decode_path("%+5%-5"), "%+5%-5");
+        assert_eq!(percent_decode_query("%zz%4"), "%zz%4");
+        assert_eq!(percent_decode_query("%FF"), "\u{FFFD}");
+
+        assert_eq!(
+            percent_decode_path("/files/my%20file+1"),
+            "/files/my file+1"
+        );
+        assert_eq!(percent_decode_path("/files/a%2Fb%2fc"), "/files/a%2Fb%2fc");
+    }
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(percent_encode("John Doe"), "John%20Doe");
+        assert_eq!(percent_encode("a/b?c=d&e"), "a%2Fb%3Fc%3Dd%26e");
+        assert_eq!(percent_encode("café-1.0_~"), "caf%C3%A9-1.0_~");
+        assert_eq!(
+            percent_decode_query(&percent_encode("50% + \"x\"")),
+            "50% + \"x\""
+        );
+    }
+
+    #[test]
+    fn test_encode_uri() {
+        assert_eq!(
+            encode_uri("/search?q=a%20b&tag=x#top"),
+            "/search?q=a%20b&tag=x#top"
+        );
+        assert_eq!(
+            encode_uri("/a\r\nSet-Cookie: x=1"),
+            "/a%0D%0ASet-Cookie:%20x=1"
+        );
+        assert_eq!(encode_uri("/café/\\<b>"), "/caf%C3%A9/%5C%3Cb%3E");
+    }
+
+    #[test]
+    fn test_base64url() {
+        for (raw, encoded) in [
+            (&b""[..], ""),
+            (b"f", "Zg"),
+            (b"fo", "Zm8"),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg"),
+            (b"\xfb\xff\xbf", "-_-_"),
+        ] {
+            assert_eq!(base64url_encode(raw), encoded);
+            assert_eq!(base64url_decode(encoded).as_deref(), Some(raw));
+        }
+        assert_eq!(base64url_decode("Zm9vY"), None);
+        assert_eq!(base64url_decode("Zm9v+g"), None);
+
+        assert_eq!(base64_decode("+/+/").as_deref(), Some(&b"\xfb\xff\xbf"[..]));
+        assert_eq!(base64_decode("Zm8=").as_deref(), Some(&b"fo"[..]));
+        assert_eq!(base64_decode("-_-_"), None);
+        assert_eq!(base64_decode("Zg==="), None);
+    }
 }
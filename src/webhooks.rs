@@ -0,0 +1,207 @@
+/* Helpers for receiving signed webhooks (GitHub, Stripe, ...) */
+use std::time::Duration;
+
+use crate::http::Request;
+use crate::keyring::Keyring;
+use crate::testing::Clock;
+
+/// Hex-encoded HMAC-SHA256 of `payload`.
+pub fn sign_hmac_sha256(secret: &[u8], payload: &[u8]) -> String {
+    encode_hex(&Keyring::new(secret).sign(payload))
+}
+
+/// Checks a hex-encoded HMAC-SHA256 `signature` of `payload` in constant time.
+/// A leading `sha256=` (as sent by GitHub) is ignored.
+pub fn verify_signature(secret: &[u8], payload: &[u8], signature: &str) -> bool {
+    let signature = signature.trim();
+    let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+
+    decode_hex(signature).is_some_and(|expected| Keyring::new(secret).verify(payload, &expected))
+}
+
+/// Verifies the signature carried in `header` against the request's raw body,
+/// e.g. `verify_hmac_sha256(&req, secret, "X-Hub-Signature-256")`.
+pub fn verify_hmac_sha256(request: &Request, secret: &[u8], header: &str) -> bool {
+    match request.headers.get(&header.to_lowercase()) {
+        Some(signature) => verify_signature(secret, request.raw_body(), signature),
+        None => false,
+    }
+}
+
+/// A `Stripe-Signature` style header value, `t=<unix time>,v1=<hex HMAC>`,
+/// signing `<unix time>.<payload>` with the primary key of `keyring`.
+pub fn sign_timestamped(keyring: &Keyring, payload: &[u8], timestamp: u64) -> String {
+    let signature = keyring.sign(&timestamped_payload(timestamp, payload));
+    format!("t={},v1={}", timestamp, encode_hex(&signature))
+}
+
+/// Checks a `t=...,v1=...` signature header as sent by Stripe. Any `v1`
+/// entry may match any key in `keyring`, so secrets can be rolled on either
+/// side. The timestamp must be within `tolerance` of `clock`, so a captured
+/// request can't be replayed later.
+pub fn verify_timestamped_signature(
+    keyring: &Keyring,
+    payload: &[u8],
+    header: &str,
+    tolerance: Duration,
+    clock: &Clock,
+) -> bool {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for (key, value) in header
+        .split(',')
+        .filter_map(|item| item.trim().split_once('='))
+    {
+        match key {
+            "t" => timestamp = value.parse::<u64>().ok(),
+            "v1" => signatures.extend(decode_hex(value)),
+            _ => {}
+        }
+    }
+
+    let Some(timestamp) = timestamp else {
+        return false;
+    };
+    if clock.unix_secs().abs_diff(timestamp) > tolerance.as_secs() {
+        return false;
+    }
+    let signed = timestamped_payload(timestamp, payload);
+    signatures
+        .iter()
+        .any(|signature| keyring.verify(&signed, signature))
+}
+
+/// Verifies the `Stripe-Signature` header against the request's raw body,
+/// e.g. `verify_stripe_signature(&req, &Keyring::new(secret), Duration::from_secs(300))`.
+pub fn verify_stripe_signature(request: &Request, keyring: &Keyring, tolerance: Duration) -> bool {
+    match request.headers.get("stripe-signature") {
+        Some(header) => verify_timestamped_signature(
+            keyring,
+            request.raw_body(),
+            header,
+            tolerance,
+            &Clock::system(),
+        ),
+        None => false,
+    }
+}
+
+fn timestamped_payload(timestamp: u64, payload: &[u8]) -> Vec<u8> {
+    let mut signed = format!("{}.", timestamp).into_bytes();
+    signed.extend_from_slice(payload);
+    signed
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    // `from_str_radix` would also take `+a` as 0x0a
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+    use std::time::UNIX_EPOCH;
+
+    const SECRET: &[u8] = b"It's a Secret to Everybody";
+    const PAYLOAD: &str = "Hello, World!";
+    // Test vector from GitHub's webhook documentation
+    const SIGNATURE: &str = "757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+    fn webhook(signature: &str) -> Request {
+        let request_data = format!(
+            "POST /webhook HTTP/1.1\r\nX-Hub-Signature-256: {}\r\nContent-Length: {}\r\n\r\n{}",
+            signature,
+            PAYLOAD.len(),
+            PAYLOAD
+        );
+        let mut buffer = BufReader::new(request_data.as_bytes());
+        Request::read(&mut buffer).unwrap()
+    }
+
+    #[test]
+    fn test_sign_hmac_sha256() {
+        assert_eq!(sign_hmac_sha256(SECRET, PAYLOAD.as_bytes()), SIGNATURE);
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        assert!(verify_signature(SECRET, PAYLOAD.as_bytes(), SIGNATURE));
+        assert!(verify_signature(
+            SECRET,
+            PAYLOAD.as_bytes(),
+            &format!("sha256={}", SIGNATURE)
+        ));
+        assert!(!verify_signature(
+            b"wrong secret",
+            PAYLOAD.as_bytes(),
+            SIGNATURE
+        ));
+        assert!(!verify_signature(SECRET, b"Hello, World?", SIGNATURE));
+        assert!(!verify_signature(SECRET, PAYLOAD.as_bytes(), "not hex"));
+        assert!(!verify_signature(SECRET, PAYLOAD.as_bytes(), "abc"));
+        assert_eq!(decode_hex("0a"), Some(vec![0x0a]));
+        assert_eq!(decode_hex("+a"), None);
+    }
+
+    #[test]
+    fn test_verify_hmac_sha256() {
+        let request = webhook(&format!("sha256={}", SIGNATURE));
+        assert_eq!(request.raw_body(), PAYLOAD.as_bytes());
+        assert!(verify_hmac_sha256(&request, SECRET, "X-Hub-Signature-256"));
+        assert!(!verify_hmac_sha256(&request, SECRET, "X-Missing-Signature"));
+
+        let request = webhook("sha256=deadbeef");
+        assert!(!verify_hmac_sha256(&request, SECRET, "X-Hub-Signature-256"));
+    }
+
+    #[test]
+    fn test_timestamped_signatures() {
+        let keyring = Keyring::new("whsec_test");
+        let payload = br#"{"id":"evt_1"}"#;
+        let clock = Clock::manual_at(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        let tolerance = Duration::from_secs(300);
+
+        let header = sign_timestamped(&keyring, payload, 1_700_000_000);
+        assert_eq!(
+            header,
+            "t=1700000000,v1=c89214b5b5da833daed6f0b8c5bb6bd58cea9022bd80ccc78230f3942d632925"
+        );
+        assert!(verify_timestamped_signature(
+            &keyring, payload, &header, tolerance, &clock
+        ));
+
+        // Other schemes and stale secrets may be listed alongside
+        let rolled = format!("t=1700000000,v1=deadbeef,{},v0=abc", &header[13..]);
+        assert!(verify_timestamped_signature(
+            &keyring, payload, &rolled, tolerance, &clock
+        ));
+
+        assert!(!verify_timestamped_signature(
+            &keyring, b"{}", &header, tolerance, &clock
+        ));
+        let resigned = header.replacen("t=1700000000", "t=1700000001", 1);
+        assert!(!verify_timestamped_signature(
+            &keyring, payload, &resigned, tolerance, &clock
+        ));
+        assert!(!verify_timestamped_signature(
+            &keyring, payload, "v1=c892", tolerance, &clock
+        ));
+
+        clock.advance(Duration::from_secs(301));
+        assert!(!verify_timestamped_signature(
+            &keyring, payload, &header, tolerance, &clock
+        ));
+    }
+}
@@ -1,195 +1,650 @@
-// use schnell::common::{HttpMethod, Version};
-// use schnell::request::{Request, RequestError};
-// use std::io::BufReader;
-
-// #[test]
-// fn test_from_stream_get_request() {
-//     let request_data =
-//         "GET /index.html HTTP/1.1\r\nHost: localhost:8080\r\nUser-Agent: test-client/1.0\r\n\r\n";
-//     let buffer = BufReader::new(request_data.as_bytes());
-
-//     let result = Request::read(buffer);
-//     assert!(result.is_ok());
-
-//     let request = result.unwrap();
-//     assert_eq!(request.method, HttpMethod::GET);
-//     assert_eq!(request.path, "/index.html");
-//     assert_eq!(request.version, Version::HTTP1_1);
-//     assert_eq!(
-//         request.headers.get("host"),
-//         Some(&"localhost:8080".to_string())
-//     );
-//     assert_eq!(
-//         request.headers.get("user-agent"),
-//         Some(&"test-client/1.0".to_string())
-//     );
-//     assert_eq!(request.body, "");
-// }
-
-// #[test]
-// fn test_from_stream_post_request_with_body() {
-//     let body = "{\"name\": \"John Doe\"}";
-//     let content_length = body.len();
-//     let request_data = format!(
-//         "POST /api/users HTTP/1.1\r\nHost: localhost:8080\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
-//         content_length, body
-//     );
-//     let buffer = BufReader::new(request_data.as_bytes());
-
-//     let result = Request::read(buffer);
-//     assert!(result.is_ok());
-
-//     let request = result.unwrap();
-//     assert_eq!(request.method, HttpMethod::POST);
-//     assert_eq!(request.path, "/api/users");
-//     assert_eq!(request.version, Version::HTTP1_1);
-//     assert_eq!(
-//         request.headers.get("host"),
-//         Some(&"localhost:8080".to_string())
-//     );
-//     assert_eq!(
-//         request.headers.get("content-type"),
-//         Some(&"application/json".to_string())
-//     );
-//     assert_eq!(
-//         request.headers.get("content-length"),
-//         Some(&content_length.to_string())
-//     );
-//     assert_eq!(request.body, body);
-// }
-
-// #[test]
-// fn test_from_stream_multiple_headers() {
-//     let request_data = "GET /test HTTP/1.1\r\nHost: example.com\r\nUser-Agent: Mozilla/5.0\r\nAccept: text/html\r\nAccept-Language: en-US\r\n\r\n";
-//     let buffer = BufReader::new(request_data.as_bytes());
-
-//     let result = Request::read(buffer);
-//     assert!(result.is_ok());
-
-//     let request = result.unwrap();
-//     assert_eq!(request.method, HttpMethod::GET);
-//     assert_eq!(request.path, "/test");
-//     assert_eq!(request.headers.len(), 4);
-//     assert_eq!(
-//         request.headers.get("host"),
-//         Some(&"example.com".to_string())
-//     );
-//     assert_eq!(
-//         request.headers.get("user-agent"),
-//         Some(&"Mozilla/5.0".to_string())
-//     );
-//     assert_eq!(
-//         request.headers.get("accept"),
-//         Some(&"text/html".to_string())
-//     );
-//     assert_eq!(
-//         request.headers.get("accept-language"),
-//         Some(&"en-US".to_string())
-//     );
-// }
-
-// #[test]
-// fn test_from_stream_empty_request() {
-//     let request_data = "";
-//     let buffer = BufReader::new(request_data.as_bytes());
-
-//     let result = Request::read(buffer);
-//     assert!(result.is_err());
-
-//     match result.unwrap_err() {
-//         RequestError::ConnectionClosed => {} // Expected
-//         other => panic!("Expected ConnectionClosed, got {:?}", other),
-//     }
-// }
-
-// #[test]
-// fn test_from_stream_invalid_request_line() {
-//     let request_data = "INVALID REQUEST LINE\r\n\r\n";
-//     let buffer = BufReader::new(request_data.as_bytes());
-
-//     let result = Request::read(buffer);
-//     assert!(result.is_err());
-// }
-
-// #[test]
-// fn test_from_stream_invalid_method() {
-//     let request_data = "INVALID /test HTTP/1.1\r\nHost: localhost\r\n\r\n";
-//     let buffer = BufReader::new(request_data.as_bytes());
-
-//     let result = Request::read(buffer);
-//     assert!(result.is_err());
-// }
-
-// #[test]
-// fn test_from_stream_invalid_version() {
-//     let request_data = "GET /test HTTP/3.0\r\nHost: localhost\r\n\r\n";
-//     let buffer = BufReader::new(request_data.as_bytes());
-
-//     let result = Request::read(buffer);
-//     assert!(result.is_err());
-// }
-
-// #[test]
-// fn test_from_stream_headers_case_insensitive() {
-//     let request_data = "GET /test HTTP/1.1\r\nHOST: localhost\r\nContent-TYPE: text/plain\r\n\r\n";
-//     let buffer = BufReader::new(request_data.as_bytes());
-
-//     let result = Request::read(buffer);
-//     assert!(result.is_ok());
-
-//     let request = result.unwrap();
-//     assert_eq!(request.headers.get("host"), Some(&"localhost".to_string()));
-//     assert_eq!(
-//         request.headers.get("content-type"),
-//         Some(&"text/plain".to_string())
-//     );
-// }
-
-// #[test]
-// fn test_from_stream_body_with_zero_content_length() {
-//     let request_data = "POST /test HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\n\r\n";
-//     let buffer = BufReader::new(request_data.as_bytes());
-
-//     let result = Request::read(buffer);
-//     assert!(result.is_ok());
-
-//     let request = result.unwrap();
-//     assert_eq!(request.method, HttpMethod::POST);
-//     assert_eq!(request.body, "");
-// }
-
-// #[test]
-// fn test_from_stream_various_methods() {
-//     let methods = [
-//         (HttpMethod::GET, "GET"),
-//         (HttpMethod::POST, "POST"),
-//         (HttpMethod::PUT, "PUT"),
-//         (HttpMethod::DELETE, "DELETE"),
-//         (HttpMethod::HEAD, "HEAD"),
-//         (HttpMethod::OPTIONS, "OPTIONS"),
-//     ];
-
-//     for (expected_method, method_str) in methods {
-//         let request_data = format!("{} /test HTTP/1.1\r\nHost: localhost\r\n\r\n", method_str);
-//         let buffer = BufReader::new(request_data.as_bytes());
-
-//         let result = Request::read(buffer);
-//         assert!(result.is_ok());
-
-//         let request = result.unwrap();
-//         assert_eq!(request.method, expected_method);
-//         assert_eq!(request.path, "/test");
-//         assert_eq!(request.version, Version::HTTP1_1);
-//     }
-// }
-
-// #[test]
-// fn test_connection_closed_during_headers() {
-//     // Test connection closed after partial header reading
-//     let request_data = "GET /test HTTP/1.1\r\nHost: localhost\r\n"; // Missing final \r\n
-//     let buffer = BufReader::new(request_data.as_bytes());
-
-//     let result = Request::read(buffer);
-//     // This should still parse successfully as we have complete headers
-//     assert!(result.is_ok());
-// }
+#[cfg(feature = "json")]
+use schnell::http::body::BodyError;
+use schnell::http::request::{Request, RequestError, RequestLimits};
+use schnell::http::{HttpMethod, Version};
+#[cfg(feature = "forms")]
+use std::collections::HashMap;
+use std::io::BufReader;
+
+#[test]
+fn test_from_stream_get_request() {
+    let request_data =
+        "GET /index.html HTTP/1.1\r\nHost: localhost:8080\r\nUser-Agent: test-client/1.0\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let result = Request::read(&mut buffer);
+    assert!(result.is_ok());
+
+    let request = result.unwrap();
+    assert_eq!(request.method, HttpMethod::GET);
+    assert_eq!(request.path, "/index.html");
+    assert_eq!(request.version, Version::HTTP1_1);
+    assert_eq!(
+        request.headers.get("host"),
+        Some(&"localhost:8080".to_string())
+    );
+    assert_eq!(
+        request.headers.get("user-agent"),
+        Some(&"test-client/1.0".to_string())
+    );
+    assert_eq!(request.body, "");
+}
+
+#[test]
+fn test_from_stream_post_request_with_body() {
+    let body = "{\"name\": \"John Doe\"}";
+    let content_length = body.len();
+    let request_data = format!(
+        "POST /api/users HTTP/1.1\r\nHost: localhost:8080\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        content_length, body
+    );
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let result = Request::read(&mut buffer);
+    assert!(result.is_ok());
+
+    let request = result.unwrap();
+    assert_eq!(request.method, HttpMethod::POST);
+    assert_eq!(request.path, "/api/users");
+    assert_eq!(request.version, Version::HTTP1_1);
+    assert_eq!(
+        request.headers.get("host"),
+        Some(&"localhost:8080".to_string())
+    );
+    assert_eq!(
+        request.headers.get("content-type"),
+        Some(&"application/json".to_string())
+    );
+    assert_eq!(
+        request.headers.get("content-length"),
+        Some(&content_length.to_string())
+    );
+    assert_eq!(request.body, body);
+}
+
+#[test]
+fn test_from_stream_multiple_headers() {
+    let request_data = "GET /test HTTP/1.1\r\nHost: example.com\r\nUser-Agent: Mozilla/5.0\r\nAccept: text/html\r\nAccept-Language: en-US\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let result = Request::read(&mut buffer);
+    assert!(result.is_ok());
+
+    let request = result.unwrap();
+    assert_eq!(request.method, HttpMethod::GET);
+    assert_eq!(request.path, "/test");
+    assert_eq!(request.headers.len(), 4);
+    assert_eq!(
+        request.headers.get("host"),
+        Some(&"example.com".to_string())
+    );
+    assert_eq!(
+        request.headers.get("user-agent"),
+        Some(&"Mozilla/5.0".to_string())
+    );
+    assert_eq!(
+        request.headers.get("accept"),
+        Some(&"text/html".to_string())
+    );
+    assert_eq!(
+        request.headers.get("accept-language"),
+        Some(&"en-US".to_string())
+    );
+}
+
+#[test]
+fn test_from_stream_empty_request() {
+    let request_data = "";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let result = Request::read(&mut buffer);
+    assert!(result.is_err());
+
+    match result.unwrap_err() {
+        RequestError::ConnectionClosed => {} // Expected
+        other => panic!("Expected ConnectionClosed, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_from_stream_invalid_request_line() {
+    let request_data = "INVALID REQUEST LINE\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let result = Request::read(&mut buffer);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_request_target_forms() {
+    for line in ["GET @evil.com/x", "GET evil.com", "GET *"] {
+        let request_data = format!("{} HTTP/1.1\r\nHost: localhost\r\n\r\n", line);
+        let mut buffer = BufReader::new(request_data.as_bytes());
+        let result = Request::read(&mut buffer);
+        assert!(matches!(result, Err(RequestError::ParseError)), "{}", line);
+    }
+
+    let request_data = "OPTIONS * HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+    assert_eq!(Request::read(&mut buffer).unwrap().target(), "*");
+}
+
+#[test]
+fn test_from_stream_invalid_method() {
+    let request_data = "INVALID /test HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let result = Request::read(&mut buffer);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_from_stream_invalid_version() {
+    let request_data = "GET /test HTTP/3.0\r\nHost: localhost\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let result = Request::read(&mut buffer);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_from_stream_headers_case_insensitive() {
+    let request_data = "GET /test HTTP/1.1\r\nHOST: localhost\r\nContent-TYPE: text/plain\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let result = Request::read(&mut buffer);
+    assert!(result.is_ok());
+
+    let request = result.unwrap();
+    assert_eq!(request.headers.get("host"), Some(&"localhost".to_string()));
+    assert_eq!(
+        request.headers.get("content-type"),
+        Some(&"text/plain".to_string())
+    );
+}
+
+#[test]
+fn test_from_stream_body_with_zero_content_length() {
+    let request_data = "POST /test HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let result = Request::read(&mut buffer);
+    assert!(result.is_ok());
+
+    let request = result.unwrap();
+    assert_eq!(request.method, HttpMethod::POST);
+    assert_eq!(request.body, "");
+}
+
+#[test]
+fn test_from_stream_various_methods() {
+    let methods = [
+        (HttpMethod::GET, "GET"),
+        (HttpMethod::POST, "POST"),
+        (HttpMethod::PUT, "PUT"),
+        (HttpMethod::DELETE, "DELETE"),
+        (HttpMethod::HEAD, "HEAD"),
+        (HttpMethod::OPTIONS, "OPTIONS"),
+    ];
+
+    for (expected_method, method_str) in methods {
+        let request_data = format!("{} /test HTTP/1.1\r\nHost: localhost\r\n\r\n", method_str);
+        let mut buffer = BufReader::new(request_data.as_bytes());
+
+        let result = Request::read(&mut buffer);
+        assert!(result.is_ok());
+
+        let request = result.unwrap();
+        assert_eq!(request.method, expected_method);
+        assert_eq!(request.path, "/test");
+        assert_eq!(request.version, Version::HTTP1_1);
+    }
+}
+
+#[test]
+#[cfg(feature = "forms")]
+fn test_nested_form_body() {
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct User {
+        name: String,
+        age: u32,
+        emails: Vec<String>,
+    }
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Signup {
+        user: User,
+        terms: String,
+    }
+
+    let body = "user[name]=Ada+L&user[age]=36&user%5Bemails%5D%5B%5D=a%40x.io\
+                &user[emails][]=b%40x.io&terms=on";
+    let request_data = format!(
+        "POST /signup HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded\r\n\
+         Content-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let mut buffer = BufReader::new(request_data.as_bytes());
+    let request = Request::read(&mut buffer).unwrap();
+
+    assert_eq!(
+        request.form::<Signup>().unwrap(),
+        Signup {
+            user: User {
+                name: "Ada L".to_string(),
+                age: 36,
+                emails: vec!["a@x.io".to_string(), "b@x.io".to_string()],
+            },
+            terms: "on".to_string(),
+        }
+    );
+    #[cfg(feature = "json")]
+    assert!(matches!(
+        request.json::<Signup>(),
+        Err(BodyError::UnsupportedMediaType(_))
+    ));
+}
+
+#[test]
+fn test_cookies() {
+    let request_data = "GET / HTTP/1.1\r\nCookie: theme=dark; session=\"abc=123\"\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+    let request = Request::read(&mut buffer).unwrap();
+
+    assert_eq!(request.cookie("theme"), Some("dark"));
+    assert_eq!(request.cookie("session"), Some("abc=123"));
+    assert_eq!(request.cookie("missing"), None);
+    // No secret key outside a server
+    assert_eq!(request.signed_cookie("theme"), None);
+}
+
+#[test]
+fn test_method_and_version_round_trip() {
+    for method in ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"] {
+        let parsed: HttpMethod = method.parse().unwrap();
+        assert_eq!(parsed.to_string(), method);
+        assert_eq!(parsed.as_str(), method);
+    }
+    assert_eq!("get".parse::<HttpMethod>(), Ok(HttpMethod::GET));
+    assert!("BREW".parse::<HttpMethod>().is_err());
+
+    assert_eq!(Version::HTTP1_0.to_string(), "HTTP/1.0");
+    assert_eq!(Version::HTTP1_1.to_string(), "HTTP/1.1");
+}
+
+#[test]
+fn test_connection_closed_during_headers() {
+    // Test connection closed after partial header reading
+    let request_data = "GET /test HTTP/1.1\r\nHost: localhost\r\n"; // Missing final \r\n
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let result = Request::read(&mut buffer);
+    // This should still parse successfully as we have complete headers
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_pipelined_requests_leave_remainder_buffered() {
+    let request_data = "POST /first HTTP/1.1\r\nContent-Length: 5\r\n\r\nhelloGET /second?page=2 HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let first = Request::read(&mut buffer).unwrap();
+    assert_eq!(first.method, HttpMethod::POST);
+    assert_eq!(first.path, "/first");
+    assert_eq!(first.body, "hello");
+
+    let second = Request::read(&mut buffer).unwrap();
+    assert_eq!(second.method, HttpMethod::GET);
+    assert_eq!(second.path, "/second");
+    assert_eq!(second.query_param("page"), Some("2"));
+
+    match Request::read(&mut buffer) {
+        Err(RequestError::ConnectionClosed) => {}
+        other => panic!("Expected ConnectionClosed, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_pipelined_requests_with_stray_crlf() {
+    let request_data =
+        "POST /first HTTP/1.1\r\nContent-Length: 2\r\n\r\nok\r\nGET /second HTTP/1.1\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    assert_eq!(Request::read(&mut buffer).unwrap().path, "/first");
+    assert_eq!(Request::read(&mut buffer).unwrap().path, "/second");
+}
+
+#[test]
+fn test_keep_alive() {
+    let mut buffer = BufReader::new("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n".as_bytes());
+    assert!(Request::read(&mut buffer).unwrap().keep_alive());
+
+    let mut buffer = BufReader::new("GET / HTTP/1.1\r\nConnection: Close\r\n\r\n".as_bytes());
+    assert!(!Request::read(&mut buffer).unwrap().keep_alive());
+}
+
+#[test]
+fn test_uri_too_long() {
+    let limits = RequestLimits {
+        max_request_line: 1024,
+        max_uri_length: 16,
+        ..RequestLimits::default()
+    };
+
+    let mut buffer = BufReader::new("GET /short HTTP/1.1\r\n\r\n".as_bytes());
+    assert!(Request::read_with_limits(&mut buffer, &limits).is_ok());
+
+    let mut buffer = BufReader::new("GET /a/much/longer/path HTTP/1.1\r\n\r\n".as_bytes());
+    match Request::read_with_limits(&mut buffer, &limits) {
+        Err(RequestError::UriTooLong) => {}
+        other => panic!("Expected UriTooLong, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_request_line_too_long_is_not_buffered() {
+    let limits = RequestLimits {
+        max_request_line: 32,
+        max_uri_length: 1024,
+        ..RequestLimits::default()
+    };
+    let request_data = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(4096));
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    match Request::read_with_limits(&mut buffer, &limits) {
+        Err(RequestError::UriTooLong) => {}
+        other => panic!("Expected UriTooLong, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_default_request_limits() {
+    let request_data = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(16 * 1024));
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    assert!(matches!(
+        Request::read(&mut buffer),
+        Err(RequestError::UriTooLong)
+    ));
+}
+
+#[test]
+fn test_raw_body_preserves_non_utf8_bytes() {
+    let mut request_data = b"POST /upload HTTP/1.1\r\nContent-Length: 4\r\n\r\n".to_vec();
+    request_data.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+    let mut buffer = BufReader::new(request_data.as_slice());
+
+    let request = Request::read(&mut buffer).unwrap();
+    assert_eq!(request.raw_body(), &[0xde, 0xad, 0xbe, 0xef]);
+    assert!(request.body.contains('\u{FFFD}'));
+}
+
+#[test]
+fn test_host_with_ipv6_literal() {
+    let request_data = "GET / HTTP/1.1\r\nHost: [2001:db8::1]:8443\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let request = Request::read(&mut buffer).unwrap();
+    assert_eq!(request.host(), Some("2001:db8::1"));
+    assert_eq!(request.host_port(), Some(8443));
+    assert_eq!(request.client_ip(), None);
+}
+
+#[test]
+fn test_body_larger_than_limit() {
+    let limits = RequestLimits {
+        max_body_size: 4,
+        ..RequestLimits::default()
+    };
+    let request_data = "POST /upload HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    assert!(matches!(
+        Request::read_with_limits(&mut buffer, &limits),
+        Err(RequestError::RequestTooLarge)
+    ));
+}
+
+#[test]
+fn test_raw_headers_preserve_casing_and_order() {
+    let request_data = "GET / HTTP/1.1\r\nX-Legacy-TOKEN: abc\r\nhost: example.com\r\nAccept: a\r\nAccept: b\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let request = Request::read(&mut buffer).unwrap();
+    let names: Vec<&str> = request
+        .raw_headers()
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert_eq!(names, ["X-Legacy-TOKEN", "host", "Accept", "Accept"]);
+    assert_eq!(request.raw_headers()[3].1, "b");
+    assert_eq!(
+        request.headers.get("x-legacy-token"),
+        Some(&"abc".to_string())
+    );
+}
+
+#[test]
+fn test_chunked_body() {
+    let request_data = "POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+                        5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\nX-Checksum: 1\r\n\r\n\
+                        GET /next HTTP/1.1\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+
+    let request = Request::read(&mut buffer).unwrap();
+    assert_eq!(request.body, "hello, world");
+
+    let next = Request::read(&mut buffer).unwrap();
+    assert_eq!(next.path, "/next");
+}
+
+#[test]
+fn test_chunked_body_errors() {
+    let malformed = "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\nhello\r\n0\r\n\r\n";
+    let mut buffer = BufReader::new(malformed.as_bytes());
+    assert!(matches!(
+        Request::read(&mut buffer),
+        Err(RequestError::ParseError)
+    ));
+
+    let not_chunked = "POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n";
+    let mut buffer = BufReader::new(not_chunked.as_bytes());
+    assert!(matches!(
+        Request::read(&mut buffer),
+        Err(RequestError::ParseError)
+    ));
+
+    let limits = RequestLimits {
+        max_body_size: 8,
+        ..RequestLimits::default()
+    };
+    let too_large = "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n5\r\nworld\r\n0\r\n\r\n";
+    let mut buffer = BufReader::new(too_large.as_bytes());
+    assert!(matches!(
+        Request::read_with_limits(&mut buffer, &limits),
+        Err(RequestError::RequestTooLarge)
+    ));
+
+    // Would overflow `body.len() + size` after the first chunk
+    let overflowing = "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+                       1\r\na\r\nffffffffffffffff\r\n";
+    let mut buffer = BufReader::new(overflowing.as_bytes());
+    assert!(matches!(
+        Request::read(&mut buffer),
+        Err(RequestError::RequestTooLarge)
+    ));
+
+    for size in ["+5", "-5", " 5x", ""] {
+        let signed = format!(
+            "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n{}\r\nhello\r\n0\r\n\r\n",
+            size
+        );
+        let mut buffer = BufReader::new(signed.as_bytes());
+        assert!(
+            matches!(Request::read(&mut buffer), Err(RequestError::ParseError)),
+            "chunk size {:?}",
+            size
+        );
+    }
+}
+
+#[test]
+#[cfg(any(feature = "json", feature = "forms"))]
+fn test_bodies_with_byte_order_mark() {
+    let read = |content_type: &str, body: &str| {
+        let request_data = format!(
+            "POST / HTTP/1.1\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n\u{feff}{}",
+            content_type,
+            body.len() + 3,
+            body
+        );
+        let mut buffer = BufReader::new(request_data.as_bytes());
+        Request::read(&mut buffer).unwrap()
+    };
+
+    #[cfg(feature = "json")]
+    {
+        let request = read("application/json", r#"{"done":true}"#);
+        let value: serde_json::Value = request.json().unwrap();
+        assert_eq!(value["done"], true);
+    }
+
+    #[cfg(feature = "forms")]
+    {
+        let request = read("application/x-www-form-urlencoded", "name=ada");
+        let form: HashMap<String, String> = request.form().unwrap();
+        assert_eq!(form["name"], "ada");
+    }
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn test_json_body() {
+    #[derive(serde::Deserialize)]
+    struct Todo {
+        title: String,
+        done: bool,
+    }
+
+    let body = r#"{"title":"write tests","done":false}"#;
+    let request_data = format!(
+        "POST /todos HTTP/1.1\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let mut buffer = BufReader::new(request_data.as_bytes());
+    let todo: Todo = Request::read(&mut buffer).unwrap().json().unwrap();
+    assert_eq!(todo.title, "write tests");
+    assert!(!todo.done);
+
+    let request_data =
+        "POST /todos HTTP/1.1\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\n{}";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+    let err = Request::read(&mut buffer)
+        .unwrap()
+        .json::<serde_json::Value>()
+        .unwrap_err();
+    assert!(matches!(err, BodyError::UnsupportedMediaType(_)));
+    assert_eq!(err.into_response().status_code, 415);
+
+    let request_data =
+        "POST /todos HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 1\r\n\r\n{";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+    let err = Request::read(&mut buffer)
+        .unwrap()
+        .json::<serde_json::Value>()
+        .unwrap_err();
+    assert_eq!(err.into_response().status_code, 400);
+}
+
+#[test]
+fn test_multipart_upload() {
+    let mut body = b"--b0und4ry\r\n\
+        Content-Disposition: form-data; name=\"caption\"\r\n\r\nsunset\r\n\
+        --b0und4ry\r\n\
+        Content-Disposition: form-data; name=\"upload\"; filename=\"sun.bin\"\r\n\
+        Content-Type: application/octet-stream\r\n\r\n"
+        .to_vec();
+    body.extend_from_slice(&[0x00, 0xff, 0xfe, b'\r', b'\n', 0x80]);
+    body.extend_from_slice(b"\r\n--b0und4ry--\r\n");
+
+    let mut request_data = format!(
+        "POST /photos HTTP/1.1\r\nContent-Type: multipart/form-data; boundary=b0und4ry\r\nContent-Length: {}\r\n\r\n",
+        body.len()
+    )
+    .into_bytes();
+    request_data.extend_from_slice(&body);
+
+    let mut buffer = BufReader::new(request_data.as_slice());
+    let form = Request::read(&mut buffer).unwrap().multipart().unwrap();
+
+    assert_eq!(form.field("caption"), Some("sunset"));
+    let upload = form.file("upload").unwrap();
+    assert_eq!(upload.filename.as_deref(), Some("sun.bin"));
+    assert_eq!(
+        upload.content_type.as_deref(),
+        Some("application/octet-stream")
+    );
+    assert_eq!(upload.data, [0x00, 0xff, 0xfe, b'\r', b'\n', 0x80]);
+}
+
+#[test]
+fn test_header_budget() {
+    let limits = RequestLimits {
+        max_header_size: 32,
+        max_headers: 2,
+        ..RequestLimits::default()
+    };
+
+    let mut buffer = BufReader::new("GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\n\r\n".as_bytes());
+    assert!(Request::read_with_limits(&mut buffer, &limits).is_ok());
+
+    let mut buffer = BufReader::new("GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n".as_bytes());
+    assert!(matches!(
+        Request::read_with_limits(&mut buffer, &limits),
+        Err(RequestError::HeadersTooLarge)
+    ));
+
+    let request_data = format!("GET / HTTP/1.1\r\nX-Big: {}\r\n\r\n", "a".repeat(4096));
+    let mut buffer = BufReader::new(request_data.as_bytes());
+    assert!(matches!(
+        Request::read_with_limits(&mut buffer, &limits),
+        Err(RequestError::HeadersTooLarge)
+    ));
+}
+
+#[test]
+fn test_percent_decoded_path_and_query() {
+    let request_data = "GET /files/annual%20report%2F2024.pdf?name=John%20Doe&q=a+b%26c&caf%C3%A9=1 HTTP/1.1\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+    let request = Request::read(&mut buffer).unwrap();
+
+    assert_eq!(request.path, "/files/annual report%2F2024.pdf");
+    assert_eq!(request.query_param("name"), Some("John Doe"));
+    assert_eq!(request.query_param("q"), Some("a b&c"));
+    assert_eq!(request.query_param("café"), Some("1"));
+}
+
+#[test]
+fn test_absolute_form_target() {
+    let request_data = "GET HTTP://Example.com:8080/a/b?c=1 HTTP/1.1\r\nHost: example.com\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+    let request = Request::read(&mut buffer).unwrap();
+    assert_eq!(request.absolute_authority(), Some("Example.com:8080"));
+    assert_eq!(request.path, "/a/b");
+    assert_eq!(request.query_param("c"), Some("1"));
+
+    let request_data = "GET https://example.com?q=1 HTTP/1.1\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+    let request = Request::read(&mut buffer).unwrap();
+    assert_eq!(request.path, "/");
+    assert_eq!(request.query_param("q"), Some("1"));
+
+    let request_data = "GET /http://example.com HTTP/1.1\r\n\r\n";
+    let mut buffer = BufReader::new(request_data.as_bytes());
+    assert_eq!(
+        Request::read(&mut buffer).unwrap().absolute_authority(),
+        None
+    );
+}
@@ -0,0 +1,70 @@
+use schnell::http::HttpResponse;
+use schnell::routing::RouteBuilder;
+use schnell::server::Server;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const WORKERS: usize = 8;
+const CLIENTS: usize = 32;
+const HANDLER_TIME: Duration = Duration::from_millis(100);
+
+#[test]
+fn test_connections_are_served_concurrently() {
+    let mut server = Server::new("127.0.0.1", 0, Some(WORKERS));
+    server.get("/work", |_| {
+        thread::sleep(HANDLER_TIME);
+        HttpResponse::ok().text("done")
+    });
+
+    let server = Arc::new(server);
+    let handle = server.shutdown_handle();
+    let server_thread = thread::spawn({
+        let server = Arc::clone(&server);
+        move || server.listen()
+    });
+    let addr = loop {
+        if let Some(addr) = server.local_addr() {
+            break addr;
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+
+    let started = Instant::now();
+    let clients: Vec<_> = (0..CLIENTS)
+        .map(|_| {
+            thread::spawn(move || {
+                let mut stream = TcpStream::connect(addr).unwrap();
+                stream
+                    .write_all(b"GET /work HTTP/1.1\r\nConnection: close\r\n\r\n")
+                    .unwrap();
+                let mut output = String::new();
+                stream.read_to_string(&mut output).unwrap();
+                output
+            })
+        })
+        .collect();
+    for client in clients {
+        let output = client.join().unwrap();
+        assert!(output.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(output.ends_with("done"));
+    }
+    let elapsed = started.elapsed();
+
+    // Served one at a time this would take CLIENTS * HANDLER_TIME (3.2s); with
+    // WORKERS in parallel it takes CLIENTS / WORKERS rounds (0.4s)
+    let serial = HANDLER_TIME * CLIENTS as u32;
+    assert!(
+        elapsed < serial / 2,
+        "{} requests took {:?}, serial handling would take {:?}",
+        CLIENTS,
+        elapsed,
+        serial
+    );
+    assert_eq!(server.rejected_connections(), 0);
+
+    handle.shutdown();
+    assert!(server_thread.join().unwrap().is_ok());
+}